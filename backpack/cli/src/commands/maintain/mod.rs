@@ -3,70 +3,139 @@ use rootcause::hooks::Hooks;
 use rootcause_backtrace::BacktraceCollector;
 use tracing::instrument;
 
-use github_bot_lib::cli::Args;
-use github_bot_lib::github;
+use github_bot_lib::cli::{Args, OrgArgs};
+use github_bot_lib::github::{self, MaintainOptions, maintain_repo};
+use github_bot_lib::utils::{extend_with_repo_file, normalize_repo};
 
 #[instrument(level = "debug", target = "errors::rootcause", name = "run")]
-pub fn run(repo: String, action: &Option<String>) -> anyhow::Result<()> {
+pub fn run(repo: Option<String>, org: &OrgArgs, options: &MaintainOptions) -> anyhow::Result<()> {
     // Capture backtraces for all errors
     // Install hooks only if they are not already installed (helps tests run multiple times)
     let _ = Hooks::new()
         .report_creation_hook(BacktraceCollector::new_from_env())
         .install();
 
-    // Initialize basic CLI output
-    println!("Starting maintenance for {}", repo);
-    let _cli = Args::parse();
+    let cli = Args::parse();
 
-    let Ok(client) = github::GitHubClient::new() else {
-        return Ok(());
+    let client = github::GitHubClient::new()
+        .map_err(|e| anyhow::anyhow!("{e}"))?
+        .with_print_curl(cli.print_curl);
+
+    let mut summary = github::MaintainSummary::new();
+
+    let Some(org_name) = &org.org else {
+        let Some(path) = &org.repo_file else {
+            // Accept URLs as well as owner/repo
+            let repo = normalize_repo(&repo.unwrap_or_default())?;
+            summary.repos.push(maintain_repo(&client, &repo, options)?);
+            show_summary(&summary, &cli);
+            return Ok(());
+        };
+
+        let mut repos = repo
+            .as_deref()
+            .map(normalize_repo)
+            .transpose()?
+            .into_iter()
+            .collect::<Vec<_>>();
+        extend_with_repo_file(&mut repos, path)?;
+        let failed = maintain_each(&client, &repos, options, &mut summary);
+        if !cli.json {
+            println!(
+                "Maintained {} of {} repositories from {}",
+                repos.len() - failed.len(),
+                repos.len(),
+                path.display()
+            );
+            if !failed.is_empty() {
+                eprintln!("Failed: {}", failed.join(", "));
+            }
+        }
+        show_summary(&summary, &cli);
+        return fail_if_any(&failed, repos.len());
     };
 
-    // Rerunning failed jobs is handled outside the main cleanup loop
-    if *action == Some("rerun".to_string()) {
-        github::rerun_failed_jobs(&client, &repo);
+    // Org-wide package cleanup covers every repo at once
+    if options.all_packages {
+        let mut report = github::MaintainReport::new(org_name);
+        report.containers_deleted =
+            report.absorb(github::delete_org_container_versions(&client, org_name));
+        summary.repos.push(report);
+        show_summary(&summary, &cli);
         return Ok(());
     }
 
-    let is_release_action = *action == Some("release".to_string());
-    if is_release_action {
-        eprintln!(
-            "!!! DANGER: 'release' action selected. This will delete all existing releases and tags."
-        );
-
-        // Blocking confirmation prompt
-        let confirmation = true;
+    let mut repos = github::select_org_repos(&client, org)?;
+    if let Some(path) = &org.repo_file {
+        extend_with_repo_file(&mut repos, path)?;
+    }
+    let failed = maintain_each(&client, &repos, options, &mut summary);
 
-        if !confirmation {
-            println!("Exiting...");
-            return Ok(());
+    if !cli.json {
+        let source = org.repo_file.as_ref().map_or_else(
+            || org_name.clone(),
+            |path| format!("{org_name} and {}", path.display()),
+        );
+        println!(
+            "Maintained {} of {} repositories in {source}",
+            summary.repos.len() - failed.len(),
+            repos.len()
+        );
+        if !failed.is_empty() {
+            eprintln!("Failed: {}", failed.join(", "));
         }
     }
+    show_summary(&summary, &cli);
 
-    // Cleanup Repo (Always executed unless 'rerun')
-    github::delete_failed_workflows(&client, &repo);
-    println!("Deleted failed workflows");
-
-    github::delete_old_container_versions(&client, &repo);
-    println!("Deleted old containers versions");
-
-    // Create new release (only if 'release' action is specified)
-    if is_release_action {
-        println!("Starting full release cleanup");
+    fail_if_any(&failed, repos.len())
+}
 
-        if let Err(e) = github::delete_all_releases(&client, &repo) {
-            eprintln!("Failed to complete full release cleanup for {repo}: {e}");
-        } else {
-            println!("Deleted all releases and tags");
+/// Prints the summary and adds it to the GitHub Actions job summary.
+fn show_summary(summary: &github::MaintainSummary, cli: &Args) {
+    summary.render(cli.json);
+    if let Err(e) = summary.write_step_summary(cli.github_summary) {
+        eprintln!("Could not write the GitHub Actions job summary: {e}");
+    }
+}
 
-            // Then create the new release
-            github::create_release(&client, &repo)?;
+/// Fails the run when any repo failed, after the summary has shown which.
+fn fail_if_any(failed: &[&str], total: usize) -> anyhow::Result<()> {
+    if failed.is_empty() {
+        Ok(())
+    } else {
+        anyhow::bail!(
+            "Maintenance failed for {} of {total} repositories",
+            failed.len()
+        )
+    }
+}
 
-            println!("Created new release");
+/// Maintains each repo in turn, returning the ones that failed.
+///
+/// Keeps going when one repo fails so the rest still get maintained.
+fn maintain_each<'a>(
+    client: &github::GitHubClient,
+    repos: &'a [String],
+    options: &MaintainOptions,
+    summary: &mut github::MaintainSummary,
+) -> Vec<&'a str> {
+    let mut failed = Vec::new();
+    for repo in repos {
+        // Ctrl-C: the repos already done are summarized by the caller
+        if client.is_cancelled() {
+            break;
         }
 
-        println!("Release cleanup complete");
+        match maintain_repo(client, repo, options) {
+            Ok(report) => summary.repos.push(report),
+            Err(e) => {
+                eprintln!("Maintenance failed for {repo}: {e}");
+                failed.push(repo.as_str());
+                let mut report = github::MaintainReport::new(repo);
+                report.errors.push(e.to_string());
+                summary.repos.push(report);
+            }
+        }
     }
-
-    Ok(())
+    failed
 }
@@ -1,14 +1,33 @@
 use clap::Parser;
-use reqwest::blocking::Client;
 use rootcause::hooks::Hooks;
 use rootcause::prelude::*;
 use rootcause_backtrace::BacktraceCollector;
+use std::io::IsTerminal;
+use std::sync::Mutex;
 use tracing::instrument;
 
-use github_bot_lib::{cli::Args, github, utils::get_repo};
+use github_bot_lib::{
+    cli::{Args, OrgArgs},
+    ghk::config,
+    github::{self, MergeOptions},
+    plugins::Plugin,
+    utils::{self, extend_with_repo_file, get_repo, normalize_repo},
+};
 
-#[instrument(level = "debug", target = "errors::rootcause", name = "run")]
-pub fn run(target: Option<String>) -> anyhow::Result<()> {
+#[instrument(
+    level = "debug",
+    target = "errors::rootcause",
+    name = "run",
+    skip(plugins)
+)]
+pub fn run(
+    targets: Vec<String>,
+    org: &OrgArgs,
+    options: MergeOptions,
+    jobs: usize,
+    fail_on_error: bool,
+    plugins: &[Plugin],
+) -> anyhow::Result<()> {
     // Capture backtraces for all errors
     // Install hooks only if they are not already installed (helps tests run multiple times)
     let _ = Hooks::new()
@@ -27,37 +46,338 @@ pub fn run(target: Option<String>) -> anyhow::Result<()> {
             .map_err(|report| anyhow::anyhow!("{report}"))?, // Manually convert Report to anyhow::Error
     };
 
-    // Get target repo
-    let repo = get_repo(target)?;
+    // Asking on a pipe would hang, so refuse up front
+    if options.interactive && !std::io::stdin().is_terminal() {
+        anyhow::bail!("--interactive needs a terminal to ask on");
+    }
+
+    // Get target repos: a whole org, the given list, or the current repo,
+    // plus any from --repo-file
+    let mut repos = if org.org.is_some() {
+        let client = github::GitHubClient::with_token(token.clone())
+            .map_err(|e| anyhow::anyhow!("{e}"))?
+            .with_print_curl(cli.print_curl);
+        github::select_org_repos(&client, org)?
+    } else if targets.is_empty() && org.repo_file.is_none() {
+        vec![get_repo(None)?]
+    } else {
+        targets
+            .iter()
+            .map(|t| normalize_repo(t))
+            .collect::<anyhow::Result<Vec<_>>>()?
+    };
+    if let Some(path) = &org.repo_file {
+        extend_with_repo_file(&mut repos, path)?;
+    }
+
+    if repos.is_empty() {
+        println!("No repositories to process.");
+        return Ok(());
+    }
 
     // Determine repo to merge
     println!("--- Dependabot PR Auto-Processor ---");
-    println!("Target: {repo}");
+    println!("Target: {}", repos.join(", "));
 
-    // 3. Initialize the blocking HTTP client
-    let client = Client::builder().build()?;
+    // 3. Every request goes through the API client, so --token and
+    // GITHUB_API_URL apply to all of them
+    let api = github::GitHubClient::with_token(token.clone())
+        .map_err(|e| anyhow::anyhow!("{e}"))?
+        .with_print_curl(cli.print_curl);
+
+    // 4. Process the repos, a bounded number at a time. A failing repo is
+    // reported and the rest carry on. Questions can't be interleaved, so
+    // --interactive goes one repo at a time.
+    let jobs = if options.interactive { 1 } else { jobs };
+    let queue = Mutex::new(repos.iter());
+    let results = Mutex::new(Vec::new());
+    // Plugins notified from the workers (e.g. Bash ones) need tokio's reactor
+    let runtime = tokio::runtime::Handle::current();
+    std::thread::scope(|scope| {
+        for _ in 0..jobs.clamp(1, repos.len()) {
+            scope.spawn(|| {
+                let _runtime = runtime.enter();
+                loop {
+                    let Some(repo) = queue.lock().unwrap().next() else {
+                        break;
+                    };
+                    let result = merge_repo(&api, repo, options, plugins);
+                    results.lock().unwrap().push((repo, result));
+                }
+            });
+        }
+    });
+
+    let mut results = results.into_inner().unwrap();
+    results.sort_by_key(|(repo, _)| *repo);
+
+    let mut summary = github::MergeSummary::new();
+    let mut failed_repos = Vec::new();
+    for (repo, result) in results {
+        match result {
+            Ok(repo_summary) => summary.records.extend(repo_summary.records),
+            Err(e) => failed_repos.push(format!("{repo}: {e}")),
+        }
+    }
+
+    println!("\n--- Processing Complete ---");
 
-    // 4. List and filter Dependabot PRs
-    let dependabot_prs = github::list_dependabot_prs(&client, &repo, &token)?;
+    // 5. Summarize the run
+    summary.render(cli.quiet || cli.json);
+    for failure in &failed_repos {
+        eprintln!("❌ {failure}");
+    }
+
+    if let Err(e) = summary.write_step_summary(cli.github_summary) {
+        eprintln!("Could not write the GitHub Actions job summary: {e}");
+    }
+
+    // Skipped PRs were left alone on purpose, so only real failures count.
+    // Off by default so existing pipelines keep passing.
+    let failed_prs = summary.failed().len();
+    if fail_on_error && (failed_prs > 0 || !failed_repos.is_empty()) {
+        anyhow::bail!(
+            "{failed_prs} PRs failed to merge, {} repositories could not be processed",
+            failed_repos.len()
+        );
+    }
+
+    Ok(())
+}
+
+/// Deletes a merged PR's head branch. Failing to is only worth a warning.
+fn delete_head_branch(api: &github::GitHubClient, repo: &str, pr: &github::PullRequest) {
+    let Some(branch) = &pr.head_ref else {
+        return;
+    };
+    match github::delete_head_branch(api, repo, branch) {
+        Ok(github::BranchCleanup::Deleted) => println!("🧹 Deleted branch {branch}"),
+        Ok(github::BranchCleanup::AlreadyGone) => {}
+        Ok(github::BranchCleanup::Protected) => {
+            println!("Keeping branch {branch}: it is protected");
+        }
+        Err(e) => eprintln!("⚠️ Could not delete branch {branch}: {e}"),
+    }
+}
+
+/// Shows the PR and its version bump (and diff with `--diff`) and asks whether
+/// to merge it (`--interactive`).
+fn confirm_merge(
+    api: &github::GitHubClient,
+    repo: &str,
+    pr: &github::PullRequest,
+    show_diff: bool,
+) -> anyhow::Result<bool> {
+    if show_diff {
+        match github::pr_diff(api, repo, pr.number) {
+            Ok(diff) => print!(
+                "{}",
+                github::render_diff(&diff, github::MAX_DIFF_LINES, !config::isnocolor())
+            ),
+            // Still worth asking; the title says a lot
+            Err(e) => eprintln!("Could not fetch the diff of #{}: {e}", pr.number),
+        }
+    }
+
+    let bump = github::Bump::from_title(&pr.title)
+        .map_or_else(|| "unknown".to_string(), |b| b.to_string());
+    let question = format!("Merge {repo}#{}: {} ({bump} update)?", pr.number, pr.title);
+    utils::confirm(&question, false)
+}
+
+/// Lists and merges the Dependabot PRs of one repository.
+fn merge_repo(
+    api: &github::GitHubClient,
+    repo: &str,
+    options: MergeOptions,
+    plugins: &[Plugin],
+) -> anyhow::Result<github::MergeSummary> {
+    let mut summary = github::MergeSummary::new();
+
+    // List and filter Dependabot PRs
+    let dependabot_prs = github::list_dependabot_prs(api, repo)?;
 
     if dependabot_prs.is_empty() {
-        println!("\n✅ No open Dependabot PRs found. Exiting.");
-        return Ok(());
+        println!("\n✅ {repo}: no open Dependabot PRs found.");
+        return Ok(summary);
     }
 
     println!(
-        "\nFound {} open Dependabot PRs. Starting processing...",
+        "\n{repo}: found {} open Dependabot PRs. Starting processing...",
         dependabot_prs.len()
     );
 
-    // 5. Process each PR
-    for pr in dependabot_prs {
-        println!("\nProcessing PR #{}: {}", pr.number, pr.title);
-        // We ignore the individual result of process_pr to ensure we try all PRs.
-        let _ = github::process_pr(&client, &repo, &token, &pr);
+    // The repo's own .github/automerge.toml wins over the flags
+    let policy = api.repo_policy(repo)?.unwrap_or_default();
+    if policy != github::RepoPolicy::default() {
+        println!("{repo}: using {}", github::REPO_POLICY_PATH);
     }
 
-    println!("\n--- Processing Complete ---");
+    // Resolve 'auto' once against the repo settings so every PR uses an allowed method
+    let merge_method = github::resolve_merge_method(
+        api,
+        repo,
+        policy.merge_method.unwrap_or(options.merge_method),
+    )?;
+    println!("{repo}: merge method {merge_method}");
 
-    Ok(())
+    // Auto-merging into release branches is riskier, so only target the
+    // default branch unless explicitly asked otherwise.
+    let default_branch = if options.all_bases {
+        None
+    } else {
+        Some(github::default_branch(api, repo)?)
+    };
+
+    // Only fetched with --skip-workflow-changes
+    let mut pr_files = github::PrFileCache::new();
+
+    for pr in dependabot_prs {
+        // Drafts (without --include-draft) and PRs older than --since
+        if let Some(reason) = options.skip_reason(&pr) {
+            println!("\nSkipping PR #{}: {reason}", pr.number);
+            summary.record(
+                repo,
+                pr.number,
+                &pr.title,
+                github::MergeOutcome::Skipped(reason),
+            );
+            continue;
+        }
+
+        if let Some(reason) = policy.skip_reason(&pr) {
+            println!(
+                "\nSkipping PR #{}: {reason} ({})",
+                pr.number,
+                github::REPO_POLICY_PATH
+            );
+            summary.record(
+                repo,
+                pr.number,
+                &pr.title,
+                github::MergeOutcome::Skipped(reason),
+            );
+            continue;
+        }
+
+        if let (Some(default), Some(base)) = (&default_branch, &pr.base_ref)
+            && base != default
+        {
+            println!(
+                "\nSkipping PR #{}: targets '{base}', not '{default}' (use --all-bases)",
+                pr.number
+            );
+            summary.record(
+                repo,
+                pr.number,
+                &pr.title,
+                github::MergeOutcome::Skipped(format!(
+                    "targets '{base}', not the default branch '{default}'"
+                )),
+            );
+            continue;
+        }
+
+        if options.skip_workflow_changes {
+            match pr_files.get(api, repo, pr.number) {
+                Ok(files) if github::touches_workflows(files) => {
+                    println!(
+                        "\nSkipping PR #{}: changes .github/workflows/ (review it manually)",
+                        pr.number
+                    );
+                    summary.record(
+                        repo,
+                        pr.number,
+                        &pr.title,
+                        github::MergeOutcome::Skipped("changes workflow files".into()),
+                    );
+                    continue;
+                }
+                Ok(_) => {}
+                Err(e) => {
+                    // Can't tell what it touches, so don't risk merging it
+                    let reason = format!("could not list changed files: {e}");
+                    summary.record(
+                        repo,
+                        pr.number,
+                        &pr.title,
+                        github::MergeOutcome::Failed(reason),
+                    );
+                    continue;
+                }
+            }
+        }
+
+        println!("\nProcessing {repo}#{}: {}", pr.number, pr.title);
+
+        // Conflicting PRs can't merge; optionally ask Dependabot to sort them out
+        match github::has_conflicts(api, repo, pr.number) {
+            Ok(true) => {
+                let outcome = match options.on_conflict.dependabot_command() {
+                    None => github::MergeOutcome::Skipped(
+                        "has merge conflicts (see --on-conflict)".into(),
+                    ),
+                    Some(command) => {
+                        match github::comment_dependabot(api, repo, pr.number, command) {
+                            Ok(()) => {
+                                println!("💬 Asked Dependabot to {command} #{}", pr.number);
+                                github::MergeOutcome::Skipped(format!(
+                                    "has merge conflicts, commented `@dependabot {command}`"
+                                ))
+                            }
+                            Err(e) => github::MergeOutcome::Failed(format!(
+                                "has merge conflicts, could not comment `@dependabot {command}`: {e}"
+                            )),
+                        }
+                    }
+                };
+                summary.record(repo, pr.number, &pr.title, outcome);
+                continue;
+            }
+            Ok(false) => {}
+            // Let the merge attempt report the real problem
+            Err(e) => println!("Could not check #{} for conflicts: {e}", pr.number),
+        }
+
+        if options.interactive && !confirm_merge(api, repo, &pr, options.show_diff)? {
+            println!("Leaving #{} open", pr.number);
+            summary.record(
+                repo,
+                pr.number,
+                &pr.title,
+                github::MergeOutcome::Skipped("declined".into()),
+            );
+            continue;
+        }
+
+        if options.approve {
+            if let Err(e) = github::approve_pr(api, repo, pr.number) {
+                let reason = format!("could not approve: {e}");
+                summary.record(
+                    repo,
+                    pr.number,
+                    &pr.title,
+                    github::MergeOutcome::Failed(reason),
+                );
+                continue;
+            }
+            println!("👍 Approved #{}", pr.number);
+        }
+
+        // Record each result rather than bailing so we try all PRs.
+        let outcome = match github::process_pr(api, repo, &pr, merge_method, plugins) {
+            Ok(()) => {
+                if options.delete_branch {
+                    delete_head_branch(api, repo, &pr);
+                }
+                github::MergeOutcome::Merged
+            }
+            // Left alone on purpose, like the PRs filtered out above
+            Err(e @ github::MergeError::Vetoed(_)) => github::MergeOutcome::Skipped(e.to_string()),
+            Err(e) => github::MergeOutcome::Failed(e.to_string()),
+        };
+        summary.record(repo, pr.number, &pr.title, outcome);
+    }
+
+    Ok(summary)
 }
@@ -2,7 +2,10 @@ pub mod git;
 pub mod hello;
 pub mod maintain;
 pub mod merge;
+pub mod plugins;
 pub mod prune;
+pub mod ratelimit;
+pub mod version;
 pub mod wip;
 
 #[cfg(test)]
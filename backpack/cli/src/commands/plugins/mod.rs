@@ -0,0 +1,190 @@
+use rootcause::hooks::Hooks;
+use rootcause_backtrace::BacktraceCollector;
+use tracing::instrument;
+
+use anyhow::Context;
+use std::path::{Path, PathBuf};
+
+use github_bot_lib::cli::{PluginSort, PluginsCmd};
+use github_bot_lib::plugins::{self, Plugin};
+
+#[instrument(
+    level = "debug",
+    target = "errors::rootcause",
+    name = "run",
+    skip(plugins)
+)]
+pub fn run(command: &PluginsCmd, plugins: &[Plugin]) -> anyhow::Result<()> {
+    // Capture backtraces for all errors
+    // Install hooks only if they are not already installed (helps tests run multiple times)
+    let _ = Hooks::new()
+        .report_creation_hook(BacktraceCollector::new_from_env())
+        .install();
+
+    match command {
+        PluginsCmd::List {
+            sort,
+            filter,
+            event,
+        } => {
+            let selected = select(plugins, *sort, filter.as_deref(), event.as_deref());
+            if selected.is_empty() {
+                println!("No plugins found.");
+                return Ok(());
+            }
+
+            print!("{}", table(&selected));
+        }
+
+        PluginsCmd::Validate {
+            target: Some(target),
+        } => {
+            let path = resolve(target, plugins)?;
+            validate_dir(&path)?;
+        }
+
+        PluginsCmd::Validate { target: None } => {
+            let dir = plugins::plugins_dir()?;
+            if !dir.is_dir() {
+                println!("No plugins directory at {}", dir.display());
+                return Ok(());
+            }
+
+            // Check every directory, including ones discover_plugins() skipped
+            let mut dirs: Vec<PathBuf> = std::fs::read_dir(&dir)?
+                .filter_map(|entry| entry.ok().map(|e| e.path()))
+                .filter(|path| path.is_dir())
+                .collect();
+            dirs.sort();
+
+            let failed = dirs
+                .iter()
+                .filter(|path| {
+                    validate_dir(path)
+                        .map_err(|e| eprintln!("{}: {e:#}", path.display()))
+                        .is_err()
+                })
+                .count();
+
+            if failed > 0 {
+                anyhow::bail!("{failed} of {} plugins failed validation", dirs.len());
+            }
+            println!("All {} plugins are valid", dirs.len());
+        }
+    }
+
+    Ok(())
+}
+
+/// Loads and validates the plugin in `path` without running it.
+fn validate_dir(path: &Path) -> anyhow::Result<()> {
+    let plugin = Plugin::from_dir(path)?;
+    plugin.validate()?;
+    println!(
+        "{} is valid ({:?} script, {})",
+        plugin.manifest.name,
+        plugin.kind,
+        plugin.script_path.display()
+    );
+    Ok(())
+}
+
+/// Renders plugins as an aligned NAME / DESCRIPTION / AUTHOR / PATH table.
+pub fn table(plugins: &[&Plugin]) -> String {
+    use std::fmt::Write as _;
+
+    let rows: Vec<[String; 4]> = plugins
+        .iter()
+        .map(|p| {
+            [
+                p.manifest.name.clone(),
+                p.manifest.description.clone(),
+                p.manifest.author.clone(),
+                p.path.display().to_string(),
+            ]
+        })
+        .collect();
+
+    let header = ["NAME", "DESCRIPTION", "AUTHOR", "PATH"].map(String::from);
+    let mut widths = header.each_ref().map(|h| h.chars().count());
+    for row in &rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+
+    let mut out = String::new();
+    for row in std::iter::once(&header).chain(&rows) {
+        let line = row
+            .iter()
+            .zip(widths)
+            .map(|(cell, width)| format!("{cell:<width$}"))
+            .collect::<Vec<_>>()
+            .join("  ");
+        let _ = writeln!(out, "{}", line.trim_end());
+    }
+    out
+}
+
+/// Finds the directory for `plugins validate <name|path>`.
+///
+/// Tries a path first, then a directory under the plugins folder, then a loaded
+/// plugin's manifest name.
+fn resolve(target: &str, plugins: &[Plugin]) -> anyhow::Result<PathBuf> {
+    let path = Path::new(target);
+    if path.is_dir() {
+        return Ok(path.to_path_buf());
+    }
+
+    let dir = plugins::plugins_dir()?.join(target);
+    if dir.is_dir() {
+        return Ok(dir);
+    }
+
+    plugins
+        .iter()
+        .find(|p| p.manifest.name == target)
+        .map(|p| p.path.clone())
+        .with_context(|| format!("No plugin named '{target}' in {}", dir.display()))
+}
+
+/// Filters and sorts plugins for `plugins list`.
+///
+/// `filter` matches name, description or author case-insensitively; `event`
+/// keeps plugins that would receive that event.
+pub fn select<'a>(
+    plugins: &'a [Plugin],
+    sort: PluginSort,
+    filter: Option<&str>,
+    event: Option<&str>,
+) -> Vec<&'a Plugin> {
+    let needle = filter.map(str::to_lowercase);
+
+    let mut selected: Vec<&Plugin> = plugins
+        .iter()
+        .filter(|p| {
+            needle.as_deref().is_none_or(|n| {
+                [
+                    &p.manifest.name,
+                    &p.manifest.description,
+                    &p.manifest.author,
+                ]
+                .iter()
+                .any(|field| field.to_lowercase().contains(n))
+            })
+        })
+        .filter(|p| event.is_none_or(|e| p.subscribes_to_name(e)))
+        .collect();
+
+    match sort {
+        PluginSort::Name => selected.sort_by(|a, b| a.manifest.name.cmp(&b.manifest.name)),
+        PluginSort::Author => selected.sort_by(|a, b| {
+            a.manifest
+                .author
+                .cmp(&b.manifest.author)
+                .then_with(|| a.manifest.name.cmp(&b.manifest.name))
+        }),
+    }
+
+    selected
+}
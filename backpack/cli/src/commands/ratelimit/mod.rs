@@ -0,0 +1,27 @@
+use rootcause::hooks::Hooks;
+use rootcause_backtrace::BacktraceCollector;
+use tracing::instrument;
+
+use github_bot_lib::github::GitHubClient;
+
+#[instrument(
+    level = "debug",
+    target = "errors::rootcause",
+    name = "run",
+    skip(token)
+)]
+pub fn run(token: Option<String>, print_curl: bool, json: bool) -> anyhow::Result<()> {
+    // Capture backtraces for all errors
+    // Install hooks only if they are not already installed (helps tests run multiple times)
+    let _ = Hooks::new()
+        .report_creation_hook(BacktraceCollector::new_from_env())
+        .install();
+
+    let client = GitHubClient::from_token_or_env(token)
+        .map_err(|e| anyhow::anyhow!("{e}"))?
+        .with_print_curl(print_curl);
+
+    client.rate_limit()?.render(json);
+
+    Ok(())
+}
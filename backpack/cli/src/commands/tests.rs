@@ -13,10 +13,172 @@ fn test_ping_output() {
     assert!(res.is_ok());
 }
 
+#[test]
+fn test_version_run() {
+    let res = version::run();
+    assert!(res.is_ok());
+    assert!(!version::GIT_SHA.is_empty());
+}
+
 #[test]
 fn test_prune_calls_lib() {
-    // prune delegates to github_bot_lib::git::prune. We call with false to avoid confirmations.
-    let res = prune::run(false);
+    // prune delegates to github_bot_lib::git::prune. A dry run avoids confirmations.
+    let res = prune::run(github_bot_lib::git::PruneOptions {
+        dry_run: true,
+        ..Default::default()
+    });
     // The underlying function may return Err in this environment; accept both.
     assert!(res.is_ok() || res.is_err());
 }
+
+fn mock_plugin(name: &str, author: &str, events: &[&str]) -> github_bot_lib::plugins::Plugin {
+    use github_bot_lib::plugins::{DEFAULT_SCRIPT_TIMEOUT, Manifest, Plugin, ScriptKind};
+
+    Plugin {
+        manifest: Manifest {
+            name: name.to_string(),
+            description: format!("{name} plugin"),
+            homepage: None,
+            repo: None,
+            license: None,
+            author: author.to_string(),
+            events: events.iter().map(|e| (*e).to_string()).collect(),
+            allowed_hosts: vec![],
+        },
+        path: std::path::PathBuf::from(name),
+        script_path: std::path::PathBuf::from(name).join("run.rhai"),
+        kind: ScriptKind::Rhai,
+        timeout: DEFAULT_SCRIPT_TIMEOUT,
+    }
+}
+
+#[test]
+fn test_plugins_select_sort_and_filter() {
+    use github_bot_lib::cli::PluginSort;
+
+    let all = vec![
+        mock_plugin("zeta", "alice", &[]),
+        mock_plugin("alpha", "carol", &["cli-command-execution-end"]),
+        mock_plugin("beta", "bob", &["cli-command-execution-run"]),
+    ];
+
+    let names = |v: Vec<&github_bot_lib::plugins::Plugin>| {
+        v.iter()
+            .map(|p| p.manifest.name.clone())
+            .collect::<Vec<_>>()
+    };
+
+    assert_eq!(
+        names(plugins::select(&all, PluginSort::Name, None, None)),
+        ["alpha", "beta", "zeta"]
+    );
+    assert_eq!(
+        names(plugins::select(&all, PluginSort::Author, None, None)),
+        ["zeta", "beta", "alpha"]
+    );
+    assert_eq!(
+        names(plugins::select(&all, PluginSort::Name, Some("ET"), None)),
+        ["beta", "zeta"]
+    );
+    // Plugins without an `events` list receive everything
+    assert_eq!(
+        names(plugins::select(
+            &all,
+            PluginSort::Name,
+            None,
+            Some("cli-command-execution-run")
+        )),
+        ["beta", "zeta"]
+    );
+}
+
+#[test]
+fn test_plugins_table_aligns_columns() {
+    let a = mock_plugin("a", "alice", &[]);
+    let long = mock_plugin("longer-name", "bob", &[]);
+
+    let table = plugins::table(&[&a, &long]);
+    let lines: Vec<&str> = table.lines().collect();
+
+    assert_eq!(lines.len(), 3);
+    assert!(lines[0].starts_with("NAME         DESCRIPTION"));
+    assert!(lines[1].starts_with("a            a plugin"));
+    assert!(lines[2].starts_with("longer-name  longer-name plugin"));
+    // Every row starts its AUTHOR column at the same offset
+    let author_col = lines[0].find("AUTHOR").unwrap();
+    assert_eq!(&lines[1][author_col..author_col + 5], "alice");
+}
+
+#[test]
+fn test_merge_accepts_several_repos() {
+    use clap::Parser;
+    use github_bot_lib::cli::{Args, Commands};
+
+    let args = Args::try_parse_from([
+        "github-bot",
+        "merge",
+        "--repo",
+        "a/one,a/two",
+        "--repo",
+        "b/three",
+        "--jobs",
+        "2",
+    ])
+    .unwrap();
+
+    let Commands::Merge { repo, jobs, .. } = args.command else {
+        panic!("expected merge");
+    };
+    assert_eq!(repo, ["a/one", "a/two", "b/three"]);
+    assert_eq!(jobs, Some(2));
+}
+
+#[test]
+fn test_maintain_repo_file_replaces_repo() {
+    use clap::Parser;
+    use github_bot_lib::cli::{Args, Commands};
+
+    let args = Args::try_parse_from([
+        "github-bot",
+        "maintain",
+        "rerun",
+        "--repo-file",
+        "repos.txt",
+    ])
+    .unwrap();
+    let Commands::Maintain { repo, org, .. } = args.command else {
+        panic!("expected maintain");
+    };
+    assert_eq!(repo, None);
+    assert_eq!(
+        org.repo_file.as_deref(),
+        Some(std::path::Path::new("repos.txt"))
+    );
+}
+
+#[test]
+fn test_repo_file_combines_with_org() {
+    use clap::Parser;
+    use github_bot_lib::cli::{Args, Commands};
+
+    for command in ["maintain", "merge"] {
+        let args = Args::try_parse_from([
+            "github-bot",
+            command,
+            "--org",
+            "acme",
+            "--repo-file",
+            "repos.txt",
+        ])
+        .unwrap_or_else(|e| panic!("{command}: {e}"));
+        let org = match args.command {
+            Commands::Maintain { org, .. } | Commands::Merge { org, .. } => org,
+            _ => panic!("expected {command}"),
+        };
+        assert_eq!(org.org.as_deref(), Some("acme"));
+        assert_eq!(
+            org.repo_file.as_deref(),
+            Some(std::path::Path::new("repos.txt"))
+        );
+    }
+}
@@ -0,0 +1,30 @@
+use rootcause::hooks::Hooks;
+use rootcause_backtrace::BacktraceCollector;
+use tracing::instrument;
+
+/// Build details embedded by `build.rs`.
+pub const VERSION: &str = env!("CARGO_PKG_VERSION");
+pub const GIT_SHA: &str = env!("GITHUB_BOT_GIT_SHA");
+pub const BUILD_DATE: &str = env!("GITHUB_BOT_BUILD_DATE");
+pub const RUSTC: &str = env!("GITHUB_BOT_RUSTC");
+
+#[instrument(level = "debug", target = "errors::rootcause", name = "run")]
+pub fn run() -> anyhow::Result<()> {
+    // Capture backtraces for all errors
+    // Install hooks only if they are not already installed (helps tests run multiple times)
+    let _ = Hooks::new()
+        .report_creation_hook(BacktraceCollector::new_from_env())
+        .install();
+
+    println!("github-bot {VERSION}");
+    println!("commit:     {GIT_SHA}");
+    println!("build date: {BUILD_DATE}");
+    println!("rustc:      {RUSTC}");
+    println!(
+        "target:     {}-{}",
+        std::env::consts::ARCH,
+        std::env::consts::OS
+    );
+
+    Ok(())
+}
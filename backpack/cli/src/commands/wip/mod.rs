@@ -2,17 +2,21 @@ use rootcause::hooks::Hooks;
 use rootcause_backtrace::BacktraceCollector;
 use tracing::instrument;
 
+use github_bot_lib::ghk::config::Config;
 use github_bot_lib::git;
 
 #[instrument(level = "debug", target = "errors::rootcause", name = "run")]
-pub fn run(no_push: bool, no_diff: bool, rewind: Option<u32>) -> anyhow::Result<()> {
+pub fn run(mut options: git::WipOptions) -> anyhow::Result<()> {
     // Capture backtraces for all errors
     // Install hooks only if they are not already installed (helps tests run multiple times)
     let _ = Hooks::new()
         .report_creation_hook(BacktraceCollector::new_from_env())
         .install();
 
-    let _ = git::wip(no_push, no_diff, rewind);
+    // --message wins over the configured default, which wins over the built-in one
+    if options.message.is_none() {
+        options.message = Config::load().wip_message;
+    }
 
-    Ok(())
+    git::wip(&options)
 }
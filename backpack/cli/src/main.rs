@@ -1,13 +1,14 @@
 mod commands;
 
 use clap::Parser;
-use commands::{git, hello, maintain, merge, prune, wip};
+use commands::{git, hello, maintain, merge, prune, ratelimit, version, wip};
 use std::env;
 
 use github_bot_lib::cli::{Args, Commands};
-use github_bot_lib::log::{
-    self, LogFormat, Printer, ScreenLogger, SimpleLogger, Verbosity, banner,
-};
+use github_bot_lib::ghk::config::Config;
+use github_bot_lib::git::{PruneOptions, WipOptions};
+use github_bot_lib::github;
+use github_bot_lib::log::{self, LogFormat, Printer, ScreenLogger, SimpleLogger, banner};
 use github_bot_lib::plugins::{self, Event};
 use github_bot_lib::{intro, outro};
 
@@ -41,27 +42,39 @@ async fn main() -> anyhow::Result<()> {
     // Parse CLI arguments
     // ────────────────────────────────────────────────────────────────
     //
-    let mut _verbosity = Verbosity::Normal;
-    let mut format = LogFormat::Text;
-
-    for arg in std::env::args().skip(1) {
-        match arg.as_str() {
-            "-q" => _verbosity = Verbosity::Quiet,
-            "-v" => _verbosity = Verbosity::Verbose,
-            "-vv" => _verbosity = Verbosity::Trace,
-            "--json" => format = LogFormat::Json,
-            _ => {}
-        }
+    let cli = Args::parse();
+
+    // The script goes to stdout as-is, so skip the logging and plugins that could print
+    if let Commands::Completions { shell } = cli.command {
+        Args::print_completions(shell);
+        return Ok(());
+    }
+
+    if let Some(user_agent) = &cli.user_agent {
+        github::set_user_agent(user_agent.clone());
     }
+    // Before log::init, which turns on the HTTP trace target for it
+    github::set_trace_http(cli.trace_http);
+
+    log::init_color(cli.nocolor);
+    // Also hides the deletion progress bars of `maintain`
+    github_bot_lib::ghk::config::setquiet(cli.quiet);
+    let format = if cli.json {
+        LogFormat::Json
+    } else {
+        LogFormat::Text
+    };
 
     //
     // ────────────────────────────────────────────────────────────────
     // Initialize our new logger
     // ────────────────────────────────────────────────────────────────
     //
-    log::init();
+    log::init(cli.log_verbosity(), cli.log_file.as_deref())?;
     let formatter = SimpleLogger;
     let logger = Printer::new(formatter, format);
+    // The same output for library code that logs through `log::log()`
+    log::set_logger(Printer::new(SimpleLogger, format));
 
     logger.debug("Logger initialized");
     logger.trace("Tracing enabled");
@@ -76,13 +89,6 @@ async fn main() -> anyhow::Result<()> {
     const PROJECT_DESC: &str = env!("CARGO_PKG_DESCRIPTION");
     banner(PROJECT_NAME, PROJECT_DESC);
 
-    //
-    // ────────────────────────────────────────────────────────────────
-    // Parse CLI using your existing struct
-    // ────────────────────────────────────────────────────────────────
-    //
-    let cli = Args::parse();
-
     logger.trace(&format!(
         "Parsed CLI arguments: token={:?}, command={:?}",
         cli.token, cli.command
@@ -115,67 +121,133 @@ async fn main() -> anyhow::Result<()> {
     // Command dispatch
     // ────────────────────────────────────────────────────────────────
     //
-    match &cli.command {
-        Commands::Maintain { repo, action } => {
+    plugins::broadcast_event(&plugins, Event::CliCommandExecutionInit).await;
+
+    let result = match &cli.command {
+        Commands::Maintain {
+            repo,
+            org,
+            action,
+            all_packages,
+            max_reruns,
+            workflow,
+            all_jobs,
+            yes,
+            update_existing,
+        } => {
             intro!(logger, "Running maintain command");
 
-            plugins::broadcast_event(&plugins, Event::CliCommandExecutionInit).await;
+            // Let Ctrl-C finish the deletions in flight and still print the summary
+            tokio::spawn(github::cancel_on_ctrl_c());
 
             let target_repo = repo.clone();
             let action_arg = action.clone().unwrap_or_else(|| String::from("none"));
+            // Flags win over the `[maintain]` config section
+            let defaults = Config::load().maintain;
 
-            plugins::broadcast_event(
+            let result = plugins::broadcast_gate(
                 &plugins,
                 Event::CliCommandExecutionRun {
                     command: "maintain".into(),
-                    args: vec![target_repo.clone(), action_arg],
+                    args: vec![
+                        target_repo
+                            .clone()
+                            .or_else(|| org.org.clone())
+                            .unwrap_or_default(),
+                        action_arg,
+                    ],
                 },
             )
-            .await;
-
-            maintain::run(target_repo.clone(), action)?;
-
-            plugins::broadcast_event(&plugins, Event::CliCommandExecutionEnd).await;
+            .await
+            .and_then(|()| {
+                maintain::run(
+                    target_repo.clone(),
+                    org,
+                    &github::MaintainOptions {
+                        action: action.clone(),
+                        all_packages: *all_packages,
+                        max_reruns: defaults.max_reruns(*max_reruns),
+                        workflow: workflow.clone(),
+                        all_jobs: *all_jobs,
+                        yes: *yes,
+                        update_existing: *update_existing,
+                    },
+                )
+            });
 
             outro!(logger, "Maintain command complete");
+
+            result
         }
 
-        Commands::Merge { repo } => {
+        Commands::Merge {
+            repo,
+            org,
+            jobs,
+            all_bases,
+            merge_method,
+            skip_workflow_changes,
+            since,
+            include_draft,
+            on_conflict,
+            delete_branch,
+            approve,
+            interactive,
+            show_diff,
+            fail_on_error,
+        } => {
             intro!(logger, "Running merge command");
 
-            plugins::broadcast_event(&plugins, Event::CliCommandExecutionInit).await;
-
-            let target_repo = repo.clone();
+            let target_repos = repo.clone();
+            // Flags win over the `[merge]` config section
+            let defaults = Config::load().merge;
 
-            plugins::broadcast_event(
+            let result = plugins::broadcast_gate(
                 &plugins,
                 Event::CliCommandExecutionRun {
                     command: "merge".into(),
-                    args: target_repo
-                        .as_deref()
-                        .map(|r| vec![r.to_string()])
-                        .unwrap_or_default(),
+                    args: target_repos.clone(),
                 },
             )
-            .await;
-
-            merge::run(target_repo)?;
-
-            plugins::broadcast_event(&plugins, Event::CliCommandExecutionEnd).await;
+            .await
+            .and_then(|()| {
+                merge::run(
+                    target_repos,
+                    org,
+                    github::MergeOptions {
+                        all_bases: *all_bases,
+                        merge_method: defaults.merge_method(*merge_method),
+                        skip_workflow_changes: *skip_workflow_changes,
+                        since: *since,
+                        include_draft: *include_draft,
+                        approve: *approve,
+                        on_conflict: defaults.on_conflict(*on_conflict),
+                        delete_branch: defaults.delete_branch(*delete_branch),
+                        interactive: *interactive,
+                        show_diff: *show_diff,
+                    },
+                    defaults.jobs(*jobs),
+                    *fail_on_error,
+                    &plugins,
+                )
+            });
 
             outro!(logger, "Merge command complete");
+
+            result
         }
 
         Commands::Wip {
             no_push,
             no_diff,
             rewind,
+            message,
+            allow_protected,
+            no_amend,
         } => {
             intro!(logger, "Running wip command");
 
-            plugins::broadcast_event(&plugins, Event::CliCommandExecutionInit).await;
-
-            plugins::broadcast_event(
+            let result = plugins::broadcast_gate(
                 &plugins,
                 Event::CliCommandExecutionRun {
                     command: "wip".into(),
@@ -183,85 +255,143 @@ async fn main() -> anyhow::Result<()> {
                         no_push.to_string(),
                         no_diff.to_string(),
                         format!("{:#?}", rewind),
+                        format!("{:#?}", message),
+                        allow_protected.to_string(),
+                        no_amend.to_string(),
                     ],
                 },
             )
-            .await;
-
-            if let Err(e) = wip::run(*no_push, *no_diff, *rewind) {
-                logger.err(&format!("{e}"));
-            }
-
-            plugins::broadcast_event(&plugins, Event::CliCommandExecutionEnd).await;
+            .await
+            .and_then(|()| {
+                wip::run(WipOptions {
+                    no_push: *no_push,
+                    no_diff: *no_diff,
+                    rewind: *rewind,
+                    message: message.clone(),
+                    allow_protected: *allow_protected,
+                    no_amend: *no_amend,
+                })
+            });
 
             outro!(logger, "Wip command complete");
+
+            result
         }
 
-        Commands::Prune { yes } => {
+        Commands::Prune {
+            yes,
+            remote,
+            protect,
+            merged,
+            https,
+            dry_run,
+        } => {
             intro!(logger, "Running prune command");
 
-            plugins::broadcast_event(&plugins, Event::CliCommandExecutionInit).await;
-
-            plugins::broadcast_event(
+            // Failures are reported but don't fail the whole run
+            if let Err(e) = plugins::broadcast_gate(
                 &plugins,
                 Event::CliCommandExecutionRun {
                     command: "prune".into(),
-                    args: vec![yes.to_string()],
+                    args: vec![
+                        yes.to_string(),
+                        format!("{:#?}", remote),
+                        dry_run.to_string(),
+                        format!("{:#?}", protect),
+                        merged.to_string(),
+                        https.to_string(),
+                    ],
                 },
             )
-            .await;
-
-            if let Err(e) = prune::run(*yes) {
+            .await
+            .and_then(|()| {
+                prune::run(PruneOptions {
+                    confirm: *yes,
+                    remote: remote.clone(),
+                    dry_run: *dry_run,
+                    protect: protect.clone(),
+                    merged: *merged,
+                    https: *https,
+                })
+            }) {
                 logger.err(&format!("{e}"));
             }
 
-            plugins::broadcast_event(&plugins, Event::CliCommandExecutionEnd).await;
-
             outro!(logger, "Prune command complete");
+
+            Ok(())
         }
 
-        Commands::Git { command } => {
+        Commands::Git { command, .. } => {
             intro!(logger, "Running git command");
 
-            plugins::broadcast_event(&plugins, Event::CliCommandExecutionInit).await;
-
-            plugins::broadcast_event(
+            let result = plugins::broadcast_gate(
                 &plugins,
                 Event::CliCommandExecutionRun {
                     command: "git".into(),
                     args: vec![command.to_string()],
                 },
             )
-            .await;
-
-            git::run()?;
-
-            plugins::broadcast_event(&plugins, Event::CliCommandExecutionEnd).await;
+            .await
+            .and_then(|()| git::run());
 
             outro!(logger, "Git command complete");
+
+            result
         }
 
         Commands::Hello => {
             intro!(logger, "Running hello command");
 
-            plugins::broadcast_event(&plugins, Event::CliCommandExecutionInit).await;
-
-            plugins::broadcast_event(
+            let result = plugins::broadcast_gate(
                 &plugins,
                 Event::CliCommandExecutionRun {
                     command: "hello".into(),
                     args: vec![],
                 },
             )
-            .await;
+            .await
+            .and_then(|()| hello::run());
 
-            hello::run()?;
+            outro!(logger, "Hello command complete");
 
-            plugins::broadcast_event(&plugins, Event::CliCommandExecutionEnd).await;
+            result
+        }
 
-            outro!(logger, "Hello command complete");
+        Commands::RateLimit => {
+            intro!(logger, "Running ratelimit command");
+
+            let result = plugins::broadcast_gate(
+                &plugins,
+                Event::CliCommandExecutionRun {
+                    command: "ratelimit".into(),
+                    args: vec![],
+                },
+            )
+            .await
+            .and_then(|()| ratelimit::run(cli.token.clone(), cli.print_curl, cli.json));
+
+            outro!(logger, "Ratelimit command complete");
+
+            result
         }
-    }
+
+        Commands::Version => version::run(),
+
+        Commands::Completions { .. } => unreachable!("handled before plugins load"),
+
+        Commands::Man { dir } => github_bot_lib::cli::man::write_pages(dir).map(|pages| {
+            println!("Wrote {} man pages to {}", pages.len(), dir.display());
+        }),
+
+        Commands::Plugins { command } => commands::plugins::run(command, &plugins),
+    };
+
+    // Always let plugins tear down, even when the command failed
+    let exit_code = i32::from(result.is_err());
+    plugins::broadcast_event(&plugins, Event::CliCommandExecutionEnd { exit_code }).await;
+
+    result?;
 
     logger.ok("All done");
     Ok(())
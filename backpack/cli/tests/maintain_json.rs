@@ -0,0 +1,73 @@
+use serde_json::{Value, json};
+use std::process::Command;
+
+#[test]
+fn maintain_json_output_parses() {
+    let mut server = mockito::Server::new();
+    let _commits = server
+        .mock("GET", "/repos/acme/app/commits")
+        .match_query(mockito::Matcher::Any)
+        .with_status(200)
+        .with_header("content-type", "application/json")
+        .with_body(json!([{"sha": "abc123"}]).to_string())
+        .create();
+    let _runs = server
+        .mock("GET", "/repos/acme/app/actions/runs")
+        .match_query(mockito::Matcher::Any)
+        .with_status(200)
+        .with_header("content-type", "application/json")
+        .with_body(
+            json!({"workflow_runs": [{
+                "id": 1,
+                "name": "CI",
+                "status": "completed",
+                "conclusion": "failure",
+                "html_url": "https://github.com/acme/app/actions/runs/1"
+            }]})
+            .to_string(),
+        )
+        .create();
+    let _delete_run = server
+        .mock("DELETE", "/repos/acme/app/actions/runs/1")
+        .with_status(204)
+        .create();
+    let _versions = server
+        .mock("GET", "/orgs/acme/packages/container/app/versions")
+        .match_query(mockito::Matcher::Any)
+        .with_status(200)
+        .with_header("content-type", "application/json")
+        .with_body("[]")
+        .create();
+
+    // Keep the user's config, plugins and .env files out of it
+    let home = tempfile::tempdir().unwrap();
+    let output = Command::new(env!("CARGO_BIN_EXE_github-bot"))
+        .args(["maintain", "--repo", "acme/app", "--json"])
+        .current_dir(home.path())
+        .env("HOME", home.path())
+        .env("XDG_CONFIG_HOME", home.path())
+        .env("GITHUB_TOKEN", "test-token")
+        .env("GITHUB_API_URL", server.url())
+        .env_remove("RUST_LOG")
+        .output()
+        .unwrap();
+
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    // Every line on stdout is a JSON object: log events and the summary
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let lines: Vec<Value> = stdout
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| serde_json::from_str(line).unwrap_or_else(|e| panic!("{e}: {line:?}")))
+        .collect();
+    let summary = lines
+        .iter()
+        .find(|line| line.get("total").is_some())
+        .expect("a summary line");
+    assert_eq!(summary["total"]["workflows_deleted"], 1);
+}
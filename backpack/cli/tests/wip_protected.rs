@@ -0,0 +1,50 @@
+use std::path::Path;
+use std::process::Command;
+
+fn git(dir: &Path, args: &[&str]) -> String {
+    let output = Command::new("git")
+        .args([
+            "-c",
+            "user.name=Test User",
+            "-c",
+            "user.email=test@example.com",
+        ])
+        .args(args)
+        .current_dir(dir)
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "git {args:?} failed");
+    String::from_utf8(output.stdout).unwrap()
+}
+
+#[test]
+fn wip_fails_on_protected_branch() {
+    // Keep the user's config, plugins and .env files out of it
+    let home = tempfile::tempdir().unwrap();
+    let repo = tempfile::tempdir().unwrap();
+    git(repo.path(), &["init", "--quiet", "-b", "main"]);
+    std::fs::write(repo.path().join("README.md"), "hello\n").unwrap();
+    git(repo.path(), &["add", "README.md"]);
+    git(repo.path(), &["commit", "--quiet", "-m", "initial"]);
+    std::fs::write(repo.path().join("README.md"), "changed\n").unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_github-bot"))
+        .arg("wip")
+        .current_dir(repo.path())
+        .env("HOME", home.path())
+        .env("XDG_CONFIG_HOME", home.path())
+        .env_remove("RUST_LOG")
+        .output()
+        .unwrap();
+
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(!output.status.success(), "{stderr}");
+    assert!(stderr.contains("protected branch 'main'"), "{stderr}");
+
+    // Refused before anything was rewritten
+    assert_eq!(git(repo.path(), &["log", "--format=%s"]), "initial\n");
+    assert_eq!(
+        git(repo.path(), &["status", "--porcelain"]),
+        " M README.md\n"
+    );
+}
@@ -0,0 +1,70 @@
+use crate::cli::Args;
+use anyhow::Context;
+use clap::{Command, CommandFactory};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Write a roff man page for `github-bot` and every visible subcommand into `dir`.
+///
+/// Pages are named like `git log` does it: `github-bot.1`, `github-bot-git.1`,
+/// `github-bot-git-push.1`. Returns the files written.
+pub fn write_pages(dir: &Path) -> anyhow::Result<Vec<PathBuf>> {
+    fs::create_dir_all(dir).with_context(|| format!("Could not create {}", dir.display()))?;
+
+    // Build first so global flags show up on every subcommand's page
+    let mut cmd = Args::command();
+    cmd.build();
+
+    let mut written = Vec::new();
+    write_page(&cmd, "github-bot", dir, &mut written)?;
+    Ok(written)
+}
+
+fn write_page(
+    cmd: &Command,
+    name: &str,
+    dir: &Path,
+    written: &mut Vec<PathBuf>,
+) -> anyhow::Result<()> {
+    let mut page = Vec::new();
+    clap_mangen::Man::new(cmd.clone())
+        .title(name.to_uppercase())
+        .render(&mut page)?;
+
+    let path = dir.join(format!("{name}.1"));
+    fs::write(&path, page).with_context(|| format!("Could not write {}", path.display()))?;
+    written.push(path);
+
+    for sub in cmd
+        .get_subcommands()
+        .filter(|s| !s.is_hide_set() && s.get_name() != "help")
+    {
+        write_page(sub, &format!("{name}-{}", sub.get_name()), dir, written)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn writes_a_page_per_subcommand() {
+        let dir = tempfile::tempdir().unwrap();
+        let written = write_pages(dir.path()).unwrap();
+
+        for page in [
+            "github-bot.1",
+            "github-bot-merge.1",
+            "github-bot-git-push.1",
+        ] {
+            assert!(dir.path().join(page).exists(), "{page} missing");
+        }
+        // Hidden aliases don't get their own page
+        assert!(!dir.path().join("github-bot-git-save.1").exists());
+        assert!(written.iter().all(|p| p.extension().unwrap() == "1"));
+
+        let top = fs::read_to_string(dir.path().join("github-bot.1")).unwrap();
+        assert!(top.contains(".TH GITHUB-BOT"));
+    }
+}
@@ -1,22 +1,22 @@
 use crate::cli::{Args, Commands, GitCommands};
 use crate::ghk::config;
+use crate::log::Verbosity;
 use anyhow::Result;
-use std::env;
 
 pub fn run(cli: Args) -> Result<()> {
-    // Remove GITHUB_TOKEN from environment
-    if env::var("GITHUB_TOKEN").is_ok() {
-        unsafe {
-            env::remove_var("GITHUB_TOKEN");
-        }
-    }
-
     // Set global flags
     config::setquiet(cli.quiet);
-    config::setnocolor(cli.nocolor);
+    // May already be on because stdout is piped (see log::init_color)
+    config::setnocolor(cli.nocolor || config::isnocolor());
+    config::setignoretoken(cli.ignore_token);
+    let dry_run = matches!(cli.command, Commands::Git { dry_run: true, .. });
+    config::setdryrun(dry_run);
+    let verbosity = cli.log_verbosity();
+    let json = cli.json;
 
-    // First, check for quiet to avoid unnecessary calls to isfirstrun()
-    if !cli.quiet && config::isfirstrun() {
+    // First, check for quiet to avoid unnecessary calls to isfirstrun().
+    // The welcome banner would also break --json output.
+    if !cli.quiet && !json && config::isfirstrun() {
         welcome();
 
         // Save config to mark first run complete
@@ -28,31 +28,61 @@ pub fn run(cli: Args) -> Result<()> {
         }
     }
 
+    if dry_run && !json {
+        crate::ghk::util::warn("Dry run: commands that change things are printed, not run");
+    }
+
+    if cli.explain {
+        if let Commands::Git { command, .. } = &cli.command {
+            crate::ghk::explain::run(command);
+        }
+        return Ok(());
+    }
+
     match cli.command {
-        Commands::Git { command } => match command {
-            GitCommands::Init => crate::ghk::commands::init::run(),
+        Commands::Git { command, .. } => match command {
+            GitCommands::Init { branch } => crate::ghk::commands::init::run(&branch),
             GitCommands::Login => crate::ghk::commands::login::run(),
             GitCommands::Logout => crate::ghk::commands::logout::run(),
             GitCommands::User { command } => crate::ghk::commands::user::run(command),
-            GitCommands::Create => crate::ghk::commands::create::run(),
+            GitCommands::Create {
+                no_commit,
+                template,
+            } => crate::ghk::commands::create::run(no_commit, template),
             GitCommands::Fork { repo } => crate::ghk::commands::fork::run(repo),
-            GitCommands::Push | GitCommands::Save => crate::ghk::commands::push::run(),
+            GitCommands::Delete { repo } | GitCommands::Unfork { repo } => {
+                crate::ghk::commands::delete::run(repo)
+            }
+            GitCommands::Push { allow_dirty } | GitCommands::Save { allow_dirty } => {
+                crate::ghk::commands::push::run(allow_dirty)
+            }
             GitCommands::Pull | GitCommands::Sync => crate::ghk::commands::pull::run(),
             GitCommands::Clone { repo, dir } | GitCommands::Download { repo, dir } => {
                 crate::ghk::commands::clone::run(repo, dir)
             }
-            GitCommands::Status => crate::ghk::commands::status::run(),
+            // The global --json flag switches to machine-readable output
+            GitCommands::Status => crate::ghk::commands::status::run(json),
             GitCommands::Setup => crate::ghk::commands::setup::run(),
             GitCommands::Undo => crate::ghk::commands::undo::run(),
-            GitCommands::History { count } | GitCommands::Log { count } => {
-                crate::ghk::commands::history::run(count)
+            GitCommands::Amend { message } => crate::ghk::commands::amend::run(message),
+            GitCommands::Stash { pop } => crate::ghk::commands::stash::run(pop),
+            GitCommands::History { count, author } | GitCommands::Log { count, author } => {
+                // The global -v/--verbose flag switches to the detailed format
+                let verbose = matches!(verbosity, Verbosity::Verbose | Verbosity::Trace);
+                crate::ghk::commands::history::run(count, author, verbose)
             }
-            GitCommands::Open => crate::ghk::commands::open::run(),
+            GitCommands::Open { target } => crate::ghk::commands::open::run(target),
             GitCommands::Diff => crate::ghk::commands::diff::run(),
-            GitCommands::Config { key, value } => crate::ghk::commands::config::run(key, value),
+            GitCommands::Format => crate::ghk::commands::format::run(),
+            GitCommands::Config { key, value, edit } => {
+                crate::ghk::commands::config::run(key, value, edit)
+            }
             GitCommands::Ignore { template } => crate::ghk::commands::ignore::run(template),
             GitCommands::License { kind } => crate::ghk::commands::license::run(kind),
             GitCommands::Branch { name } => crate::ghk::commands::branch::run(name),
+            GitCommands::Pr { title, body } => crate::ghk::commands::pr::run(title, body),
+            GitCommands::Rename { new_name } => crate::ghk::commands::rename::run(new_name),
+            GitCommands::Tag { name, message } => crate::ghk::commands::tag::run(name, message),
             GitCommands::Completions { shell } => {
                 crate::ghk::commands::completions::run(shell);
                 Ok(())
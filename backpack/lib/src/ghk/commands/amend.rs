@@ -0,0 +1,41 @@
+use crate::ghk::{git, util};
+use anyhow::{Result, bail};
+use dialoguer::Confirm;
+
+pub fn run(message: Option<String>) -> Result<()> {
+    if !git::isrepo() {
+        util::err("Not a git repository");
+        bail!("Not a git repository");
+    }
+
+    let history = git::history(1, None, false)?;
+    if history.is_empty() {
+        util::warn("No commits to amend");
+        util::dim("Run 'ghk push' to make your first save");
+        return Ok(());
+    }
+
+    util::info("Last commit:");
+    util::dim(&format!("  {0}", history[0]));
+
+    // Amending a commit that is already on GitHub rewrites shared history
+    if git::hasremote() && !git::hasunpushed() {
+        util::warn("This commit has already been pushed to GitHub!");
+        util::dim("Amending rewrites history - you will need to force push afterwards");
+
+        if !Confirm::new()
+            .with_prompt("Amend it anyway?")
+            .default(false)
+            .interact()?
+        {
+            util::dim("Cancelled");
+            return Ok(());
+        }
+    }
+
+    git::addall()?;
+    git::amend(message.as_deref())?;
+
+    util::ok("Last commit updated");
+    Ok(())
+}
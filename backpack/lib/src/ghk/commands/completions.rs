@@ -1,10 +1,7 @@
 use crate::cli::Args;
 
-use clap::CommandFactory;
-use clap_complete::{Shell, generate};
-use std::io;
+use clap_complete::Shell;
 
 pub fn run(shell: Shell) {
-    let mut cmd = Args::command();
-    generate(shell, &mut cmd, "github-bot", &mut io::stdout());
+    Args::print_completions(shell);
 }
@@ -1,9 +1,17 @@
-use crate::ghk::{config::Config, util};
-use anyhow::Result;
+use crate::ghk::{
+    config::{self, Config},
+    util,
+};
+use anyhow::{Context, Result, bail};
+use std::process::Command;
 
-pub fn run(key: Option<String>, value: Option<String>) -> Result<()> {
+pub fn run(key: Option<String>, value: Option<String>, edit: bool) -> Result<()> {
     let mut cfg = Config::load();
 
+    if edit {
+        return editfile(&cfg);
+    }
+
     match (key, value) {
         // Show all settings
         (None, None) => {
@@ -16,8 +24,20 @@ pub fn run(key: Option<String>, value: Option<String>) -> Result<()> {
                 editor = cfg.editor.as_deref().unwrap_or("(default)")
             ));
             util::dim(&format!(
-                "  org  = {org}",
-                org = cfg.org.as_deref().unwrap_or("")
+                "  org     = {org}",
+                org = cfg.org.as_deref().unwrap_or("(personal account)")
+            ));
+            util::dim(&format!(
+                "  scary_patterns = {patterns}",
+                patterns = cfg.scary_patterns.join(",")
+            ));
+            util::dim(&format!(
+                "  protected_branches = {branches}",
+                branches = cfg.protected_branches.join(",")
+            ));
+            util::dim(&format!(
+                "  wip_message = {message}",
+                message = cfg.wip_message.as_deref().unwrap_or("(default)")
             ));
             println!();
             util::dim(&format!("Config file: {}", Config::path().display()));
@@ -38,3 +58,42 @@ pub fn run(key: Option<String>, value: Option<String>) -> Result<()> {
 
     Ok(())
 }
+
+/// Open the config file in an editor, then check it still parses
+fn editfile(cfg: &Config) -> Result<()> {
+    let path = Config::path();
+    if !path.exists() {
+        cfg.save().context("Could not create the config file")?;
+    }
+
+    let editor = config::pickeditor(
+        cfg.editor.as_deref(),
+        std::env::var("EDITOR").ok().as_deref(),
+        std::env::var("VISUAL").ok().as_deref(),
+        cfg!(windows),
+    );
+
+    // Editors are often configured with arguments, e.g. "code --wait"
+    let mut parts = editor.split_whitespace();
+    let program = parts.next().unwrap_or_default();
+    let status = Command::new(program)
+        .args(parts)
+        .arg(&path)
+        .status()
+        .with_context(|| format!("Could not start editor '{editor}'"))?;
+
+    if !status.success() {
+        util::err(&format!("{editor} exited with an error"));
+        bail!("Editor failed");
+    }
+
+    let text = std::fs::read_to_string(&path).context("Could not read the config file")?;
+    if let Err(e) = toml::from_str::<Config>(&text) {
+        util::warn(&format!("The config is not valid TOML: {}", e.message()));
+        util::dim("Defaults are used until it's fixed; run 'ghk config --edit' again");
+        return Ok(());
+    }
+
+    util::ok("Settings saved");
+    Ok(())
+}
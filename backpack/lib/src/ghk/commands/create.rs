@@ -1,8 +1,8 @@
-use crate::ghk::{config::Config, gh, git, util};
+use crate::ghk::{gh, git, util};
 use anyhow::{Result, bail};
 use dialoguer::{Confirm, Input};
 
-pub fn run() -> Result<()> {
+pub fn run(no_commit: bool, template: Option<String>) -> Result<()> {
     // Check prerequisites
     if !git::isrepo() {
         util::err("Not a git repository");
@@ -25,45 +25,96 @@ pub fn run() -> Result<()> {
         return Ok(());
     }
 
+    // The template's files replace this folder's history, so there must be none to lose
+    if template.is_some() && (hasanycommits() || git::haschanges()?) {
+        util::err("This folder already has files or commits");
+        util::dim("A template repo starts from the template's files, so use an empty folder");
+        util::dim("Or run 'ghk create' without --template to upload this folder instead");
+        bail!("Cannot use --template with existing work");
+    }
+
     // Get repo name
     let defaultname = std::env::current_dir()
         .ok()
         .and_then(|p| p.file_name().map(|n| n.to_string_lossy().to_string()))
         .unwrap_or_else(|| "my-project".to_string());
 
-    let mut name: String = Input::new()
+    let name: String = Input::new()
         .with_prompt("Repository name")
         .default(defaultname)
         .interact_text()?;
-
-    let cfg = Config::load();
-    if let Some(org) = cfg.org.as_deref() {
-        name = format!("{org}/{name}");
-    }
+    // The ruleset, security settings and template download all need owner/repo
+    let name = qualifyname(&name, gh::owner)?;
 
     let private = Confirm::new()
         .with_prompt("Make it private?")
         .default(false)
         .interact()?;
 
+    if let Some(template) = template.as_deref() {
+        util::info(&format!("Creating repository on GitHub from {template}..."));
+        let secured = gh::createrepo(&name, private, Some(template))?;
+
+        let url = format!("https://github.com/{name}.git");
+        match git::checkoutremote(&url) {
+            Ok(branch) => util::ok(&format!("Downloaded the template files on {branch}")),
+            Err(e) => {
+                util::warn(&format!("Could not download the template files: {e}"));
+                util::dim("Run 'ghk pull' in a moment to fetch them");
+            }
+        }
+
+        util::ok(&format!("Repository '{name}' created from {template}!"));
+        securitynote(secured);
+        return Ok(());
+    }
+
     // Make sure there's at least one commit
-    if git::haschanges()? || !hasanycommits() {
+    if no_commit {
+        if !hasanycommits() {
+            util::err("No commits yet - GitHub needs at least one to push");
+            util::dim("Commit your files first, then run 'ghk create' again");
+            bail!("No commits to push");
+        }
+        if git::haschanges()? {
+            util::warn("Uncommitted changes will not be uploaded");
+            util::dim("Run 'ghk push' afterwards to save them");
+        }
+    } else if git::haschanges()? || !hasanycommits() {
         util::info("Creating initial save...");
         git::addall()?;
         let _ = git::commit("chore: Initial commit.");
     }
 
     util::info("Creating repository on GitHub...");
-    gh::createrepo(&name, private)?;
+    let secured = gh::createrepo(&name, private, None)?;
 
     util::ok(&format!("Repository '{name}' created!"));
-    util::dim("Security features have been enabled:");
-    util::ok("  dependency graph");
-    util::ok("  security updates");
+    securitynote(secured);
     util::dim("Run 'ghk push' to save your changes");
     Ok(())
 }
 
+/// `owner/repo` for the new repo: a bare name goes under `owner` (the
+/// configured org, else the logged in user).
+fn qualifyname(name: &str, owner: impl FnOnce() -> Result<String>) -> Result<String> {
+    let name = name.trim();
+    if name.contains('/') {
+        Ok(name.to_string())
+    } else {
+        Ok(format!("{}/{name}", owner()?))
+    }
+}
+
+/// Only claims what was turned on; failures were already warned about
+fn securitynote(enabled: bool) {
+    if enabled {
+        util::dim("Security features have been enabled:");
+        util::ok("  dependency graph");
+        util::ok("  security updates");
+    }
+}
+
 fn hasanycommits() -> bool {
     std::process::Command::new("git")
         .args(["rev-parse", "HEAD"])
@@ -71,3 +122,21 @@ fn hasanycommits() -> bool {
         .map(|out| out.status.success())
         .unwrap_or(false)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn qualifyname_adds_owner_to_bare_names() {
+        assert_eq!(
+            qualifyname("my-project", || Ok("alice".into())).unwrap(),
+            "alice/my-project"
+        );
+        assert_eq!(
+            qualifyname("acme/my-project", || bail!("owner not needed")).unwrap(),
+            "acme/my-project"
+        );
+        assert!(qualifyname("my-project", || bail!("Not logged in")).is_err());
+    }
+}
@@ -0,0 +1,61 @@
+use crate::ghk::{gh, git, util};
+use crate::utils::normalize_repo;
+use anyhow::{Context, Result, bail};
+use dialoguer::Input;
+
+pub fn run(target: Option<String>) -> Result<()> {
+    if !gh::loggedin() {
+        util::err("Not logged in to GitHub");
+        util::dim("Run 'ghk login' first to connect your account");
+        bail!("Not logged in");
+    }
+
+    // Default to the repository this folder pushes to
+    let from_origin = target.is_none();
+    let repo = match target {
+        Some(t) => normalize_repo(&t)?,
+        None if git::isrepo() => {
+            let url = git::remoteurl().context("Could not determine remote URL of current repo")?;
+            normalize_repo(&url)?
+        }
+        None => {
+            util::err("No repository given");
+            util::dim("Run 'ghk delete owner/repo' or run it inside a cloned repo");
+            bail!("No repository given");
+        }
+    };
+
+    // Classic tokens list their scopes; fail early rather than after confirming
+    if gh::tokenscopes().is_some_and(|scopes| !scopes.iter().any(|s| s == "delete_repo")) {
+        util::err("Your GitHub login can't delete repositories");
+        util::dim("Run 'gh auth refresh -h github.com -s delete_repo' to allow it");
+        bail!("Missing delete_repo scope");
+    }
+
+    util::warn(&format!("This permanently deletes {repo} on GitHub"));
+    util::dim("Issues, pull requests, releases and settings are lost. Local files stay.");
+
+    let typed: String = Input::new()
+        .with_prompt(format!("Type '{repo}' to confirm"))
+        .allow_empty(true)
+        .interact_text()?;
+
+    if typed.trim() != repo {
+        util::dim("Name didn't match, nothing deleted");
+        return Ok(());
+    }
+
+    if let Err(e) = gh::deleterepo(&repo) {
+        util::err(&e.to_string());
+        if e.to_string().contains("delete_repo") {
+            util::dim("Run 'gh auth refresh -h github.com -s delete_repo' to allow it");
+        }
+        return Err(e);
+    }
+
+    util::ok(&format!("Deleted {repo} on GitHub"));
+    if from_origin {
+        util::dim("This folder still points at it; run 'git remote remove origin' to unlink");
+    }
+    Ok(())
+}
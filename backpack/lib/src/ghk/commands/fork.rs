@@ -1,4 +1,4 @@
-use crate::ghk::{config::Config, gh, git, util};
+use crate::ghk::{gh, git, util};
 use anyhow::{Context, Result, bail};
 use dialoguer::Input;
 
@@ -21,12 +21,7 @@ pub fn run(target: Option<String>) -> Result<()> {
     };
 
     // Determine destination owner (org or personal account)
-    let cfg = Config::load();
-    let owner = if let Some(org) = cfg.org.as_deref() {
-        org.to_string()
-    } else {
-        gh::whoami()?
-    };
+    let owner = gh::owner()?;
 
     gh::forkrepo(&upstream, &owner)?;
 
@@ -0,0 +1,136 @@
+use crate::ghk::{git, util};
+use anyhow::{Context, Result, bail};
+use std::path::Path;
+use std::process::Command;
+use which::which;
+
+/// A formatter `ghk format` knows how to run.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Formatter {
+    Treefmt,
+    Cargo,
+    Prettier,
+}
+
+impl Formatter {
+    /// Picks the formatter for the project at `root`: treefmt when it is
+    /// configured, else one that matches the project type.
+    #[must_use]
+    pub fn detect(root: &Path) -> Option<Self> {
+        if crate::git::has_treefmt_config(root) {
+            Some(Self::Treefmt)
+        } else if root.join("Cargo.toml").exists() {
+            Some(Self::Cargo)
+        } else if root.join("package.json").exists() {
+            Some(Self::Prettier)
+        } else {
+            None
+        }
+    }
+
+    #[must_use]
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::Treefmt => "treefmt",
+            Self::Cargo => "cargo fmt",
+            Self::Prettier => "prettier",
+        }
+    }
+
+    fn command(self, root: &Path) -> Command {
+        let mut cmd = match self {
+            Self::Treefmt => Command::new("treefmt"),
+            Self::Cargo => {
+                let mut cmd = Command::new("cargo");
+                cmd.args(["fmt", "--all"]);
+                cmd
+            }
+            Self::Prettier => {
+                // Prefer a global install, else the project's own through npx
+                let mut cmd = if which("prettier").is_ok() {
+                    Command::new("prettier")
+                } else {
+                    let mut cmd = Command::new("npx");
+                    cmd.arg("prettier");
+                    cmd
+                };
+                cmd.args(["--write", "."]);
+                cmd
+            }
+        };
+        cmd.current_dir(root);
+        cmd
+    }
+}
+
+pub fn run() -> Result<()> {
+    if !git::isrepo() {
+        util::err("Not a git repository");
+        util::dim("Run 'ghk init' first");
+        bail!("Not a git repository");
+    }
+
+    let repo = git2::Repository::discover(".")?;
+    let Some(root) = repo.workdir() else {
+        bail!("Cannot format a bare repository");
+    };
+
+    let Some(formatter) = Formatter::detect(root) else {
+        util::warn("No formatter found");
+        util::dim("Add a treefmt.toml, or use a Cargo.toml or package.json project");
+        return Ok(());
+    };
+
+    let mut cmd = formatter.command(root);
+    if util::dryrun(&cmd) {
+        return Ok(());
+    }
+
+    let before = unstaged_diff(root)?;
+    util::info(&format!("Formatting with {}...", formatter.name()));
+    let status = cmd
+        .status()
+        .with_context(|| format!("Failed to run {} - is it installed?", formatter.name()))?;
+    if !status.success() {
+        util::err(&format!("{} failed", formatter.name()));
+        bail!("{} failed", formatter.name());
+    }
+
+    util::ok(&format!("Formatted with {}", formatter.name()));
+    if unstaged_diff(root)? == before {
+        util::dim("No files changed");
+    } else {
+        util::dim("Files changed - run 'ghk diff' to review them");
+    }
+    Ok(())
+}
+
+/// Everything not yet staged, to tell whether the formatter touched anything
+fn unstaged_diff(root: &Path) -> Result<Vec<u8>> {
+    let output = Command::new("git")
+        .current_dir(root)
+        .args(["--no-pager", "diff", "--no-color"])
+        .output()
+        .context("Failed to run git diff")?;
+    Ok(output.stdout)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn detect_prefers_treefmt() {
+        let dir = tempfile::tempdir().unwrap();
+        assert_eq!(Formatter::detect(dir.path()), None);
+
+        std::fs::write(dir.path().join("package.json"), "{}").unwrap();
+        assert_eq!(Formatter::detect(dir.path()), Some(Formatter::Prettier));
+
+        std::fs::write(dir.path().join("Cargo.toml"), "[package]").unwrap();
+        assert_eq!(Formatter::detect(dir.path()), Some(Formatter::Cargo));
+
+        std::fs::write(dir.path().join(".treefmt.toml"), "").unwrap();
+        assert_eq!(Formatter::detect(dir.path()), Some(Formatter::Treefmt));
+    }
+}
@@ -1,16 +1,20 @@
 use crate::ghk::{git, util};
 use anyhow::{Result, bail};
 
-pub fn run(count: Option<usize>) -> Result<()> {
+pub fn run(count: Option<usize>, author: Option<String>, verbose: bool) -> Result<()> {
     if !git::isrepo() {
         util::err("Not a git repository");
         bail!("Not a git repository");
     }
 
     let n = count.unwrap_or(10);
-    let commits = git::history(n)?;
+    let commits = git::history(n, author.as_deref(), verbose)?;
 
     if commits.is_empty() {
+        if let Some(author) = &author {
+            util::warn(&format!("No saves by {author}"));
+            return Ok(());
+        }
         util::warn("No history yet");
         util::dim("Make some changes and run 'ghk push'");
         return Ok(());
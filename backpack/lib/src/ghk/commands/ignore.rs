@@ -1,4 +1,4 @@
-use crate::ghk::{git, util};
+use crate::ghk::{gh, git, util};
 use anyhow::{Result, bail};
 use dialoguer::Select;
 use std::fs;
@@ -25,56 +25,87 @@ pub fn run(template: Option<String>) -> Result<()> {
         bail!("Not a git repository");
     }
 
+    let online = gh::isonline();
+
     // Pick template name
     let name = if let Some(t) = template {
         t
     } else {
-        let names: Vec<&str> = TEMPLATES.iter().map(|(n, _)| *n).collect();
+        let names = templatenames(online);
         let idx = Select::new()
             .with_prompt("Choose template")
             .items(&names)
             .default(0)
             .interact()?;
-        names[idx].to_string()
+        names[idx].clone()
     };
 
-    // Find main template content
-    let main = TEMPLATES.iter().find(|(n, _)| *n == name).map(|(_, c)| *c);
+    // Prefer the bundled template, then GitHub's catalog when online
+    let main_content = match TEMPLATES.iter().find(|(n, _)| *n == name) {
+        Some((_, c)) => (*c).to_string(),
+        None if online => match gh::gitignoretemplate(&name) {
+            Ok(c) => c,
+            Err(e) => {
+                util::err(&e.to_string());
+                util::dim("Run 'ghk ignore' to pick from the list");
+                return Ok(());
+            }
+        },
+        None => {
+            util::err(&format!("Unknown template: {name}"));
+            util::dim(&format!("Available offline: {}", localnames().join(", ")));
+            return Ok(());
+        }
+    };
 
-    if let Some(main_content) = main {
-        let path = ".gitignore";
-        let existing = fs::read_to_string(path).unwrap_or_default();
+    let path = ".gitignore";
+    let existing = fs::read_to_string(path).unwrap_or_default();
 
-        // Build final template: main + all base templates
-        let mut combined = format!("# {name}\n{main_content}");
+    // Build final template: main + all base templates
+    let mut combined = format!("# {name}\n{main_content}");
 
-        use std::fmt::Write as _;
-        for (base_name, base_content) in BASE_TEMPLATES {
-            let _ = write!(combined, "\n# base: {base_name}\n{base_content}");
-        }
-
-        // If .gitignore already contains the first line of the main template, skip
-        let first_line = main_content.lines().next().unwrap_or("");
-        if existing.contains(first_line) {
-            util::warn("Already has this template");
-            return Ok(());
-        }
+    use std::fmt::Write as _;
+    for (base_name, base_content) in BASE_TEMPLATES {
+        let _ = write!(combined, "\n# base: {base_name}\n{base_content}");
+    }
 
-        // Append or create new file
-        let new = if existing.trim().is_empty() {
-            combined
-        } else {
-            format!("{}\n{}", existing.trim(), combined)
-        };
+    // If .gitignore already contains the first pattern of the main template, skip
+    let first_line = main_content
+        .lines()
+        .map(str::trim)
+        .find(|l| !l.is_empty() && !l.starts_with('#'))
+        .unwrap_or("");
+    if !first_line.is_empty() && existing.contains(first_line) {
+        util::warn("Already has this template");
+        return Ok(());
+    }
 
-        fs::write(path, new)?;
-        util::ok(&format!(
-            "Added {name} template (with base templates) to .gitignore"
-        ));
+    // Append or create new file
+    let new = if existing.trim().is_empty() {
+        combined
     } else {
-        util::err(&format!("Unknown template: {name}"));
-        util::dim("Available: node, python, rust, go, java, web, macos, windows, linux, ide");
-    }
+        format!("{}\n{}", existing.trim(), combined)
+    };
+
+    fs::write(path, new)?;
+    util::ok(&format!(
+        "Added {name} template (with base templates) to .gitignore"
+    ));
 
     Ok(())
 }
+
+fn localnames() -> Vec<String> {
+    TEMPLATES.iter().map(|(n, _)| (*n).to_string()).collect()
+}
+
+/// Template names to offer, from GitHub when online and the bundled list otherwise
+fn templatenames(online: bool) -> Vec<String> {
+    if online
+        && let Ok(names) = gh::gitignoretemplates()
+        && !names.is_empty()
+    {
+        return names;
+    }
+    localnames()
+}
@@ -1,13 +1,13 @@
 use crate::ghk::{git, util};
 use anyhow::Result;
 
-pub fn run() -> Result<()> {
+pub fn run(branch: &str) -> Result<()> {
     if git::isrepo() {
         util::warn("Already a git repository");
         util::dim("Your project folder is already set up");
     } else {
-        git::init()?;
-        util::ok("Project folder ready");
+        git::init(branch)?;
+        util::ok(&format!("Project folder ready on branch '{branch}'"));
         util::dim("Created .git folder to track your changes");
     }
     Ok(())
@@ -19,7 +19,15 @@ pub fn run(kind: Option<LicenseKind>) -> Result<()> {
     let license = if let Some(ref k) = kind {
         k
     } else {
-        let options = ["MIT", "Apache 2.0", "GPL 3.0", "Unlicense"];
+        let options = [
+            "MIT",
+            "Apache 2.0",
+            "GPL 3.0",
+            "Unlicense",
+            "BSD 3-Clause",
+            "MPL 2.0",
+            "ISC",
+        ];
         let idx = Select::new()
             .with_prompt("Choose license")
             .items(options)
@@ -29,7 +37,10 @@ pub fn run(kind: Option<LicenseKind>) -> Result<()> {
             0 => LicenseKind::Mit,
             1 => LicenseKind::Apache,
             2 => LicenseKind::Gpl,
-            _ => LicenseKind::Unlicense,
+            3 => LicenseKind::Unlicense,
+            4 => LicenseKind::Bsd3,
+            5 => LicenseKind::Mpl2,
+            _ => LicenseKind::Isc,
         }
     };
 
@@ -90,6 +101,48 @@ pub fn run(kind: Option<LicenseKind>) -> Result<()> {
             software to the public domain.\n\n\
             THE SOFTWARE IS PROVIDED \"AS IS\", WITHOUT WARRANTY OF ANY KIND.\n",
         ),
+        LicenseKind::Bsd3 => format!(
+            "BSD 3-Clause License\n\nCopyright (c) {year}, {author}\n\n\
+            Redistribution and use in source and binary forms, with or without\n\
+            modification, are permitted provided that the following conditions are met:\n\n\
+            1. Redistributions of source code must retain the above copyright notice, this\n\
+               list of conditions and the following disclaimer.\n\n\
+            2. Redistributions in binary form must reproduce the above copyright notice,\n\
+               this list of conditions and the following disclaimer in the documentation\n\
+               and/or other materials provided with the distribution.\n\n\
+            3. Neither the name of the copyright holder nor the names of its\n\
+               contributors may be used to endorse or promote products derived from\n\
+               this software without specific prior written permission.\n\n\
+            THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS \"AS IS\"\n\
+            AND ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE\n\
+            IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE\n\
+            DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE\n\
+            FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL\n\
+            DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR\n\
+            SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER\n\
+            CAUSED AND ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT LIABILITY,\n\
+            OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE\n\
+            OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.\n"
+        ),
+        LicenseKind::Mpl2 => format!(
+            "Copyright (c) {year} {author}\n\n\
+            This Source Code Form is subject to the terms of the Mozilla Public\n\
+            License, v. 2.0. If a copy of the MPL was not distributed with this\n\
+            file, You can obtain one at https://mozilla.org/MPL/2.0/.\n"
+        ),
+        LicenseKind::Isc => format!(
+            "ISC License\n\nCopyright (c) {year} {author}\n\n\
+            Permission to use, copy, modify, and/or distribute this software for any\n\
+            purpose with or without fee is hereby granted, provided that the above\n\
+            copyright notice and this permission notice appear in all copies.\n\n\
+            THE SOFTWARE IS PROVIDED \"AS IS\" AND THE AUTHOR DISCLAIMS ALL WARRANTIES\n\
+            WITH REGARD TO THIS SOFTWARE INCLUDING ALL IMPLIED WARRANTIES OF\n\
+            MERCHANTABILITY AND FITNESS. IN NO EVENT SHALL THE AUTHOR BE LIABLE FOR\n\
+            ANY SPECIAL, DIRECT, INDIRECT, OR CONSEQUENTIAL DAMAGES OR ANY DAMAGES\n\
+            WHATSOEVER RESULTING FROM LOSS OF USE, DATA OR PROFITS, WHETHER IN AN\n\
+            ACTION OF CONTRACT, NEGLIGENCE OR OTHER TORTIOUS ACTION, ARISING OUT OF\n\
+            OR IN CONNECTION WITH THE USE OR PERFORMANCE OF THIS SOFTWARE.\n"
+        ),
     };
 
     fs::write("LICENSE", content)?;
@@ -1,10 +1,13 @@
+pub mod amend;
 pub mod branch;
 pub mod clone;
 pub mod completions;
 pub mod config;
 pub mod create;
+pub mod delete;
 pub mod diff;
 pub mod fork;
+pub mod format;
 pub mod history;
 pub mod ignore;
 pub mod init;
@@ -12,9 +15,13 @@ pub mod license;
 pub mod login;
 pub mod logout;
 pub mod open;
+pub mod pr;
 pub mod pull;
 pub mod push;
+pub mod rename;
 pub mod setup;
+pub mod stash;
 pub mod status;
+pub mod tag;
 pub mod undo;
 pub mod user;
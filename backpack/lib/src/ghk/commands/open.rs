@@ -1,7 +1,8 @@
+use crate::cli::OpenTarget;
 use crate::ghk::{gh, git, util};
 use anyhow::{Result, bail};
 
-pub fn run() -> Result<()> {
+pub fn run(target: OpenTarget) -> Result<()> {
     if !git::isrepo() {
         util::err("Not a git repository");
         bail!("Not a git repository");
@@ -14,7 +15,7 @@ pub fn run() -> Result<()> {
     }
 
     util::info("Opening in browser...");
-    gh::openrepo()?;
+    gh::openrepo(target)?;
     util::ok("Opened");
     Ok(())
 }
@@ -0,0 +1,62 @@
+use crate::ghk::{gh, git, util};
+use anyhow::{Result, bail};
+use dialoguer::Input;
+
+pub fn run(title: Option<String>, body: Option<String>) -> Result<()> {
+    // Check prerequisites
+    if !git::isrepo() {
+        util::err("Not a git repository");
+        util::dim("Run 'ghk init' first");
+        bail!("Not a git repository");
+    }
+
+    if !gh::loggedin() {
+        util::err("Not logged in to GitHub");
+        util::dim("Run 'ghk login' first");
+        bail!("Not logged in");
+    }
+
+    if !git::hasremote() {
+        util::err("Not connected to GitHub");
+        util::dim("Run 'ghk create' first to create a repository");
+        bail!("No remote configured");
+    }
+
+    let branch = git::currentbranch()?;
+    if git::defaultbranch().is_some_and(|d| d == branch) {
+        util::err(&format!("You are on {branch}, the default branch"));
+        util::dim("Run 'ghk branch <name>' to start a branch for your changes");
+        bail!("Cannot open a pull request from the default branch");
+    }
+
+    // The branch has to be on GitHub before a PR can point at it
+    if !git::hasremotebranch(&branch) || git::hasunpushed() {
+        git::push()?;
+    }
+
+    let title = match title {
+        Some(t) => t,
+        None => Input::new()
+            .with_prompt("Pull request title")
+            .interact_text()?,
+    };
+
+    let body = match body {
+        Some(b) => b,
+        None => Input::new()
+            .with_prompt("Describe your changes (optional)")
+            .allow_empty(true)
+            .interact_text()?,
+    };
+
+    let Some(url) = gh::createpr(&title, &body)? else {
+        return Ok(());
+    };
+    util::ok("Pull request opened!");
+    util::dim(&format!("  {url}"));
+
+    if let Err(e) = gh::openpr(&url) {
+        util::warn(&e.to_string());
+    }
+    Ok(())
+}
@@ -1,8 +1,8 @@
-use crate::ghk::{gh, git, util};
+use crate::ghk::{config::Config, gh, git, util};
 use anyhow::{Result, bail};
 use dialoguer::Input;
 
-pub fn run() -> Result<()> {
+pub fn run(allow_dirty: bool) -> Result<()> {
     // Check prerequisites
     if !git::isrepo() {
         util::err("Not a git repository");
@@ -30,46 +30,21 @@ pub fn run() -> Result<()> {
         return Ok(());
     }
 
-    // Safety check for scary files (sensitive or large temp files)
+    // Safety check for scary files (sensitive or large temp files),
+    // unless this repo is trusted to commit them
     let files = git::changedfiles()?;
-    let scarypatterns = [
-        ".env",
-        "node_modules",
-        "target",
-        "dist",
-        "venv",
-        ".venv",
-        "env",
-        "vendor",
-        ".DS_Store",
-        "thumbs.db",
-        "__pycache__",
-        "desktop.ini",
-        ".vscode",
-        ".idea",
-        "build",
-        "out",
-        "bin",
-        "obj",
-        "pyc",
-        "pyo",
-        "log",
-        "tmp",
-        "bak",
-        "swp",
-        "exe",
-        "dll",
-        "so",
-        "dylib",
-    ];
+    let skipcheck = allow_dirty || git::allowdirty();
+    let scarypatterns = scarypatterns();
 
     let mut foundscary = Vec::new();
-    for file in &files {
-        let filelow = file.to_lowercase();
-        for pattern in &scarypatterns {
-            if filelow.contains(pattern) {
-                foundscary.push(file.clone());
-                break;
+    if !skipcheck {
+        for file in &files {
+            let filelow = file.to_lowercase();
+            for pattern in &scarypatterns {
+                if filelow.contains(pattern.as_str()) {
+                    foundscary.push(file.clone());
+                    break;
+                }
             }
         }
     }
@@ -126,3 +101,21 @@ pub fn run() -> Result<()> {
     util::ok("Saved to GitHub!");
     Ok(())
 }
+
+/// Patterns from config, minus any listed in the repo's `.ghkignore-warnings`
+fn scarypatterns() -> Vec<String> {
+    let ignored: Vec<String> = std::fs::read_to_string(".ghkignore-warnings")
+        .unwrap_or_default()
+        .lines()
+        .map(str::trim)
+        .filter(|l| !l.is_empty() && !l.starts_with('#'))
+        .map(str::to_lowercase)
+        .collect();
+
+    Config::load()
+        .scary_patterns
+        .into_iter()
+        .map(|p| p.to_lowercase())
+        .filter(|p| !ignored.contains(p))
+        .collect()
+}
@@ -0,0 +1,56 @@
+use crate::ghk::{git, util};
+use anyhow::{Result, bail};
+use dialoguer::Confirm;
+
+pub fn run(new_name: String) -> Result<()> {
+    if !git::isrepo() {
+        util::err("Not a git repository");
+        bail!("Not a git repository");
+    }
+
+    let Ok(old) = git::currentbranch() else {
+        util::err("Not on any branch");
+        bail!("Not on any branch");
+    };
+
+    if old == new_name {
+        util::warn(&format!("Already on {new_name}"));
+        return Ok(());
+    }
+
+    // Renaming the default branch breaks clones and open PRs, so ask first
+    if git::defaultbranch().is_some_and(|d| d == old) {
+        util::warn(&format!("{old} is this repository's default branch"));
+        if !Confirm::new()
+            .with_prompt(format!("Rename it to {new_name} anyway?"))
+            .default(false)
+            .interact()?
+        {
+            util::dim("Cancelled");
+            return Ok(());
+        }
+    }
+
+    if let Err(e) = git::renamebranch(&new_name) {
+        util::err(&e.to_string());
+        return Err(e);
+    }
+    util::ok(&format!("Renamed {old} to {new_name}"));
+
+    if !git::hasremote() {
+        util::dim("No remote yet, so nothing to update on GitHub");
+        return Ok(());
+    }
+
+    let old_on_remote = git::hasremotebranch(&old).then_some(old.as_str());
+    if let Err(e) = git::pushrename(old_on_remote, &new_name) {
+        util::err(&e.to_string());
+        util::dim(&format!("Your local branch is already called {new_name}"));
+        return Err(e);
+    }
+
+    util::ok(&format!(
+        "Updated GitHub: {new_name} now tracks origin/{new_name}"
+    ));
+    Ok(())
+}
@@ -1,5 +1,6 @@
-use anyhow::Result;
+use anyhow::{Context, Result, bail};
 use dialoguer::Confirm;
+use std::path::{Path, PathBuf};
 use std::process::Command;
 use which::which;
 
@@ -59,7 +60,7 @@ pub fn run() -> Result<()> {
         } else {
             util::warn("No SSH key found");
             util::dim("You can still use HTTPS, but SSH is recommended");
-            util::dim("Run 'gh ssh-key add' to add your SSH key");
+            setupsshkey()?;
         }
     }
 
@@ -80,6 +81,102 @@ pub fn run() -> Result<()> {
 
 /* ---------- helpers ---------- */
 
+/// Key types in order of preference, as named by `ssh-keygen`
+const SSHKEYS: [&str; 3] = ["id_ed25519", "id_ecdsa", "id_rsa"];
+
+/// Offer to upload an existing key, or to generate one first
+fn setupsshkey() -> Result<()> {
+    let Some(dir) = dirs::home_dir().map(|h| h.join(".ssh")) else {
+        sshkeyhelp();
+        return Ok(());
+    };
+
+    let pubkey = if let Some(existing) = findsshkey(&dir) {
+        util::info(&format!("Found {}", existing.display()));
+        if !Confirm::new()
+            .with_prompt("Add it to GitHub?")
+            .default(true)
+            .interact()?
+        {
+            sshkeyhelp();
+            return Ok(());
+        }
+        existing
+    } else {
+        if which("ssh-keygen").is_err() {
+            util::warn("ssh-keygen not found");
+            sshkeyhelp();
+            return Ok(());
+        }
+        if !Confirm::new()
+            .with_prompt("Generate an SSH key and add it to GitHub?")
+            .default(true)
+            .interact()?
+        {
+            sshkeyhelp();
+            return Ok(());
+        }
+        match generatesshkey(&dir.join(SSHKEYS[0])) {
+            Ok(pubkey) => pubkey,
+            Err(e) => {
+                util::warn(&e.to_string());
+                sshkeyhelp();
+                return Ok(());
+            }
+        }
+    };
+
+    match gh::addsshkey(&pubkey) {
+        Ok(()) => util::ok("SSH key added to GitHub"),
+        Err(e) => {
+            util::warn(&e.to_string());
+            sshkeyhelp();
+        }
+    }
+    Ok(())
+}
+
+/// The public key to upload from `dir`, preferring the newer key types
+fn findsshkey(dir: &Path) -> Option<PathBuf> {
+    SSHKEYS
+        .iter()
+        .map(|name| dir.join(format!("{name}.pub")))
+        .find(|p| p.is_file())
+}
+
+/// Generate an ed25519 key at `key`, returning the public key's path.
+///
+/// `ssh-keygen` asks for the passphrase itself.
+fn generatesshkey(key: &Path) -> Result<PathBuf> {
+    // Never overwrite a private key that lost its .pub
+    if key.exists() {
+        bail!("{} exists without a public key", key.display());
+    }
+    if let Some(dir) = key.parent() {
+        std::fs::create_dir_all(dir)?;
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            std::fs::set_permissions(dir, std::fs::Permissions::from_mode(0o700))?;
+        }
+    }
+
+    let mut cmd = Command::new("ssh-keygen");
+    cmd.args(["-t", "ed25519", "-f"]).arg(key);
+    if !util::dryrun(&cmd) {
+        let status = cmd.status().context("Failed to run ssh-keygen")?;
+        if !status.success() {
+            bail!("ssh-keygen failed");
+        }
+        util::ok(&format!("Created {}", key.display()));
+    }
+    Ok(key.with_extension("pub"))
+}
+
+fn sshkeyhelp() {
+    util::dim("Run 'gh ssh-key add ~/.ssh/id_ed25519.pub' to add your SSH key");
+}
+
 fn installtool(tool: &str) -> Result<()> {
     if !Confirm::new()
         .with_prompt(format!("Install {tool} now?"))
@@ -210,3 +307,33 @@ fn runpkg(cmd: &str, args: &[&str]) -> Result<()> {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn findsshkey_prefers_ed25519() {
+        let dir = tempfile::tempdir().unwrap();
+        assert_eq!(findsshkey(dir.path()), None);
+
+        std::fs::write(dir.path().join("id_rsa.pub"), "ssh-rsa AAAA").unwrap();
+        assert_eq!(findsshkey(dir.path()), Some(dir.path().join("id_rsa.pub")));
+
+        std::fs::write(dir.path().join("id_ed25519.pub"), "ssh-ed25519 AAAA").unwrap();
+        assert_eq!(
+            findsshkey(dir.path()),
+            Some(dir.path().join("id_ed25519.pub"))
+        );
+    }
+
+    #[test]
+    fn generatesshkey_keeps_existing_private_key() {
+        let dir = tempfile::tempdir().unwrap();
+        let key = dir.path().join("id_ed25519");
+        std::fs::write(&key, "private").unwrap();
+
+        assert!(generatesshkey(&key).is_err());
+        assert_eq!(std::fs::read_to_string(&key).unwrap(), "private");
+    }
+}
@@ -0,0 +1,34 @@
+use crate::ghk::{git, util};
+use anyhow::{Result, bail};
+
+pub fn run(pop: bool) -> Result<()> {
+    if !git::isrepo() {
+        util::err("Not a git repository");
+        util::dim("Run 'ghk init' first");
+        bail!("Not a git repository");
+    }
+
+    if pop {
+        if let Err(e) = git::stashpop() {
+            util::err(&e.to_string());
+            return Err(e);
+        }
+        util::ok("Restored your stashed changes");
+        return Ok(());
+    }
+
+    if !git::haschanges()? {
+        util::warn("No local changes to save");
+        util::dim("Nothing to stash");
+        return Ok(());
+    }
+
+    if git::stash()? {
+        util::ok("Changes set aside");
+        util::dim("Run 'ghk stash --pop' to bring them back");
+    } else {
+        util::warn("No local changes to save");
+    }
+
+    Ok(())
+}
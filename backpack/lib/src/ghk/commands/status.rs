@@ -1,7 +1,58 @@
 use crate::ghk::{gh, git, util};
 use anyhow::Result;
+use serde::Serialize;
+
+/// Everything `ghk status` reports, as printed by `--json`
+#[derive(Debug, Default, Serialize)]
+struct Status {
+    is_repo: bool,
+    branch: Option<String>,
+    remote: Option<String>,
+    /// Commits not yet pushed, `null` without an upstream
+    ahead: Option<usize>,
+    /// Commits not yet pulled (as of the last fetch), `null` without an upstream
+    behind: Option<usize>,
+    has_changes: bool,
+    changed_files: Vec<String>,
+    logged_in: bool,
+    user: Option<String>,
+}
+
+impl Status {
+    fn gather() -> Self {
+        let mut status = Self {
+            is_repo: git::isrepo(),
+            logged_in: gh::loggedin(),
+            ..Self::default()
+        };
+
+        if status.is_repo {
+            status.branch = git::currentbranch().ok();
+            status.remote = git::remoteurl().ok();
+            if let Ok((ahead, behind)) = git::aheadbehind() {
+                status.ahead = Some(ahead);
+                status.behind = Some(behind);
+            }
+            status.has_changes = git::haschanges().unwrap_or(false);
+            if status.has_changes {
+                status.changed_files = git::changedfiles().unwrap_or_default();
+            }
+        }
+
+        if status.logged_in {
+            status.user = gh::whoami().ok();
+        }
+
+        status
+    }
+}
+
+pub fn run(json: bool) -> Result<()> {
+    if json {
+        println!("{}", serde_json::to_string_pretty(&Status::gather())?);
+        return Ok(());
+    }
 
-pub fn run() -> Result<()> {
     println!();
 
     // Git status
@@ -18,6 +69,22 @@ pub fn run() -> Result<()> {
             if let Ok(url) = git::remoteurl() {
                 util::dim(&format!("Remote: {url}"));
             }
+
+            // Counts are as of the last fetch; status itself stays offline
+            match git::aheadbehind() {
+                Ok((0, 0)) => util::dim("Up to date with GitHub"),
+                Ok((ahead, behind)) => {
+                    let s = if ahead == 1 { "" } else { "s" };
+                    util::info(&format!("{ahead} commit{s} ahead, {behind} behind"));
+                    if ahead > 0 {
+                        util::dim("Run 'ghk push' to upload them");
+                    }
+                    if behind > 0 {
+                        util::dim("Run 'ghk pull' to download the rest");
+                    }
+                }
+                Err(_) => util::dim("Branch not on GitHub yet (run 'ghk push')"),
+            }
         } else {
             util::dim("Remote: Not connected (run 'ghk create')");
         }
@@ -0,0 +1,50 @@
+use crate::ghk::{git, util};
+use anyhow::{Result, bail};
+use dialoguer::Input;
+
+pub fn run(name: Option<String>, message: Option<String>) -> Result<()> {
+    if !git::isrepo() {
+        util::err("Not a git repository");
+        util::dim("Run 'ghk init' first");
+        bail!("Not a git repository");
+    }
+
+    let Some(name) = name else {
+        // List tags
+        let tags = git::tags()?;
+        if tags.is_empty() {
+            util::warn("No tags yet");
+            util::dim("Run 'ghk tag v0.1.0' to create one");
+            return Ok(());
+        }
+
+        println!();
+        util::info("Tags:");
+        for tag in &tags {
+            util::dim(&format!("  {tag}"));
+        }
+        println!();
+        return Ok(());
+    };
+
+    if !git::hasremote() {
+        util::err("Not connected to GitHub");
+        util::dim("Run 'ghk create' first");
+        bail!("No remote configured");
+    }
+
+    let msg = match message {
+        Some(m) => m,
+        None => Input::new()
+            .with_prompt("Tag message")
+            .default(format!("Release {name}"))
+            .interact_text()?,
+    };
+
+    util::info(&format!("Creating tag {name}..."));
+    git::tag(&name, &msg)?;
+    git::pushtag(&name)?;
+
+    util::ok(&format!("Tag {name} pushed to GitHub"));
+    Ok(())
+}
@@ -9,7 +9,7 @@ pub fn run() -> Result<()> {
     }
 
     // Show what will be undone
-    let history = git::history(1)?;
+    let history = git::history(1, None, false)?;
     if history.is_empty() {
         util::warn("No commits to undo");
         return Ok(());
@@ -1,6 +1,8 @@
 use crate::cli::UserCmd;
-use crate::ghk::{gh, util};
+use crate::ghk::{config::Config, gh, util};
 use anyhow::Result;
+use dialoguer::Input;
+use std::io::IsTerminal;
 
 pub fn run(cmd: UserCmd) -> Result<()> {
     match cmd {
@@ -23,8 +25,35 @@ pub fn run(cmd: UserCmd) -> Result<()> {
 
             util::info(&format!("Switching to {name}..."));
             gh::switchuser(&name)?;
-            util::ok(&format!("Now using {name}"));
+
+            // The switch may have ended in a login as someone else
+            let user = gh::whoami().unwrap_or(name);
+
+            let mut cfg = Config::load();
+            let org = if std::io::stdin().is_terminal() {
+                Some(askorg(cfg.org.as_deref())?)
+            } else {
+                None
+            };
+            cfg.switchuser(&user, org.as_deref());
+            cfg.save()?;
+
+            util::ok(&format!("Now using {user}"));
+            match cfg.org.as_deref() {
+                Some(org) => util::dim(&format!("New repos and forks go to {org}")),
+                None => util::dim("New repos and forks go to your own account"),
+            }
         }
     }
     Ok(())
 }
+
+/// Ask which org new repos go to, starting from the current one
+fn askorg(current: Option<&str>) -> Result<String> {
+    // Initial text rather than a default, so it can be erased to clear the org
+    Ok(Input::<String>::new()
+        .with_prompt("Organization for new repos (empty for your own account)")
+        .with_initial_text(current.unwrap_or_default())
+        .allow_empty(true)
+        .interact_text()?)
+}
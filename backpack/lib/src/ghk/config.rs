@@ -1,15 +1,139 @@
+use crate::cli::{MergeMethod, OnConflict};
 use anyhow::Result;
 use serde::{Deserialize, Serialize};
 use std::fs;
 use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+use std::time::Duration;
 
-#[derive(Default, Serialize, Deserialize)]
+/// How many times `Config::save` tries to move the new file into place
+const SAVE_ATTEMPTS: u32 = 3;
+
+/// Files matching these are flagged by `ghk push` before saving
+pub const DEFAULT_SCARY_PATTERNS: &[&str] = &[
+    ".env",
+    "node_modules",
+    "target",
+    "dist",
+    "venv",
+    ".venv",
+    "env",
+    "vendor",
+    ".DS_Store",
+    "thumbs.db",
+    "__pycache__",
+    "desktop.ini",
+    ".vscode",
+    ".idea",
+    "build",
+    "out",
+    "bin",
+    "obj",
+    "pyc",
+    "pyo",
+    "log",
+    "tmp",
+    "bak",
+    "swp",
+    "exe",
+    "dll",
+    "so",
+    "dylib",
+];
+
+#[derive(Serialize, Deserialize)]
+#[serde(default)]
 pub struct Config {
+    /// Account from the last `ghk user switch`. Only shown to the user; gh's
+    /// login decides who commands run as.
     pub lastuser: Option<String>,
     pub quiet: bool,
     pub nocolor: bool,
     pub editor: Option<String>,
     pub org: Option<String>,
+    pub scary_patterns: Vec<String>,
+    /// Commit message for `github-bot wip`
+    pub wip_message: Option<String>,
+    /// Branch patterns `github-bot prune` never deletes
+    pub protected_branches: Vec<String>,
+    /// Defaults for `github-bot merge`
+    pub merge: MergeDefaults,
+    /// Defaults for `github-bot maintain`
+    pub maintain: MaintainDefaults,
+}
+
+/// The `[merge]` section: defaults for `github-bot merge` flags.
+///
+/// A flag given on the command line wins, then this, then the built-in default.
+#[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq, Eq)]
+#[serde(default)]
+pub struct MergeDefaults {
+    pub merge_method: Option<MergeMethod>,
+    pub jobs: Option<usize>,
+    pub on_conflict: Option<OnConflict>,
+    pub delete_branch: Option<bool>,
+}
+
+impl MergeDefaults {
+    #[must_use]
+    pub fn merge_method(&self, flag: Option<MergeMethod>) -> MergeMethod {
+        flag.or(self.merge_method).unwrap_or(MergeMethod::Squash)
+    }
+
+    #[must_use]
+    pub fn jobs(&self, flag: Option<usize>) -> usize {
+        flag.or(self.jobs)
+            .unwrap_or(crate::github::DEFAULT_MERGE_JOBS)
+    }
+
+    #[must_use]
+    pub fn on_conflict(&self, flag: Option<OnConflict>) -> OnConflict {
+        flag.or(self.on_conflict).unwrap_or_default()
+    }
+
+    #[must_use]
+    pub fn delete_branch(&self, flag: Option<bool>) -> bool {
+        flag.or(self.delete_branch).unwrap_or(true)
+    }
+}
+
+/// The `[maintain]` section: defaults for `github-bot maintain` flags.
+#[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq, Eq)]
+#[serde(default)]
+pub struct MaintainDefaults {
+    pub max_reruns: Option<usize>,
+}
+
+impl MaintainDefaults {
+    #[must_use]
+    pub fn max_reruns(&self, flag: Option<usize>) -> usize {
+        flag.or(self.max_reruns)
+            .unwrap_or(crate::github::DEFAULT_MAX_RERUNS)
+    }
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            lastuser: None,
+            quiet: false,
+            nocolor: false,
+            editor: None,
+            org: None,
+            scary_patterns: DEFAULT_SCARY_PATTERNS
+                .iter()
+                .map(std::string::ToString::to_string)
+                .collect(),
+            wip_message: None,
+            protected_branches: crate::git::DEFAULT_PROTECTED_BRANCHES
+                .iter()
+                .map(std::string::ToString::to_string)
+                .collect(),
+            merge: MergeDefaults::default(),
+            maintain: MaintainDefaults::default(),
+        }
+    }
 }
 
 impl Config {
@@ -34,14 +158,44 @@ impl Config {
         }
     }
 
+    /// Write the config atomically.
+    ///
+    /// The content goes to a sibling temp file first and is then renamed into
+    /// place, so an interrupted save never leaves a half-written config behind.
+    /// The rename is retried briefly in case another tool is holding the file.
     pub fn save(&self) -> Result<()> {
         let path = Self::path();
         if let Some(parent) = path.parent() {
             fs::create_dir_all(parent)?;
         }
         let content = toml::to_string_pretty(self)?;
-        fs::write(path, content)?;
-        Ok(())
+
+        let tmp = path.with_extension("toml.tmp");
+        fs::write(&tmp, content)?;
+
+        let mut attempt = 1;
+        loop {
+            match fs::rename(&tmp, &path) {
+                Ok(()) => return Ok(()),
+                Err(_) if attempt < SAVE_ATTEMPTS => {
+                    thread::sleep(Duration::from_millis(100 * u64::from(attempt)));
+                    attempt += 1;
+                }
+                Err(e) => {
+                    let _ = fs::remove_file(&tmp);
+                    return Err(e.into());
+                }
+            }
+        }
+    }
+
+    /// Record a `ghk user switch`: the account now in use and, when given, the
+    /// org new repos go to (empty means the personal account).
+    pub fn switchuser(&mut self, user: &str, org: Option<&str>) {
+        self.lastuser = Some(user.to_string());
+        if let Some(org) = org {
+            self.org = Some(org.trim()).filter(|o| !o.is_empty()).map(String::from);
+        }
     }
 
     #[must_use]
@@ -52,6 +206,9 @@ impl Config {
             "editor" => self.editor.clone(),
             "org" => self.org.clone(),
             "lastuser" => self.lastuser.clone(),
+            "scary_patterns" => Some(self.scary_patterns.join(",")),
+            "wip_message" => self.wip_message.clone(),
+            "protected_branches" => Some(self.protected_branches.join(",")),
             _ => None,
         }
     }
@@ -62,36 +219,118 @@ impl Config {
             "nocolor" => self.nocolor = value == "true" || value == "1",
             "editor" => self.editor = Some(value.to_string()),
             "org" => self.org = Some(value.to_string()),
+            "scary_patterns" => editlist(&mut self.scary_patterns, value, DEFAULT_SCARY_PATTERNS),
+            "protected_branches" => editlist(
+                &mut self.protected_branches,
+                value,
+                crate::git::DEFAULT_PROTECTED_BRANCHES,
+            ),
+            // An empty value goes back to the built-in message
+            "wip_message" => {
+                self.wip_message = Some(value).filter(|v| !v.is_empty()).map(String::from)
+            }
             _ => anyhow::bail!("Unknown setting: {key}"),
         }
         self.save()
     }
 }
 
+/// Update a list setting (scary-file or protected-branch patterns).
+///
+/// `a,b` replaces the list, `+a` adds to it, `-a` removes from it and
+/// `default` restores the built-in list.
+fn editlist(list: &mut Vec<String>, value: &str, default: &[&str]) {
+    let value = value.trim();
+    if value == "default" {
+        *list = default
+            .iter()
+            .map(std::string::ToString::to_string)
+            .collect();
+    } else if let Some(add) = value.strip_prefix('+') {
+        for p in splitlist(add) {
+            if !list.contains(&p) {
+                list.push(p);
+            }
+        }
+    } else if let Some(remove) = value.strip_prefix('-') {
+        let remove = splitlist(remove);
+        list.retain(|p| !remove.contains(p));
+    } else {
+        *list = splitlist(value);
+    }
+}
+
+/// Editor command line for `ghk config --edit`.
+///
+/// The configured editor wins, then `$EDITOR`, then `$VISUAL`, then `notepad`
+/// on Windows or `vi` elsewhere.
+#[must_use]
+pub fn pickeditor(
+    configured: Option<&str>,
+    editor: Option<&str>,
+    visual: Option<&str>,
+    windows: bool,
+) -> String {
+    [configured, editor, visual]
+        .into_iter()
+        .flatten()
+        .map(str::trim)
+        .find(|e| !e.is_empty())
+        .map_or_else(
+            || if windows { "notepad" } else { "vi" }.to_string(),
+            str::to_string,
+        )
+}
+
+fn splitlist(value: &str) -> Vec<String> {
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .map(std::string::ToString::to_string)
+        .collect()
+}
+
 // global flags
-static mut QUIET: bool = false;
-static mut NOCOLOR: bool = false;
+static QUIET: AtomicBool = AtomicBool::new(false);
+static NOCOLOR: AtomicBool = AtomicBool::new(false);
+static IGNORETOKEN: AtomicBool = AtomicBool::new(false);
+static DRYRUN: AtomicBool = AtomicBool::new(false);
 
 pub fn setquiet(q: bool) {
-    unsafe {
-        QUIET = q;
-    }
+    QUIET.store(q, Ordering::Relaxed);
 }
 
 #[must_use]
 pub fn isquiet() -> bool {
-    unsafe { QUIET }
+    QUIET.load(Ordering::Relaxed)
 }
 
 pub fn setnocolor(c: bool) {
-    unsafe {
-        NOCOLOR = c;
-    }
+    NOCOLOR.store(c, Ordering::Relaxed);
 }
 
 #[must_use]
 pub fn isnocolor() -> bool {
-    unsafe { NOCOLOR }
+    NOCOLOR.load(Ordering::Relaxed)
+}
+
+pub fn setdryrun(d: bool) {
+    DRYRUN.store(d, Ordering::Relaxed);
+}
+
+#[must_use]
+pub fn isdryrun() -> bool {
+    DRYRUN.load(Ordering::Relaxed)
+}
+
+pub fn setignoretoken(i: bool) {
+    IGNORETOKEN.store(i, Ordering::Relaxed);
+}
+
+#[must_use]
+pub fn isignoretoken() -> bool {
+    IGNORETOKEN.load(Ordering::Relaxed)
 }
 
 #[must_use]
@@ -105,3 +344,153 @@ pub const fn isverbose() -> bool {
 pub fn isfirstrun() -> bool {
     !Config::path().exists()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Mutex, MutexGuard};
+
+    static ENV_LOCK: Mutex<()> = Mutex::new(());
+
+    /// Point the config dir at a fresh temp dir for the duration of a test
+    fn isolated_config() -> (MutexGuard<'static, ()>, tempfile::TempDir) {
+        let guard = ENV_LOCK
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner);
+        let dir = tempfile::tempdir().unwrap();
+        unsafe {
+            std::env::set_var("XDG_CONFIG_HOME", dir.path());
+            std::env::set_var("APPDATA", dir.path());
+        }
+        (guard, dir)
+    }
+
+    #[test]
+    fn global_flags_set_then_get() {
+        setquiet(true);
+        assert!(isquiet());
+        setquiet(false);
+        assert!(!isquiet());
+
+        setnocolor(true);
+        assert!(isnocolor());
+        setnocolor(false);
+        assert!(!isnocolor());
+    }
+
+    #[test]
+    fn command_defaults_sit_between_flags_and_builtins() {
+        let config: Config = toml::from_str(
+            "[merge]\nmerge_method = \"rebase\"\njobs = 8\ndelete_branch = false\n\n[maintain]\nmax_reruns = 3\n",
+        )
+        .unwrap();
+
+        // Config over built-in defaults
+        assert_eq!(config.merge.merge_method(None), MergeMethod::Rebase);
+        assert_eq!(config.merge.jobs(None), 8);
+        assert!(!config.merge.delete_branch(None));
+        assert_eq!(config.maintain.max_reruns(None), 3);
+        // Unset keys keep the built-in default
+        assert_eq!(config.merge.on_conflict(None), OnConflict::Skip);
+        // Flags over config
+        assert_eq!(
+            config.merge.merge_method(Some(MergeMethod::Merge)),
+            MergeMethod::Merge
+        );
+        assert_eq!(config.merge.jobs(Some(1)), 1);
+        assert!(config.merge.delete_branch(Some(true)));
+
+        let defaults = Config::default();
+        assert_eq!(defaults.merge.merge_method(None), MergeMethod::Squash);
+        assert_eq!(defaults.merge.jobs(None), crate::github::DEFAULT_MERGE_JOBS);
+        assert!(defaults.merge.delete_branch(None));
+
+        // Round-trips through the saved file
+        let saved: Config = toml::from_str(&toml::to_string_pretty(&config).unwrap()).unwrap();
+        assert_eq!(saved.merge, config.merge);
+        assert_eq!(saved.maintain, config.maintain);
+    }
+
+    #[test]
+    fn save_leaves_no_temp_file() {
+        let (_guard, _dir) = isolated_config();
+
+        Config::default().save().unwrap();
+
+        let path = Config::path();
+        assert!(path.exists());
+        assert!(!path.with_extension("toml.tmp").exists());
+    }
+
+    #[test]
+    fn org_round_trip() {
+        let (_guard, _dir) = isolated_config();
+
+        let mut cfg = Config::load();
+        cfg.set("org", "my-company").unwrap();
+
+        let reloaded = Config::load();
+        assert_eq!(reloaded.org.as_deref(), Some("my-company"));
+        assert_eq!(reloaded.get("org").as_deref(), Some("my-company"));
+    }
+
+    #[test]
+    fn switchuser_updates_user_and_org() {
+        let (_guard, _dir) = isolated_config();
+
+        let mut cfg = Config::load();
+        cfg.org = Some("old-company".into());
+        cfg.switchuser("octocat", None);
+        cfg.save().unwrap();
+
+        let reloaded = Config::load();
+        assert_eq!(reloaded.lastuser.as_deref(), Some("octocat"));
+        assert_eq!(reloaded.org.as_deref(), Some("old-company"));
+
+        cfg.switchuser("hubot", Some("new-company"));
+        assert_eq!(cfg.org.as_deref(), Some("new-company"));
+
+        // An empty org goes back to the personal account
+        cfg.switchuser("hubot", Some(" "));
+        assert_eq!(cfg.org, None);
+    }
+
+    #[test]
+    fn protected_branches_add_and_reset() {
+        let (_guard, _dir) = isolated_config();
+
+        let mut cfg = Config::load();
+        cfg.set("protected_branches", "+develop,release/*").unwrap();
+        assert_eq!(
+            Config::load().protected_branches,
+            ["main", "master", "develop", "release/*"]
+        );
+
+        cfg.set("protected_branches", "default").unwrap();
+        assert_eq!(Config::load().protected_branches, ["main", "master"]);
+    }
+
+    #[test]
+    fn pickeditor_order() {
+        assert_eq!(pickeditor(Some("hx"), Some("vim"), None, false), "hx");
+        assert_eq!(pickeditor(None, Some("vim"), Some("code -w"), false), "vim");
+        assert_eq!(
+            pickeditor(Some(" "), None, Some("code -w"), false),
+            "code -w"
+        );
+        assert_eq!(pickeditor(None, None, None, false), "vi");
+        assert_eq!(pickeditor(None, None, None, true), "notepad");
+    }
+
+    #[test]
+    fn wip_message_empty_resets() {
+        let (_guard, _dir) = isolated_config();
+
+        let mut cfg = Config::load();
+        cfg.set("wip_message", "chore: wip").unwrap();
+        assert_eq!(Config::load().wip_message.as_deref(), Some("chore: wip"));
+
+        cfg.set("wip_message", "").unwrap();
+        assert_eq!(Config::load().wip_message, None);
+    }
+}
@@ -0,0 +1,180 @@
+use crate::cli::{GitCommands, OpenTarget, UserCmd};
+use crate::ghk::util;
+
+/// Plain-language steps a command will take, for `--explain`
+#[must_use]
+pub fn steps(cmd: &GitCommands) -> Vec<String> {
+    let steps: &[&str] = match cmd {
+        GitCommands::Setup => &[
+            "check that git and the GitHub CLI (gh) are installed",
+            "offer to install anything that is missing",
+            "check that you are logged in to GitHub and offer to log in",
+            "check for an SSH key and that GitHub is reachable",
+        ],
+        GitCommands::Init { .. } => &[
+            "turn this folder into a git repository so changes can be tracked",
+            "name the first branch main (or the one given with --branch)",
+        ],
+        GitCommands::Login => &["open the GitHub login flow in your browser or terminal"],
+        GitCommands::Logout => &["forget the GitHub account you are logged in with"],
+        GitCommands::User { command } => match command {
+            UserCmd::List => &["show the GitHub accounts you are logged in with"],
+            UserCmd::Switch { .. } => &[
+                "switch to another logged in GitHub account",
+                "ask you to log in if that account is not known yet",
+            ],
+        },
+        GitCommands::Create {
+            no_commit,
+            template,
+        } => {
+            if template.is_some() {
+                &[
+                    "check that this folder has no commits or changes of its own",
+                    "ask for a name and whether the repository should be private",
+                    "create the repository on GitHub as a copy of the template",
+                    "turn on branch protection, dependency alerts and security updates",
+                    "connect this folder to it and download the template's files",
+                ]
+            } else if *no_commit {
+                &[
+                    "ask for a name and whether the repository should be private",
+                    "create the repository on GitHub and upload your existing commits",
+                    "turn on branch protection, dependency alerts and security updates",
+                ]
+            } else {
+                &[
+                    "ask for a name and whether the repository should be private",
+                    "save your current files as a first commit if needed",
+                    "create the repository on GitHub and upload your commits",
+                    "turn on branch protection, dependency alerts and security updates",
+                ]
+            }
+        }
+        GitCommands::Fork { .. } => &[
+            "make a copy of the repository under your account or organization",
+            "turn on branch protection, dependency alerts and security updates on the copy",
+        ],
+        GitCommands::Delete { .. } | GitCommands::Unfork { .. } => &[
+            "check that your GitHub login is allowed to delete repositories",
+            "ask you to type the repository's name to confirm",
+            "permanently delete the repository on GitHub (your local copy is kept)",
+        ],
+        GitCommands::Push { .. } | GitCommands::Save { .. } => &[
+            "warn you about files that look sensitive or temporary",
+            "stage all changes",
+            "commit them with your message",
+            "push to origin",
+        ],
+        GitCommands::Pull | GitCommands::Sync => &[
+            "download new changes from GitHub",
+            "replay your local commits on top of them",
+        ],
+        GitCommands::Clone { .. } | GitCommands::Download { .. } => {
+            &["download a copy of the repository into a new folder"]
+        }
+        GitCommands::Status => &[
+            "show your branch, remote, unsaved changes and GitHub login",
+            "show how many commits you are ahead of or behind GitHub (as of the last pull)",
+            "with --json, print the same as a JSON object for scripts",
+        ],
+        GitCommands::Diff => &["show the lines you changed since your last save"],
+        GitCommands::Format => &[
+            "run treefmt if the repository has a treefmt.toml",
+            "otherwise run cargo fmt for Rust projects or prettier for JavaScript ones",
+            "tell you whether any files changed",
+        ],
+        GitCommands::Undo => &[
+            "remove your last commit",
+            "keep all of its changes in your files so nothing is lost",
+        ],
+        GitCommands::Amend { .. } => &[
+            "stage all changes",
+            "add them to your last commit, optionally with a new message",
+            "warn you first if that commit is already on GitHub",
+        ],
+        GitCommands::Stash { pop } => {
+            if *pop {
+                &["bring back the changes you set aside most recently"]
+            } else {
+                &["set your unsaved changes aside so your files match your last commit"]
+            }
+        }
+        GitCommands::History { .. } | GitCommands::Log { .. } => &["list your most recent commits"],
+        GitCommands::Open { target } => match target {
+            OpenTarget::Repo => &["open this repository's GitHub page in your browser"],
+            OpenTarget::Prs => &["open this repository's pull requests in your browser"],
+            OpenTarget::Issues => &["open this repository's issues in your browser"],
+            OpenTarget::Actions => &["open this repository's Actions runs in your browser"],
+            OpenTarget::Settings => &["open this repository's settings in your browser"],
+        },
+        GitCommands::Config { edit, .. } => {
+            if *edit {
+                &[
+                    "create the settings file if it doesn't exist yet",
+                    "open it in your editor (the 'editor' setting, $EDITOR or $VISUAL)",
+                    "check that it is still valid once you close the editor",
+                ]
+            } else {
+                &["show or change ghk settings"]
+            }
+        }
+        GitCommands::Ignore { .. } => &["add a .gitignore template (from GitHub if not built in)"],
+        GitCommands::License { .. } => {
+            &["write a LICENSE file with the current year and your name"]
+        }
+        GitCommands::Branch { name } => {
+            if name.is_some() {
+                &["switch to the branch, creating it if it does not exist"]
+            } else {
+                &["list your branches and mark the current one"]
+            }
+        }
+        GitCommands::Pr { .. } => &[
+            "push your current branch to GitHub if it is not there yet",
+            "open a pull request for it, asking for a title and description if not given",
+            "open the new pull request in your browser",
+        ],
+        GitCommands::Rename { .. } => &[
+            "rename your current branch (asking first if it is the default branch)",
+            "push the new name to GitHub and delete the old one there",
+            "make the new branch track its GitHub copy",
+        ],
+        GitCommands::Tag { name, .. } => {
+            if name.is_some() {
+                &[
+                    "create an annotated tag on your current commit",
+                    "push the tag to GitHub",
+                ]
+            } else {
+                &["list the tags in this repository"]
+            }
+        }
+        GitCommands::Completions { .. } => &["print a shell completion script"],
+    };
+
+    steps.iter().map(std::string::ToString::to_string).collect()
+}
+
+/// Print what a command will do without running it
+pub fn run(cmd: &GitCommands) {
+    println!();
+    util::info("I will:");
+    for step in steps(cmd) {
+        util::dim(&format!("  - {step}"));
+    }
+    println!();
+    util::dim("Nothing was changed. Run again without --explain to do it.");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn push_explains_each_step() {
+        let steps = steps(&GitCommands::Push { allow_dirty: false });
+        assert!(steps.iter().any(|s| s.contains("stage all changes")));
+        assert!(steps.iter().any(|s| s.contains("push to origin")));
+    }
+}
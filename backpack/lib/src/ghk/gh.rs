@@ -1,13 +1,29 @@
 use anyhow::{Context, bail};
 use indicatif::{ProgressBar, ProgressStyle};
 use std::process::{Command, Stdio};
+use std::sync::OnceLock;
 use std::time::Duration;
 
-use crate::ghk::config::Config;
+use crate::cli::OpenTarget;
+use crate::ghk::config::{self, Config};
+use crate::ghk::util;
+use crate::github;
+
+/// Build a gh command.
+///
+/// With `--ignore-token`, `GITHUB_TOKEN` is hidden from gh so it falls back to
+/// its own stored login. The variable is left untouched for the rest of the process.
+fn ghcmd() -> Command {
+    let mut cmd = Command::new("gh");
+    if config::isignoretoken() {
+        cmd.env_remove("GITHUB_TOKEN");
+    }
+    cmd
+}
 
 /// Login to GitHub via gh CLI
 pub fn login() -> anyhow::Result<()> {
-    let status = Command::new("gh")
+    let status = ghcmd()
         .args(["auth", "login"])
         .status()
         .context("Failed to run gh - is it installed?")?;
@@ -20,7 +36,7 @@ pub fn login() -> anyhow::Result<()> {
 
 /// Logout from GitHub
 pub fn logout() -> anyhow::Result<()> {
-    let status = Command::new("gh")
+    let status = ghcmd()
         .args(["auth", "logout"])
         .status()
         .context("Failed to run gh")?;
@@ -33,7 +49,7 @@ pub fn logout() -> anyhow::Result<()> {
 
 /// Check if user is logged in
 pub fn loggedin() -> bool {
-    Command::new("gh")
+    ghcmd()
         .args(["auth", "status"])
         .stdout(Stdio::null())
         .stderr(Stdio::null())
@@ -44,16 +60,38 @@ pub fn loggedin() -> bool {
 
 /// Get copyright holder name
 pub fn copyright() -> anyhow::Result<String> {
-    let cfg = Config::load();
-    match cfg.org.as_deref() {
-        Some(org) => Ok(org.to_string()),
-        _ => whoami(),
+    owner()
+}
+
+/// Account new repos and forks belong to: the configured org, else whoever gh
+/// is logged in as.
+///
+/// The cached `lastuser` is not used: gh may have switched accounts since.
+/// Reads the config fresh, so a switch earlier in the session is picked up.
+pub fn owner() -> anyhow::Result<String> {
+    match Config::load().org {
+        Some(org) => Ok(org),
+        None => currentuser(),
     }
 }
 
+/// Logged in username, asked from GitHub at most once per process.
+///
+/// Use `whoami` instead where the account may have just changed (login, logout, switch).
+pub fn currentuser() -> anyhow::Result<String> {
+    static USER: OnceLock<String> = OnceLock::new();
+
+    if let Some(user) = USER.get() {
+        return Ok(user.clone());
+    }
+    // Only successful lookups are cached, so a later login is still picked up
+    let user = whoami()?;
+    Ok(USER.get_or_init(|| user).clone())
+}
+
 /// Get current logged in username
 pub fn whoami() -> anyhow::Result<String> {
-    let output = Command::new("gh")
+    let output = ghcmd()
         .args(["api", "user", "-q", ".login"])
         .output()
         .context("Failed to get current user")?;
@@ -67,7 +105,7 @@ pub fn whoami() -> anyhow::Result<String> {
 
 /// List logged in accounts
 pub fn listusers() -> anyhow::Result<()> {
-    let status = Command::new("gh")
+    let status = ghcmd()
         .args(["auth", "status"])
         .status()
         .context("Failed to run gh")?;
@@ -80,7 +118,7 @@ pub fn listusers() -> anyhow::Result<()> {
 
 /// Switch to a different account
 pub fn switchuser(name: &str) -> anyhow::Result<()> {
-    let status = Command::new("gh")
+    let status = ghcmd()
         .args(["auth", "switch", "-u", name])
         .status()
         .context("Failed to switch user")?;
@@ -92,44 +130,70 @@ pub fn switchuser(name: &str) -> anyhow::Result<()> {
     Ok(())
 }
 
-/// Create a new repository on GitHub with spinner
-pub fn createrepo(name: &str, private: bool) -> anyhow::Result<()> {
-    let spinner = makespinner("Creating repository on GitHub...");
-
-    let mut args = vec!["repo", "create", name, "--source=.", "--push"];
+/// Create a new repository on GitHub with spinner.
+///
+/// Without a template the current folder is pushed as the first contents; with one,
+/// GitHub copies the template and nothing local is uploaded. `name` must be
+/// `owner/repo`. Returns whether the dependency graph and security updates are on.
+pub fn createrepo(name: &str, private: bool, template: Option<&str>) -> anyhow::Result<bool> {
+    let mut args = vec!["repo", "create", name];
+    match template {
+        Some(template) => args.extend(["--template", template]),
+        None => args.extend(["--source=.", "--push"]),
+    }
     if private {
         args.push("--private");
     } else {
         args.push("--public");
     }
 
-    let output = Command::new("gh")
-        .args(&args)
-        .output()
-        .context("Failed to create repository")?;
+    // The ruleset and security settings need the repo, so a dry run stops here
+    let mut cmd = ghcmd();
+    cmd.args(&args);
+    if util::dryrun(&cmd) {
+        return Ok(false);
+    }
+
+    let spinner = makespinner("Creating repository on GitHub...");
+    let output = cmd.output().context("Failed to create repository")?;
 
     spinner.finish_and_clear();
 
     if !output.status.success() {
+        let err = String::from_utf8_lossy(&output.stderr);
+        if let Some(template) = template
+            && err.contains("not a template")
+        {
+            bail!("{template} is not a template repository");
+        }
         bail!("Failed to create repository");
     }
 
+    // Copying the template happens in the background, like forking
+    if template.is_some()
+        && !waitforrepo(
+            name,
+            FORK_READY_TIMEOUT,
+            "Waiting for GitHub to copy the template...",
+        )
+    {
+        util::warn("The repository is taking a while to appear, trying to configure it anyway");
+    }
+
     // Set branch rules
     createruleset(name)?;
 
     // Enable Dependency Graph / Alerts
-    enable_dep_graph(name)?;
+    let depgraph = enable_dep_graph(name)?;
 
     // Enable Auto-fix PRs
-    enable_security_updates(name)?;
+    let securityupdates = enable_security_updates(name)?;
 
-    Ok(())
+    Ok(depgraph && securityupdates)
 }
 
 /// Fork an existing repository
 pub fn forkrepo(repo: &str, owner: &str) -> anyhow::Result<()> {
-    let spinner = makespinner("Forking repository on GitHub...");
-
     let repo_name = repo
         .trim_end_matches('/')
         .rsplit('/')
@@ -146,15 +210,19 @@ pub fn forkrepo(repo: &str, owner: &str) -> anyhow::Result<()> {
         "--clone=false",
     ];
 
-    let me = whoami().unwrap_or_default();
+    let me = currentuser().unwrap_or_default();
     if owner != me {
         args.extend(["--org", owner]);
     }
 
-    let output = Command::new("gh")
-        .args(&args)
-        .output()
-        .context("Failed to fork repository")?;
+    let mut cmd = ghcmd();
+    cmd.args(&args);
+    if util::dryrun(&cmd) {
+        return Ok(());
+    }
+
+    let spinner = makespinner("Forking repository on GitHub...");
+    let output = cmd.output().context("Failed to fork repository")?;
 
     spinner.finish_and_clear();
 
@@ -178,10 +246,14 @@ pub fn forkrepo(repo: &str, owner: &str) -> anyhow::Result<()> {
         }
     }
 
-    // Give GitHub a moment to finish provisioning the fork
-    let spinner = makespinner("Waiting for GitHub to provision fork...");
-    std::thread::sleep(Duration::from_secs(3));
-    spinner.finish_and_clear();
+    // GitHub provisions forks asynchronously; settings calls fail until it's there
+    if !waitforrepo(
+        &fork_target,
+        FORK_READY_TIMEOUT,
+        "Waiting for GitHub to provision fork...",
+    ) {
+        util::warn("The fork is taking a while to appear, trying to configure it anyway");
+    }
 
     createruleset(&fork_target)?;
     enable_dep_graph(&fork_target)?;
@@ -190,6 +262,39 @@ pub fn forkrepo(repo: &str, owner: &str) -> anyhow::Result<()> {
     Ok(())
 }
 
+/// How long to wait for a new fork or templated repo to become reachable
+const FORK_READY_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Poll `GET /repos/{name}` until it answers, backing off from 500ms to 5s.
+/// Returns false if `timeout` passes first.
+fn waitforrepo(name: &str, timeout: Duration, msg: &str) -> bool {
+    let spinner = makespinner(msg);
+    let deadline = std::time::Instant::now() + timeout;
+    let mut delay = Duration::from_millis(500);
+
+    let ready = loop {
+        let found = ghcmd()
+            .args(["api", &format!("repos/{name}"), "--silent"])
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .status()
+            .is_ok_and(|s| s.success());
+
+        if found {
+            break true;
+        }
+        let now = std::time::Instant::now();
+        if now >= deadline {
+            break false;
+        }
+        std::thread::sleep(delay.min(deadline - now));
+        delay = (delay * 2).min(Duration::from_secs(5));
+    };
+
+    spinner.finish_and_clear();
+    ready
+}
+
 /// Clone a repository by owner/repo name
 pub fn clonerepo(repo: &str, dir: Option<&str>) -> anyhow::Result<()> {
     let spinner = makespinner("Downloading repository...");
@@ -199,7 +304,7 @@ pub fn clonerepo(repo: &str, dir: Option<&str>) -> anyhow::Result<()> {
         args.push(d);
     }
 
-    let output = Command::new("gh")
+    let output = ghcmd()
         .args(&args)
         .stdout(Stdio::piped())
         .stderr(Stdio::piped())
@@ -221,10 +326,10 @@ pub fn clonerepo(repo: &str, dir: Option<&str>) -> anyhow::Result<()> {
     Ok(())
 }
 
-/// Open repository in browser
-pub fn openrepo() -> anyhow::Result<()> {
-    let status = Command::new("gh")
-        .args(["repo", "view", "--web"])
+/// Open a page of the repository in the browser
+pub fn openrepo(target: OpenTarget) -> anyhow::Result<()> {
+    let status = ghcmd()
+        .args(target.gh_args())
         .status()
         .context("Failed to open browser")?;
 
@@ -234,9 +339,115 @@ pub fn openrepo() -> anyhow::Result<()> {
     Ok(())
 }
 
+/// Open a pull request for the current branch, returning its URL.
+///
+/// `None` with `--dry-run`, when no pull request was opened.
+pub fn createpr(title: &str, body: &str) -> anyhow::Result<Option<String>> {
+    let mut cmd = ghcmd();
+    cmd.args(["pr", "create", "--title", title, "--body", body]);
+    if util::dryrun(&cmd) {
+        return Ok(None);
+    }
+
+    let spinner = makespinner("Opening pull request...");
+
+    let output = cmd
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .output()
+        .context("Failed to run gh - is it installed?")?;
+
+    spinner.finish_and_clear();
+
+    if !output.status.success() {
+        let err = String::from_utf8_lossy(&output.stderr);
+        if err.contains("already exists") {
+            bail!("A pull request for this branch already exists");
+        }
+        if err.contains("No commits between") {
+            bail!("Nothing to merge - this branch has no new commits");
+        }
+        bail!("Could not open pull request: {}", err.trim());
+    }
+
+    // gh prints the PR URL as the last line
+    let text = String::from_utf8_lossy(&output.stdout);
+    Ok(Some(
+        text.lines().last().unwrap_or_default().trim().to_string(),
+    ))
+}
+
+/// Open a pull request in the browser
+pub fn openpr(url: &str) -> anyhow::Result<()> {
+    let status = ghcmd()
+        .args(["pr", "view", url, "--web"])
+        .status()
+        .context("Failed to open browser")?;
+
+    if !status.success() {
+        bail!("Could not open in browser");
+    }
+    Ok(())
+}
+
+/// Scopes granted to the current token, from the `X-OAuth-Scopes` header.
+///
+/// `None` when GitHub doesn't say (e.g. fine-grained tokens), so callers should
+/// let the API decide rather than refuse.
+pub fn tokenscopes() -> Option<Vec<String>> {
+    let output = ghcmd()
+        .args(["api", "user", "--include", "--silent"])
+        .stderr(Stdio::null())
+        .output()
+        .ok()?;
+
+    if !output.status.success() {
+        return None;
+    }
+
+    let text = String::from_utf8_lossy(&output.stdout);
+    text.lines().find_map(|line| {
+        let (name, value) = line.split_once(':')?;
+        name.eq_ignore_ascii_case("x-oauth-scopes").then(|| {
+            value
+                .split(',')
+                .map(|s| s.trim().to_string())
+                .filter(|s| !s.is_empty())
+                .collect()
+        })
+    })
+}
+
+/// Permanently delete a repository on GitHub
+pub fn deleterepo(name: &str) -> anyhow::Result<()> {
+    let mut cmd = ghcmd();
+    cmd.args(["repo", "delete", name, "--yes"]);
+    if util::dryrun(&cmd) {
+        return Ok(());
+    }
+
+    let spinner = makespinner("Deleting repository on GitHub...");
+    let output = cmd
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .output()
+        .context("Failed to run gh - is it installed?")?;
+
+    spinner.finish_and_clear();
+
+    if !output.status.success() {
+        let err = String::from_utf8_lossy(&output.stderr);
+        if err.contains("delete_repo") {
+            bail!("Your GitHub login is missing the delete_repo permission");
+        }
+        bail!("Could not delete {name}: {}", err.trim());
+    }
+    Ok(())
+}
+
 /// Get gh CLI version
 pub fn version() -> Option<String> {
-    Command::new("gh").arg("--version").output().ok().map(|o| {
+    ghcmd().arg("--version").output().ok().map(|o| {
         String::from_utf8_lossy(&o.stdout)
             .lines()
             .next()
@@ -245,18 +456,90 @@ pub fn version() -> Option<String> {
     })
 }
 
+/// List gitignore template names GitHub knows about
+pub fn gitignoretemplates() -> anyhow::Result<Vec<String>> {
+    let output = ghcmd()
+        .args(["api", "gitignore/templates", "-q", ".[]"])
+        .output()
+        .context("Failed to run gh")?;
+
+    if !output.status.success() {
+        bail!("Could not list gitignore templates");
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .map(str::trim)
+        .filter(|l| !l.is_empty())
+        .map(String::from)
+        .collect())
+}
+
+/// Fetch a gitignore template from GitHub, matching the name case-insensitively
+pub fn gitignoretemplate(name: &str) -> anyhow::Result<String> {
+    let spinner = makespinner(&format!("Fetching {name} template..."));
+
+    let Some(found) = gitignoretemplates()?
+        .into_iter()
+        .find(|t| t.eq_ignore_ascii_case(name))
+    else {
+        spinner.finish_and_clear();
+        bail!("Unknown template: {name}");
+    };
+
+    let endpoint = format!("gitignore/templates/{found}");
+    let output = ghcmd()
+        .args(["api", &endpoint, "-q", ".source"])
+        .output()
+        .context("Failed to run gh")?;
+
+    spinner.finish_and_clear();
+
+    if !output.status.success() {
+        bail!("Could not fetch {found} template");
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout).to_string())
+}
+
 /// Check if we have SSH key configured
 pub fn hassshkey() -> bool {
-    Command::new("gh")
+    ghcmd()
         .args(["ssh-key", "list"])
         .output()
         .map(|o| o.status.success() && !o.stdout.is_empty())
         .unwrap_or(false)
 }
 
+/// Upload a public SSH key to the logged-in GitHub account
+pub fn addsshkey(pubkey: &std::path::Path) -> anyhow::Result<()> {
+    let mut cmd = ghcmd();
+    cmd.arg("ssh-key").arg("add").arg(pubkey);
+    if util::dryrun(&cmd) {
+        return Ok(());
+    }
+
+    let output = cmd
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .output()
+        .context("Failed to run gh - is it installed?")?;
+
+    if !output.status.success() {
+        let err = String::from_utf8_lossy(&output.stderr);
+        if err.contains("admin:public_key") {
+            bail!(
+                "Your GitHub login is missing the admin:public_key permission - run 'gh auth refresh -s admin:public_key'"
+            );
+        }
+        bail!("Could not add SSH key: {}", err.trim());
+    }
+    Ok(())
+}
+
 /// Check if we can reach GitHub (online check)
 pub fn isonline() -> bool {
-    Command::new("gh")
+    ghcmd()
         .args(["api", "rate_limit"])
         .stdout(Stdio::null())
         .stderr(Stdio::null())
@@ -279,64 +562,53 @@ fn makespinner(msg: &str) -> ProgressBar {
     pb
 }
 
-/// Create default ruleset
+/// Create default ruleset.
+///
+/// Talks to the API directly when `GITHUB_TOKEN` is set, so gh is only needed
+/// as a fallback.
 pub fn createruleset(name: &str) -> anyhow::Result<()> {
+    // A dry run shows the gh command, which is what the API call amounts to
+    if !config::isdryrun() && !config::isignoretoken() && std::env::var("GITHUB_TOKEN").is_ok() {
+        match github::GitHubClient::new() {
+            Ok(client) => match github::create_ruleset(&client, name) {
+                Ok(()) => return Ok(()),
+                Err(e) => util::dim(&format!("API request failed ({e}), trying gh")),
+            },
+            Err(e) => util::dim(&format!("Could not set up API client ({e}), trying gh")),
+        }
+    }
+
+    createrulesetgh(name)
+}
+
+/// Create default ruleset via `gh api`
+fn createrulesetgh(name: &str) -> anyhow::Result<()> {
     let (owner, repo) = name
         .split_once('/')
-        .expect("input must be in the form owner/repo");
+        .context("Repository name must be in the format 'owner/repo'")?;
 
     let endpoint = format!("repos/{owner}/{repo}/rulesets");
 
-    let body = r#"
-{
-  "name": "default",
-  "target": "branch",
-  "enforcement": "active",
-  "conditions": {
-    "ref_name": {
-      "include": ["~DEFAULT_BRANCH"],
-      "exclude": []
-    }
-  },
-  "bypass_actors": [
-    {
-      "actor_type": "OrganizationAdmin",
-      "bypass_mode": "always"
-    }
-  ],
-  "rules": [
-    { "type": "required_signatures", "parameters": {} },
-    { "type": "pull_request", "parameters": {
-        "dismiss_stale_reviews_on_push": false,
-        "require_code_owner_review": false,
-        "require_last_push_approval": false,
-        "required_approving_review_count": 0,
-        "required_review_thread_resolution": false,
-        "allowed_merge_methods": [
-          "squash",
-          "rebase"
-        ]
-      }
-    },
-    { "type": "non_fast_forward", "parameters": {} },
-    { "type": "deletion", "parameters": {} }
-  ]
-}
-"#;
-
-    let mut child = Command::new("gh")
-        .args([
-            "api",
-            "-X",
-            "POST",
-            &endpoint,
-            "--input",
-            "-",
-            "-H",
-            "Accept: application/vnd.github+json",
-            "-H",
-            "X-GitHub-Api-Version: 2022-11-28",
-        ])
+    let body = github::default_ruleset().to_string();
+
+    let mut cmd = ghcmd();
+    cmd.args([
+        "api",
+        "-X",
+        "POST",
+        &endpoint,
+        "--input",
+        "-",
+        "-H",
+        "Accept: application/vnd.github+json",
+        "-H",
+        "X-GitHub-Api-Version: 2022-11-28",
+    ]);
+    if util::dryrun(&cmd) {
+        return Ok(());
+    }
+
+    let mut child = cmd
         .stdin(std::process::Stdio::piped())
         .stdout(std::process::Stdio::null())
         .spawn()
@@ -362,68 +634,94 @@ pub fn createruleset(name: &str) -> anyhow::Result<()> {
     Ok(())
 }
 
-/// Enable Dependency Graph and Security Analysis
-pub fn enable_dep_graph(name: &str) -> anyhow::Result<()> {
-    let (owner, repo) = name
-        .split_once('/')
-        .context("Repository name must be in the format 'owner/repo'")?;
-
+/// Enable Dependency Graph and Security Analysis, returning whether it is on.
+///
+/// Only warns on failure so creating or forking a repo still completes.
+pub fn enable_dep_graph(name: &str) -> anyhow::Result<bool> {
     // Enable Vulnerability Alerts (this ensures dependency graph is active)
     // Documentation: https://docs.github.com/en/rest/vulnerability-alerts/vulnerability-alerts
-    let endpoint = format!("repos/{owner}/{repo}/vulnerability-alerts");
-
-    let output = Command::new("gh")
-        .args([
-            "api",
-            "-X",
-            "PUT",
-            &endpoint,
-            "-H",
-            "Accept: application/vnd.github+json",
-            "-H",
-            "X-GitHub-Api-Version: 2022-11-28",
-        ])
-        .output()
-        .context("Failed to enable dependency graph via gh api")?;
-
-    if !output.status.success() {
-        let err = String::from_utf8_lossy(&output.stderr);
-        // Note: Some repos (like public ones) might have this enabled by default
-        // We log the error but you might want to handle "already enabled" silently
-        bail!("Failed to enable dependency graph: {}", err.trim());
-    }
+    enablesetting(name, "vulnerability-alerts", "dependency graph")
+}
 
-    Ok(())
+/// Enable Dependabot Security Updates, returning whether they are on.
+///
+/// Only warns on failure so creating or forking a repo still completes.
+pub fn enable_security_updates(name: &str) -> anyhow::Result<bool> {
+    // Enable automated security fixes
+    // Documentation: https://docs.github.com/en/rest/vulnerability-alerts/automated-security-fixes
+    enablesetting(name, "automated-security-fixes", "security updates")
 }
 
-/// Enable Dependabot Security Updates
-pub fn enable_security_updates(name: &str) -> anyhow::Result<()> {
+/// `PUT repos/{name}/{setting}`, treating "already enabled" as success
+fn enablesetting(name: &str, setting: &str, label: &str) -> anyhow::Result<bool> {
     let (owner, repo) = name
         .split_once('/')
         .context("Repository name must be in the format 'owner/repo'")?;
 
-    // Enable automated security fixes
-    // Documentation: https://docs.github.com/en/rest/vulnerability-alerts/automated-security-fixes
-    let endpoint = format!("repos/{owner}/{repo}/automated-security-fixes");
-
-    let output = Command::new("gh")
-        .args([
-            "api",
-            "-X",
-            "PUT",
-            &endpoint,
-            "-H",
-            "Accept: application/vnd.github+json",
-            "-H",
-            "X-GitHub-Api-Version: 2022-11-28",
-        ])
-        .output()
-        .context("Failed to enable Dependabot security updates")?;
+    let endpoint = format!("repos/{owner}/{repo}/{setting}");
+
+    let mut cmd = ghcmd();
+    cmd.args([
+        "api",
+        "-X",
+        "PUT",
+        &endpoint,
+        "-H",
+        "Accept: application/vnd.github+json",
+        "-H",
+        "X-GitHub-Api-Version: 2022-11-28",
+    ]);
+    // Nothing was turned on, so don't claim it was
+    if util::dryrun(&cmd) {
+        return Ok(false);
+    }
 
-    if !output.status.success() {
-        let err = String::from_utf8_lossy(&output.stderr);
-        bail!("Failed to enable security updates: {}", err.trim());
+    let output = match cmd.output() {
+        Ok(output) => output,
+        Err(e) => {
+            util::warn(&format!("Could not enable {label}: failed to run gh ({e})"));
+            return Ok(false);
+        }
+    };
+
+    // GitHub answers 204 when it's on, whether or not it already was
+    if output.status.success() {
+        return Ok(true);
     }
 
-    Ok(())
+    let err = String::from_utf8_lossy(&output.stderr);
+    if alreadyenabled(&err) {
+        return Ok(true);
+    }
+
+    util::warn(&format!("Could not enable {label}: {}", err.trim()));
+    util::dim("You can turn it on later under Settings > Code security");
+    Ok(false)
+}
+
+/// Whether a gh error means the setting was on already
+fn alreadyenabled(err: &str) -> bool {
+    let err = err.to_ascii_lowercase();
+    err.contains("already enabled") || err.contains("already exists")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn alreadyenabled_matches_gh_errors() {
+        assert!(alreadyenabled(
+            "gh: Vulnerability alerts are already enabled (HTTP 422)"
+        ));
+        assert!(alreadyenabled("HTTP 422: Already Exists"));
+        assert!(!alreadyenabled("HTTP 404: Not Found"));
+        assert!(!alreadyenabled(""));
+    }
+
+    #[test]
+    fn createrulesetgh_rejects_unqualified_name() {
+        let err = createrulesetgh("my-project").unwrap_err();
+        assert!(err.to_string().contains("owner/repo"), "{err}");
+    }
 }
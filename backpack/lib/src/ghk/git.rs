@@ -1,3 +1,4 @@
+use crate::ghk::util;
 use anyhow::{Context, Result, bail};
 use indicatif::{ProgressBar, ProgressStyle};
 use std::{
@@ -11,18 +12,53 @@ pub fn isrepo() -> bool {
 }
 
 /// Initialize a new git repository
-pub fn init() -> Result<()> {
-    let status = Command::new("git")
-        .arg("init")
-        .status()
-        .context("Failed to run git")?;
+pub fn init(branch: &str) -> Result<()> {
+    // `git init -b` arrived in git 2.28; older versions get the branch renamed afterwards
+    let hasinitbranch = version().is_some_and(|v| v >= (2, 28));
+
+    let mut cmd = Command::new("git");
+    cmd.arg("init");
+    if hasinitbranch {
+        cmd.args(["-b", branch]);
+    }
+    if util::dryrun(&cmd) {
+        return Ok(());
+    }
 
+    let status = cmd.status().context("Failed to run git")?;
     if !status.success() {
         bail!("git init failed");
     }
+
+    if !hasinitbranch {
+        // There are no commits yet, so pointing HEAD elsewhere is the rename
+        let status = Command::new("git")
+            .args(["symbolic-ref", "HEAD", &format!("refs/heads/{branch}")])
+            .status()
+            .context("Failed to run git")?;
+
+        if !status.success() {
+            bail!("Could not name the first branch '{branch}'");
+        }
+    }
     Ok(())
 }
 
+/// Installed git version as (major, minor)
+fn version() -> Option<(u32, u32)> {
+    let output = Command::new("git").arg("--version").output().ok()?;
+    parseversion(&String::from_utf8_lossy(&output.stdout))
+}
+
+/// Parse `git --version` output, e.g. `git version 2.39.3 (Apple Git-146)`
+fn parseversion(out: &str) -> Option<(u32, u32)> {
+    let version = out.trim().strip_prefix("git version ")?;
+    let mut parts = version.split(['.', ' ']);
+    let major = parts.next()?.parse().ok()?;
+    let minor = parts.next()?.parse().ok()?;
+    Some((major, minor))
+}
+
 /// Check if a remote named 'origin' exists
 pub fn hasremote() -> bool {
     Command::new("git")
@@ -32,6 +68,46 @@ pub fn hasremote() -> bool {
         .unwrap_or(false)
 }
 
+/// Connect origin to `url` and check out its default branch.
+///
+/// Meant for a fresh repo with no commits of its own, e.g. one created from a template.
+pub fn checkoutremote(url: &str) -> Result<String> {
+    // The checkout depends on what the fetch finds, so only the first steps can be shown
+    if util::dryrun(Command::new("git").args(["remote", "add", "origin", url])) {
+        util::dryrun(Command::new("git").args(["fetch", "origin"]));
+        return Ok("<default branch>".to_string());
+    }
+
+    let spinner = makespinner("Downloading from GitHub...");
+
+    let run = |args: &[&str]| -> Result<()> {
+        let output = Command::new("git")
+            .args(args)
+            .output()
+            .context("Failed to run git")?;
+        if !output.status.success() {
+            bail!(
+                "git {} failed: {}",
+                args[0],
+                String::from_utf8_lossy(&output.stderr).trim()
+            );
+        }
+        Ok(())
+    };
+
+    let result = run(&["remote", "add", "origin", url])
+        .and_then(|()| run(&["fetch", "origin"]))
+        .and_then(|()| run(&["remote", "set-head", "origin", "--auto"]))
+        .and_then(|()| defaultbranch().context("Could not determine the default branch"))
+        .and_then(|branch| {
+            let upstream = format!("origin/{branch}");
+            run(&["checkout", "-B", &branch, "--track", &upstream]).map(|()| branch)
+        });
+
+    spinner.finish_and_clear();
+    result
+}
+
 /// Check if there are uncommitted changes
 pub fn haschanges() -> Result<bool> {
     let output = Command::new("git")
@@ -57,12 +133,24 @@ pub fn changedfiles() -> Result<Vec<String>> {
         .collect())
 }
 
+/// Check if this repo opted out of the scary-file check (`git config ghk.allowdirty true`)
+pub fn allowdirty() -> bool {
+    Command::new("git")
+        .args(["config", "--bool", "ghk.allowdirty"])
+        .output()
+        .map(|out| out.status.success() && String::from_utf8_lossy(&out.stdout).trim() == "true")
+        .unwrap_or(false)
+}
+
 /// Stage all changes
 pub fn addall() -> Result<()> {
-    let status = Command::new("git")
-        .args(["add", "-A"])
-        .status()
-        .context("Failed to run git add")?;
+    let mut cmd = Command::new("git");
+    cmd.args(["add", "-A"]);
+    if util::dryrun(&cmd) {
+        return Ok(());
+    }
+
+    let status = cmd.status().context("Failed to run git add")?;
 
     if !status.success() {
         bail!("git add failed");
@@ -72,10 +160,13 @@ pub fn addall() -> Result<()> {
 
 /// Commit with message
 pub fn commit(msg: &str) -> Result<()> {
-    let status = Command::new("git")
-        .args(["commit", "-m", msg])
-        .status()
-        .context("Failed to run git commit")?;
+    let mut cmd = Command::new("git");
+    cmd.args(["commit", "-m", msg]);
+    if util::dryrun(&cmd) {
+        return Ok(());
+    }
+
+    let status = cmd.status().context("Failed to run git commit")?;
 
     if !status.success() {
         bail!("git commit failed");
@@ -83,12 +174,39 @@ pub fn commit(msg: &str) -> Result<()> {
     Ok(())
 }
 
+/// Amend the last commit, keeping its message unless a new one is given
+pub fn amend(msg: Option<&str>) -> Result<()> {
+    let mut args = vec!["commit", "--amend"];
+    match msg {
+        Some(m) => args.extend(["-m", m]),
+        None => args.push("--no-edit"),
+    }
+
+    let mut cmd = Command::new("git");
+    cmd.args(&args);
+    if util::dryrun(&cmd) {
+        return Ok(());
+    }
+
+    let status = cmd.status().context("Failed to run git commit --amend")?;
+
+    if !status.success() {
+        bail!("git commit --amend failed");
+    }
+    Ok(())
+}
+
 /// Push to origin with spinner
 pub fn push() -> Result<()> {
+    let mut cmd = Command::new("git");
+    cmd.args(["push", "-u", "origin", "HEAD"]);
+    if util::dryrun(&cmd) {
+        return Ok(());
+    }
+
     let spinner = makespinner("Pushing to GitHub...");
 
-    let output = Command::new("git")
-        .args(["push", "-u", "origin", "HEAD"])
+    let output = cmd
         .stdout(Stdio::piped())
         .stderr(Stdio::piped())
         .output()
@@ -108,10 +226,15 @@ pub fn push() -> Result<()> {
 
 /// Pull from origin with spinner
 pub fn pull() -> Result<()> {
+    let mut cmd = Command::new("git");
+    cmd.args(["pull", "--rebase"]);
+    if util::dryrun(&cmd) {
+        return Ok(());
+    }
+
     let spinner = makespinner("Syncing from GitHub...");
 
-    let output = Command::new("git")
-        .args(["pull", "--rebase"])
+    let output = cmd
         .stdout(Stdio::piped())
         .stderr(Stdio::piped())
         .output()
@@ -188,10 +311,13 @@ pub fn remoteurl() -> Result<String> {
 
 /// Undo last commit (keep changes)
 pub fn undolast() -> Result<()> {
-    let status = Command::new("git")
-        .args(["reset", "--soft", "HEAD~1"])
-        .status()
-        .context("Failed to undo")?;
+    let mut cmd = Command::new("git");
+    cmd.args(["reset", "--soft", "HEAD~1"]);
+    if util::dryrun(&cmd) {
+        return Ok(());
+    }
+
+    let status = cmd.status().context("Failed to undo")?;
 
     if !status.success() {
         bail!("Undo failed - may be no commits to undo");
@@ -199,10 +325,72 @@ pub fn undolast() -> Result<()> {
     Ok(())
 }
 
-/// Get recent commit history
-pub fn history(count: usize) -> Result<Vec<String>> {
+/// Stash uncommitted changes with spinner
+///
+/// Returns `false` when there was nothing to stash.
+pub fn stash() -> Result<bool> {
+    let mut cmd = Command::new("git");
+    cmd.args(["stash", "push", "--include-untracked"]);
+    if util::dryrun(&cmd) {
+        return Ok(true);
+    }
+
+    let spinner = makespinner("Stashing changes...");
+
+    let output = cmd
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .output()
+        .context("Failed to run git stash")?;
+
+    spinner.finish_and_clear();
+
+    if !output.status.success() {
+        bail!("git stash failed");
+    }
+
+    let out = String::from_utf8_lossy(&output.stdout);
+    Ok(!out.contains("No local changes to save"))
+}
+
+/// Restore the most recent stash with spinner
+pub fn stashpop() -> Result<()> {
+    let mut cmd = Command::new("git");
+    cmd.args(["stash", "pop"]);
+    if util::dryrun(&cmd) {
+        return Ok(());
+    }
+
+    let spinner = makespinner("Restoring stashed changes...");
+
+    let output = cmd
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .output()
+        .context("Failed to run git stash pop")?;
+
+    spinner.finish_and_clear();
+
+    if !output.status.success() {
+        let err = String::from_utf8_lossy(&output.stderr);
+        if err.contains("No stash entries found") {
+            bail!("Nothing stashed");
+        }
+        if err.contains("CONFLICT") || err.contains("conflict") {
+            bail!("Stashed changes conflict with your files - please resolve manually");
+        }
+        bail!("git stash pop failed");
+    }
+    Ok(())
+}
+
+/// Recent commits, optionally only those by `author`.
+///
+/// `verbose` adds the author and relative authored date to each line and
+/// orders by authored date; otherwise this is `git log --oneline`.
+pub fn history(count: usize, author: Option<&str>, verbose: bool) -> Result<Vec<String>> {
     let output = Command::new("git")
-        .args(["log", "--oneline", "-n", &count.to_string()])
+        .args(historyargs(count, author, verbose))
         .output()
         .context("Failed to get history")?;
 
@@ -214,27 +402,209 @@ pub fn history(count: usize) -> Result<Vec<String>> {
     Ok(text.lines().map(std::string::ToString::to_string).collect())
 }
 
-/// Check if there are unpushed commits
-#[allow(dead_code)]
-pub fn hasunpushed() -> bool {
-    Command::new("git")
-        .args(["log", "@{u}..", "--oneline"])
+fn historyargs(count: usize, author: Option<&str>, verbose: bool) -> Vec<String> {
+    let mut args = vec!["log".to_string(), "-n".to_string(), count.to_string()];
+    if verbose {
+        args.push("--author-date-order".to_string());
+        args.push("--pretty=format:%h %s (%an, %ar)".to_string());
+    } else {
+        args.push("--oneline".to_string());
+    }
+    if let Some(author) = author {
+        args.push(format!("--author={author}"));
+    }
+    args
+}
+
+/// List existing tags, newest first
+pub fn tags() -> Result<Vec<String>> {
+    let output = Command::new("git")
+        .args(["tag", "--list", "--sort=-creatordate"])
         .output()
-        .map(|out| !out.stdout.is_empty())
-        .unwrap_or(false)
+        .context("Failed to list tags")?;
+
+    if !output.status.success() {
+        return Ok(vec![]);
+    }
+
+    let text = String::from_utf8_lossy(&output.stdout);
+    Ok(text.lines().map(std::string::ToString::to_string).collect())
 }
 
-/// Check if there are unpulled commits
-#[allow(dead_code)]
-pub fn hasunpulled() -> bool {
-    // fetch first to check
-    let _ = Command::new("git").args(["fetch", "--quiet"]).status();
+/// Create an annotated tag
+pub fn tag(name: &str, msg: &str) -> Result<()> {
+    let mut cmd = Command::new("git");
+    cmd.args(["tag", "-a", name, "-m", msg]);
+    if util::dryrun(&cmd) {
+        return Ok(());
+    }
+
+    let output = cmd
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .output()
+        .context("Failed to run git tag")?;
+
+    if !output.status.success() {
+        let err = String::from_utf8_lossy(&output.stderr);
+        if err.contains("already exists") {
+            bail!("Tag '{name}' already exists");
+        }
+        bail!("git tag failed");
+    }
+    Ok(())
+}
+
+/// Push a single tag to origin with spinner
+pub fn pushtag(name: &str) -> Result<()> {
+    let mut cmd = Command::new("git");
+    cmd.args(["push", "origin", name]);
+    if util::dryrun(&cmd) {
+        return Ok(());
+    }
+
+    let spinner = makespinner("Pushing tag to GitHub...");
+
+    let output = cmd
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .output()
+        .context("Failed to run git push")?;
+
+    spinner.finish_and_clear();
+
+    if !output.status.success() {
+        let err = String::from_utf8_lossy(&output.stderr);
+        if err.contains("already exists") {
+            bail!("Tag '{name}' already exists on GitHub");
+        }
+        bail!("git push failed - check your permissions and try again");
+    }
+    Ok(())
+}
+
+/// Name of the default branch, from origin's HEAD or `init.defaultBranch`
+pub fn defaultbranch() -> Option<String> {
+    let output = Command::new("git")
+        .args(["symbolic-ref", "--short", "refs/remotes/origin/HEAD"])
+        .output()
+        .ok()?;
+
+    if output.status.success() {
+        let name = String::from_utf8_lossy(&output.stdout).trim().to_string();
+        return name.strip_prefix("origin/").map(str::to_string);
+    }
+
+    let output = Command::new("git")
+        .args(["config", "init.defaultBranch"])
+        .output()
+        .ok()?;
+    let name = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    (output.status.success() && !name.is_empty()).then_some(name)
+}
+
+/// Rename the current branch
+pub fn renamebranch(new: &str) -> Result<()> {
+    let mut cmd = Command::new("git");
+    cmd.args(["branch", "-m", new]);
+    if util::dryrun(&cmd) {
+        return Ok(());
+    }
+
+    let output = cmd.output().context("Failed to run git branch")?;
+
+    if !output.status.success() {
+        let err = String::from_utf8_lossy(&output.stderr);
+        if err.contains("already exists") {
+            bail!("Branch '{new}' already exists");
+        }
+        bail!("git branch -m failed: {}", err.trim());
+    }
+    Ok(())
+}
 
+/// Check if origin has a branch with this name
+pub fn hasremotebranch(name: &str) -> bool {
     Command::new("git")
-        .args(["log", "..@{u}", "--oneline"])
+        .args(["ls-remote", "--exit-code", "--heads", "origin", name])
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .is_ok_and(|s| s.success())
+}
+
+/// Push `new` to origin, delete `old` there if given, and track the new branch
+pub fn pushrename(old: Option<&str>, new: &str) -> Result<()> {
+    let mut args = vec!["push".to_string(), "origin".to_string()];
+    if let Some(old) = old {
+        args.push(format!(":{old}"));
+    }
+    args.push(new.to_string());
+
+    let mut cmd = Command::new("git");
+    cmd.args(&args);
+    let mut track = Command::new("git");
+    track.args(["branch", "--set-upstream-to", &format!("origin/{new}")]);
+    if util::dryrun(&cmd) {
+        util::dryrun(&track);
+        return Ok(());
+    }
+
+    let spinner = makespinner("Renaming branch on GitHub...");
+
+    let output = cmd
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
         .output()
-        .map(|out| !out.stdout.is_empty())
-        .unwrap_or(false)
+        .context("Failed to run git push")?;
+
+    if !output.status.success() {
+        spinner.finish_and_clear();
+        let err = String::from_utf8_lossy(&output.stderr);
+        if err.contains("refusing to delete the current branch") {
+            bail!(
+                "GitHub still uses the old branch as default - change it in the repo settings first"
+            );
+        }
+        bail!("git push failed - check your permissions and try again");
+    }
+
+    let status = track
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .context("Failed to set upstream")?;
+
+    spinner.finish_and_clear();
+
+    if !status.success() {
+        bail!("Could not track origin/{new}");
+    }
+    Ok(())
+}
+
+/// Check if there are unpushed commits
+pub fn hasunpushed() -> bool {
+    aheadbehind().is_ok_and(|(ahead, _)| ahead > 0)
+}
+
+/// Commits the current branch is (ahead, behind) its upstream, as of the last fetch.
+///
+/// Errors when not on a branch or the branch has no upstream.
+pub fn aheadbehind() -> Result<(usize, usize)> {
+    let repo = git2::Repository::discover(".")?;
+    let head = repo.head().context("No commits yet")?;
+    if !head.is_branch() {
+        bail!("Not on a branch");
+    }
+
+    let branch = git2::Branch::wrap(head);
+    let upstream = branch.upstream().context("No upstream branch")?;
+
+    let (Some(local), Some(remote)) = (branch.get().target(), upstream.get().target()) else {
+        bail!("Could not resolve branch commits");
+    };
+    Ok(repo.graph_ahead_behind(local, remote)?)
 }
 
 /// Get git version
@@ -258,3 +628,74 @@ pub fn makespinner(msg: &str) -> ProgressBar {
     pb.enable_steady_tick(Duration::from_millis(100));
     pb
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ghk::{config, gh};
+
+    #[test]
+    fn dryrun_changes_nothing() {
+        let dir = tempfile::tempdir().unwrap();
+        let repo = git2::Repository::init(dir.path()).unwrap();
+        let sig = git2::Signature::now("Test User", "test@example.com").unwrap();
+        let tree = repo
+            .find_tree(repo.index().unwrap().write_tree().unwrap())
+            .unwrap();
+        repo.commit(Some("HEAD"), &sig, &sig, "initial", &tree, &[])
+            .unwrap();
+        std::fs::write(dir.path().join("dirty.txt"), "keep me").unwrap();
+        let branch = repo.head().unwrap().shorthand().unwrap().to_string();
+
+        // Should dry run fail, whatever runs stays inside the temp repo
+        std::env::set_current_dir(dir.path()).unwrap();
+        config::setdryrun(true);
+        let results = [
+            tag("v1.0.0", "release"),
+            pushtag("v1.0.0"),
+            renamebranch("renamed"),
+            pushrename(Some(&branch), "renamed"),
+            stash().map(|_| ()),
+            stashpop(),
+            pull(),
+            gh::createruleset("owner/repo"),
+        ];
+        let pr = gh::createpr("title", "body");
+        let depgraph = gh::enable_dep_graph("owner/repo");
+        config::setdryrun(false);
+
+        for result in results {
+            result.unwrap();
+        }
+        assert_eq!(pr.unwrap(), None);
+        assert!(!depgraph.unwrap());
+        assert!(repo.tag_names(None).unwrap().is_empty());
+        assert_eq!(repo.head().unwrap().shorthand(), Some(branch.as_str()));
+        assert!(dir.path().join("dirty.txt").exists());
+    }
+
+    #[test]
+    fn history_defaults_to_oneline() {
+        assert_eq!(historyargs(5, None, false), ["log", "-n", "5", "--oneline"]);
+    }
+
+    #[test]
+    fn history_verbose_filters_by_author() {
+        let args = historyargs(10, Some("octocat"), true);
+        assert!(!args.contains(&"--oneline".to_string()));
+        assert!(args.contains(&"--author-date-order".to_string()));
+        assert!(args.iter().any(|a| a.contains("%an") && a.contains("%ar")));
+        assert_eq!(args.last().unwrap(), "--author=octocat");
+    }
+
+    #[test]
+    fn parseversion_handles_vendor_suffixes() {
+        assert_eq!(parseversion("git version 2.43.0\n"), Some((2, 43)));
+        assert_eq!(
+            parseversion("git version 2.39.3 (Apple Git-146)"),
+            Some((2, 39))
+        );
+        assert_eq!(parseversion("git version 2.45.1.windows.1"), Some((2, 45)));
+        assert_eq!(parseversion("not git"), None);
+    }
+}
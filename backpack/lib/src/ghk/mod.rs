@@ -2,6 +2,7 @@ mod app;
 mod commands;
 pub mod config;
 mod error;
+mod explain;
 mod gh;
 mod git;
 mod util;
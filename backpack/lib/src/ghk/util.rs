@@ -1,4 +1,5 @@
 use crate::ghk::config;
+use std::process::Command;
 
 /// Print success message with green checkmark
 pub fn ok(m: &str) {
@@ -52,3 +53,31 @@ pub fn dim(m: &str) {
         println!("\x1b[90m  {m}\x1b[0m");
     }
 }
+
+/// With `--dry-run`, print the command instead of running it.
+///
+/// Returns true when the caller should skip running it. Printed even with
+/// `--quiet`, since the list of commands is the whole point.
+pub fn dryrun(cmd: &Command) -> bool {
+    if !config::isdryrun() {
+        return false;
+    }
+
+    let mut line = cmd.get_program().to_string_lossy().to_string();
+    for arg in cmd.get_args() {
+        let arg = arg.to_string_lossy();
+        if arg.is_empty() || arg.contains(char::is_whitespace) {
+            line.push_str(&format!(" '{arg}'"));
+        } else {
+            line.push(' ');
+            line.push_str(&arg);
+        }
+    }
+
+    if config::isnocolor() {
+        println!("~ would run: {line}");
+    } else {
+        println!("\x1b[36m~\x1b[0m would run: {line}");
+    }
+    true
+}
@@ -1,6 +1,9 @@
+use std::path::Path;
 use std::process::{Command, ExitStatus};
 use tracing::{debug, warn};
 
+use crate::utils::glob_match;
+
 fn run(cmd: &mut Command) -> anyhow::Result<ExitStatus> {
     let status = cmd.status()?;
     Ok(status)
@@ -13,13 +16,93 @@ fn ensure_success(status: ExitStatus, msg: &str) -> anyhow::Result<()> {
     Ok(())
 }
 
-pub fn wip(no_push: bool, no_diff: bool, rewind: Option<u32>) -> anyhow::Result<()> {
-    // Check if working directory is clean
+/// Commit message used by `wip` unless one is given or configured
+pub const DEFAULT_WIP_MESSAGE: &str = "wip 🚧: work-in-progress";
+
+/// Branches `prune` keeps by default, and that `wip` won't force-push when
+/// origin's default branch can't be determined
+pub const DEFAULT_PROTECTED_BRANCHES: &[&str] = &["main", "master"];
+
+/// Output of a git command that must succeed, trimmed
+fn git_output(args: &[&str]) -> Option<String> {
+    let output = Command::new("git").args(args).output().ok()?;
+    let text = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    (output.status.success() && !text.is_empty()).then_some(text)
+}
+
+/// The checked out branch, or `None` on a detached HEAD
+fn current_branch() -> Option<String> {
+    git_output(&["symbolic-ref", "--short", "HEAD"])
+}
+
+/// origin's default branch, from `refs/remotes/origin/HEAD`
+fn default_branch() -> Option<String> {
+    git_output(&["symbolic-ref", "--short", "refs/remotes/origin/HEAD"])
+        .and_then(|name| name.strip_prefix("origin/").map(str::to_string))
+}
+
+/// Whether force-pushing `branch` should be refused.
+///
+/// That's the default branch when known, otherwise `main` or `master`.
+fn is_protected(branch: &str, default: Option<&str>) -> bool {
+    match default {
+        Some(default) => branch == default,
+        None => DEFAULT_PROTECTED_BRANCHES.contains(&branch),
+    }
+}
+
+/// Paths to format from `git status --porcelain -z` output.
+///
+/// Deleted files are left out and renames give the new path. With `-z` paths
+/// are never quoted, and a rename or copy is followed by its original path.
+fn changed_paths(status: &str) -> Vec<String> {
+    let mut entries = status.split('\0');
+    let mut paths = Vec::new();
+    while let Some(entry) = entries.next() {
+        let (Some(code), Some(path)) = (entry.get(..2), entry.get(3..)) else {
+            continue;
+        };
+        if code.contains(['R', 'C']) {
+            entries.next();
+        }
+        if !code.contains('D') && !path.is_empty() {
+            paths.push(path.to_string());
+        }
+    }
+    paths
+}
+
+/// Settings for a `wip` run
+#[derive(Debug, Clone, Default)]
+pub struct WipOptions {
+    pub no_push: bool,
+    pub no_diff: bool,
+    /// Number of commits to fold the changes into (default: 1)
+    pub rewind: Option<u32>,
+    /// Commit message, `DEFAULT_WIP_MESSAGE` when unset
+    pub message: Option<String>,
+    /// Force-push even on the default branch
+    pub allow_protected: bool,
+    /// Make a new commit instead of amending the last one
+    pub no_amend: bool,
+}
+
+/// Whether the repo at `root` configures treefmt (`treefmt.toml` or `.treefmt.toml`).
+#[must_use]
+pub fn has_treefmt_config(root: &Path) -> bool {
+    root.join("treefmt.toml").exists() || root.join(".treefmt.toml").exists()
+}
+
+pub fn wip(options: &WipOptions) -> anyhow::Result<()> {
+    // Check if working directory is clean. Porcelain paths are relative to the
+    // repo root, which is where treefmt runs.
     debug!(
-        command = "git status -s",
+        command = "git status --porcelain -z",
         "Checking working directory status"
     );
-    let output = Command::new("git").args(["status", "-s"]).output()?;
+    let output = Command::new("git")
+        .args(["status", "--porcelain", "-z"])
+        .output()?;
 
     debug!(
         stdout = %String::from_utf8_lossy(&output.stdout),
@@ -32,6 +115,21 @@ pub fn wip(no_push: bool, no_diff: bool, rewind: Option<u32>) -> anyhow::Result<
         return Ok(());
     }
 
+    // Force-pushing rewrites history, so never do it to the default branch by
+    // accident. Checked before the reset and amend so nothing has changed yet.
+    if !options.no_push
+        && !options.no_amend
+        && !options.allow_protected
+        && let Some(branch) = current_branch()
+        && is_protected(&branch, default_branch().as_deref())
+    {
+        eprintln!(
+            "Move your changes to a feature branch with `git switch -c <name>` and run wip again,"
+        );
+        eprintln!("or pass --allow-protected if you really mean to rewrite '{branch}'.");
+        anyhow::bail!("Not force-pushing to protected branch '{branch}'; nothing was changed");
+    }
+
     // Format code
     let repo = git2::Repository::discover(".")?;
     let repo_root = repo
@@ -40,17 +138,27 @@ pub fn wip(no_push: bool, no_diff: bool, rewind: Option<u32>) -> anyhow::Result<
 
     debug!(repo_root = %repo_root.display(), "Located git repository root");
 
-    let has_treefmt_config =
-        repo_root.join("treefmt.toml").exists() || repo_root.join(".treefmt.toml").exists();
-
-    if has_treefmt_config {
+    if has_treefmt_config(repo_root) {
         debug!("Found treefmt config; executing formatting...");
+
+        // Only format what changed; on a big repo a full run is slow
+        let paths = changed_paths(&String::from_utf8_lossy(&output.stdout));
         let mut cmd = Command::new("treefmt");
-        cmd.current_dir(repo_root);
+        cmd.current_dir(repo_root).args(&paths);
 
         // We run the command but don't use 'ensure_success' or '?'
         // because we don't want check failures to block our WIP commit.
-        match run(&mut cmd) {
+        let mut result = run(&mut cmd);
+        if !paths.is_empty() && matches!(&result, Ok(status) if !status.success()) {
+            // Older treefmt releases don't take paths, so try the whole tree
+            debug!(
+                files = paths.len(),
+                "treefmt on changed files did not succeed, retrying on the whole tree"
+            );
+            result = run(Command::new("treefmt").current_dir(repo_root));
+        }
+
+        match result {
             Ok(status) if status.success() => {
                 debug!("treefmt completed successfully");
             }
@@ -71,7 +179,7 @@ pub fn wip(no_push: bool, no_diff: bool, rewind: Option<u32>) -> anyhow::Result<
     }
 
     // Show diff unless suppressed
-    if !no_diff {
+    if !options.no_diff {
         run(Command::new("git").args(["--no-pager", "diff"]))?;
     }
 
@@ -79,11 +187,20 @@ pub fn wip(no_push: bool, no_diff: bool, rewind: Option<u32>) -> anyhow::Result<
     let status = run(Command::new("git").args(["add", "--all"]))?;
     ensure_success(status, "Failed to stage files")?;
 
-    let status = run(Command::new("git").args(["commit", "-am", "wip 🚧: work-in-progress"]))?;
+    let message = options.message.as_deref().unwrap_or(DEFAULT_WIP_MESSAGE);
+    let status = run(Command::new("git").args(["commit", "-am", message]))?;
     ensure_success(status, "Unable to create WIP commit")?;
 
+    // A standalone commit adds to history, so a normal push is enough
+    if options.no_amend {
+        if !options.no_push {
+            ensure_success(run(Command::new("git").arg("push"))?, "git push failed")?;
+        }
+        return Ok(());
+    }
+
     // Determine rewind count
-    let rewind = rewind.unwrap_or(1);
+    let rewind = options.rewind.unwrap_or(1);
     let head_arg = format!("HEAD~{rewind}");
 
     // Soft reset and amend
@@ -98,7 +215,7 @@ pub fn wip(no_push: bool, no_diff: bool, rewind: Option<u32>) -> anyhow::Result<
     )?;
 
     // Push unless suppressed
-    if !no_push {
+    if !options.no_push {
         ensure_success(
             run(Command::new("git").args(["push", "-f"]))?,
             "git push failed",
@@ -108,14 +225,245 @@ pub fn wip(no_push: bool, no_diff: bool, rewind: Option<u32>) -> anyhow::Result<
     Ok(())
 }
 
-fn delete_stale_local_branches(confirm: bool) -> anyhow::Result<()> {
+/// Pick the remote to prune against.
+///
+/// An explicit `--remote` wins, then the remote the current branch tracks,
+/// then `origin`, then the only remote if there is just one.
+fn pick_remote(explicit: Option<&str>, tracking: Option<&str>, remotes: &[&str]) -> Option<String> {
+    explicit
+        .or(tracking)
+        .or_else(|| remotes.iter().copied().find(|r| *r == "origin"))
+        .or_else(|| match remotes {
+            [only] => Some(*only),
+            _ => None,
+        })
+        .map(str::to_string)
+}
+
+/// The remote the checked out branch tracks, if any
+fn tracking_remote(repo: &git2::Repository) -> Option<String> {
+    let head = repo.head().ok()?;
+    let refname = head.name()?;
+    let remote = repo.branch_upstream_remote(refname).ok()?;
+    remote.as_str().map(str::to_string)
+}
+
+fn resolve_remote(repo: &git2::Repository, explicit: Option<&str>) -> anyhow::Result<String> {
+    let remotes = repo.remotes()?;
+    let names: Vec<&str> = remotes.iter().flatten().collect();
+    let tracking = tracking_remote(repo);
+
+    pick_remote(explicit, tracking.as_deref(), &names).ok_or_else(|| {
+        if names.is_empty() {
+            anyhow::anyhow!("This repository has no remotes")
+        } else {
+            anyhow::anyhow!(
+                "Several remotes ({}) and none is tracked; pick one with --remote",
+                names.join(", ")
+            )
+        }
+    })
+}
+
+/// Settings for a `prune` run
+#[derive(Debug, Clone)]
+pub struct PruneOptions {
+    /// Delete without asking
+    pub confirm: bool,
+    /// Remote to compare against, see `pick_remote`
+    pub remote: Option<String>,
+    /// Only list the stale branches; delete nothing and ask nothing
+    pub dry_run: bool,
+    /// Glob patterns (e.g. `release/*`) of branches never to delete
+    pub protect: Vec<String>,
+    /// Also delete branches fully merged into the remote's default branch
+    pub merged: bool,
+    /// Fetch over HTTPS (with `GITHUB_TOKEN`) even if the remote uses SSH
+    pub https: bool,
+}
+
+impl Default for PruneOptions {
+    fn default() -> Self {
+        Self {
+            confirm: false,
+            remote: None,
+            dry_run: false,
+            protect: DEFAULT_PROTECTED_BRANCHES
+                .iter()
+                .map(std::string::ToString::to_string)
+                .collect(),
+            merged: false,
+            https: false,
+        }
+    }
+}
+
+impl PruneOptions {
+    /// Whether `branch` matches one of the `protect` patterns
+    #[must_use]
+    pub fn protects(&self, branch: &str) -> bool {
+        self.protect.iter().any(|p| glob_match(p, branch))
+    }
+}
+
+/// The remote-tracking branch `name` was set up to follow (e.g. `origin/feature`),
+/// read from config so it's known even after the remote branch is gone
+fn tracked_branch(repo: &git2::Repository, name: &str) -> Option<String> {
+    let upstream = repo
+        .branch_upstream_name(&format!("refs/heads/{name}"))
+        .ok()?;
+    let upstream = upstream.as_str()?;
+    Some(
+        upstream
+            .strip_prefix("refs/remotes/")
+            .unwrap_or(upstream)
+            .to_string(),
+    )
+}
+
+/// The remote's default branch (e.g. `origin/main`) and its tip.
+///
+/// Uses `refs/remotes/<remote>/HEAD` when set, else `main` or `master`.
+fn remote_default_tip(repo: &git2::Repository, remote: &str) -> Option<(String, git2::Oid)> {
+    let head = repo
+        .find_reference(&format!("refs/remotes/{remote}/HEAD"))
+        .ok()
+        .and_then(|r| r.symbolic_target().map(str::to_string));
+    let fallbacks = DEFAULT_PROTECTED_BRANCHES
+        .iter()
+        .map(|b| format!("refs/remotes/{remote}/{b}"));
+
+    head.into_iter().chain(fallbacks).find_map(|refname| {
+        let oid = repo.refname_to_id(&refname).ok()?;
+        let short = refname.strip_prefix("refs/remotes/").unwrap_or(&refname);
+        Some((short.to_string(), oid))
+    })
+}
+
+/// Whether every commit on `branch` is already in `tip`
+fn is_merged(
+    repo: &git2::Repository,
+    branch: &git2::Branch,
+    tip: git2::Oid,
+) -> anyhow::Result<bool> {
+    let Some(oid) = branch.get().target() else {
+        return Ok(false);
+    };
+    Ok(oid == tip || repo.graph_descendant_of(tip, oid)?)
+}
+
+/// Ask before deleting `name`; `--yes` answers for the user, and without a
+/// terminal the branch is skipped unless `--yes` was given.
+fn ask_delete(name: &str, question: &str, confirm: bool) -> anyhow::Result<bool> {
+    use std::io::IsTerminal;
+
+    let ans = if confirm {
+        true
+    } else if std::io::stdin().is_terminal() {
+        cliclack::confirm(question)
+            .initial_value(false) // Default to 'No'
+            .interact()?
+    } else {
+        false
+    };
+
+    if !ans {
+        if std::io::stdin().is_terminal() {
+            cliclack::log::remark(format!("\x1b[90m  - Skipping '{name}'.\x1b[0m"))?;
+        } else {
+            println!("\x1b[90m  - Skipping '{name}'.\x1b[0m");
+        }
+    }
+    Ok(ans)
+}
+
+/// Credentials for prune's fetch.
+///
+/// Over SSH: the agent, then the default `~/.ssh` keys (asking for a
+/// passphrase if needed), then a prompt. Over HTTPS: `GITHUB_TOKEN` when set
+/// and the remote is on github.com, then git's credential helper, then a prompt.
+fn authenticator(https: bool) -> auth_git2::GitAuthenticator {
+    let auth = auth_git2::GitAuthenticator::new_empty();
+    if https {
+        // Only GitHub gets the token; other hosts use their own credentials
+        let auth = match std::env::var("GITHUB_TOKEN") {
+            Ok(token) => auth.add_plaintext_credentials("github.com", "x-access-token", token),
+            Err(_) => auth,
+        };
+        auth.try_cred_helper(true).try_password_prompt(3)
+    } else {
+        auth.try_ssh_agent(true)
+            .add_default_ssh_keys()
+            .prompt_ssh_key_password(true)
+            .add_default_username()
+            .try_cred_helper(true)
+            .try_password_prompt(3)
+    }
+}
+
+/// The HTTPS form of a remote URL: `git@host:owner/repo.git` and
+/// `ssh://git@host/owner/repo.git` become `https://host/owner/repo.git`.
+/// `None` for URLs with no HTTPS equivalent, like local paths.
+fn https_url(url: &str) -> Option<String> {
+    if url.starts_with("https://") || url.starts_with("http://") {
+        return Some(url.to_string());
+    }
+
+    let (host, path) = if let Some(rest) = url.strip_prefix("ssh://") {
+        let rest = rest.split_once('@').map_or(rest, |(_, r)| r);
+        let (host, path) = rest.split_once('/')?;
+        // Drop any port; it was the SSH one
+        (host.split(':').next()?, path)
+    } else {
+        let (user_host, path) = url.split_once(':')?;
+        let host = user_host.split_once('@').map_or(user_host, |(_, h)| h);
+        // A '/' before the ':' means a local path, not user@host:path
+        if host.is_empty() || host.contains('/') {
+            return None;
+        }
+        (host, path)
+    };
+
+    Some(format!("https://{host}/{}", path.trim_start_matches('/')))
+}
+
+/// Turn auth failures into something more useful than libgit2's message
+fn fetch_error(e: &git2::Error, remote: &str, https: bool) -> anyhow::Error {
+    let auth_failed = e.code() == git2::ErrorCode::Auth || e.class() == git2::ErrorClass::Ssh;
+    if !auth_failed {
+        return anyhow::anyhow!("Fetching {remote} failed: {}", e.message());
+    }
+
+    if https {
+        anyhow::anyhow!(
+            "Authentication failed fetching {remote} over HTTPS. Check GITHUB_TOKEN or your git credential helper."
+        )
+    } else {
+        anyhow::anyhow!(
+            "Authentication failed fetching {remote}. Check your SSH agent/key, or retry with --https."
+        )
+    }
+}
+
+fn delete_stale_local_branches(options: &PruneOptions) -> anyhow::Result<()> {
+    let confirm = options.confirm;
     // Open repo
     let repo = git2::Repository::discover(".")?;
     let git_config = git2::Config::open_default()?;
-    let auth = auth_git2::GitAuthenticator::default();
-
-    let remote_name = "origin";
-    let mut remote = repo.find_remote(remote_name)?;
+    let auth = authenticator(options.https);
+
+    let remote_name = resolve_remote(&repo, options.remote.as_deref())?;
+    debug!(remote = %remote_name, "Pruning against remote");
+    let mut remote = repo.find_remote(&remote_name)?;
+
+    // --https fetches the same repo over HTTPS without touching the remote's config
+    if options.https {
+        let url = remote.url().and_then(https_url).ok_or_else(|| {
+            anyhow::anyhow!("Can't fetch {remote_name} over HTTPS: unsupported URL")
+        })?;
+        debug!(url = %url, "Fetching over HTTPS");
+        remote = repo.remote_anonymous(&url)?;
+    }
 
     // Build the callbacks
     let mut callbacks = git2::RemoteCallbacks::new();
@@ -127,21 +475,35 @@ fn delete_stale_local_branches(confirm: bool) -> anyhow::Result<()> {
     fetch_options.prune(git2::FetchPrune::On);
 
     // Perform the fetch
-    remote.fetch(
-        &["+refs/heads/*:refs/remotes/origin/*"],
-        Some(&mut fetch_options),
-        None,
-    )?;
+    remote
+        .fetch(
+            &[format!("+refs/heads/*:refs/remotes/{remote_name}/*")],
+            Some(&mut fetch_options),
+            None,
+        )
+        .map_err(|e| fetch_error(&e, &remote_name, options.https))?;
+
+    // With --merged, branches already in the remote's default branch go too
+    let merged_target = if options.merged {
+        let target = remote_default_tip(&repo, &remote_name);
+        if target.is_none() {
+            eprintln!("Could not find {remote_name}'s default branch, so --merged was skipped");
+        }
+        target
+    } else {
+        None
+    };
 
     let mut branches_to_delete = Vec::new();
+    let mut stale = Vec::new();
     let local_branches = repo.branches(Some(git2::BranchType::Local))?;
 
     for branch_res in local_branches {
         let (branch, _) = branch_res?;
         let branch_name = branch.name()?.unwrap_or("unknown").to_string();
 
-        // Safety: Never delete current branch or main/master
-        if branch.is_head() || branch_name == "main" || branch_name == "master" {
+        // Safety: Never delete the current branch or a protected one
+        if branch.is_head() || options.protects(&branch_name) {
             continue;
         }
 
@@ -151,6 +513,12 @@ fn delete_stale_local_branches(confirm: bool) -> anyhow::Result<()> {
         let remote_ref_path = format!("refs/remotes/{remote_name}/{branch_name}");
 
         if repo.find_reference(&remote_ref_path).is_err() {
+            if options.dry_run {
+                let tracked = tracked_branch(&repo, &branch_name);
+                stale.push((branch_name, tracked));
+                continue;
+            }
+
             // Check if this branch ever even tried to track the remote.
             // If it has upstream config OR just matches the remote name, we prune it.
             let has_tracking_config = branch.upstream().is_ok();
@@ -158,39 +526,37 @@ fn delete_stale_local_branches(confirm: bool) -> anyhow::Result<()> {
             if has_tracking_config {
                 println!("  - Branch '{branch_name}' tracks a deleted remote branch. Deleting...");
                 branches_to_delete.push(branch_name);
-            } else {
-                use std::io::IsTerminal;
-
-                if std::io::stdin().is_terminal() {
-                    //cliclack::log::remark("This branch exists locally but not on origin/main.")?;
-                    let ans = if confirm {
-                        true
-                    } else {
-                        cliclack::confirm(format!(
-                            "Branch '{branch_name}' has no remote counterpart. Delete locally?"
-                        ))
-                        .initial_value(false) // Default to 'No'
-                        .interact()?
-                    };
-
-                    if ans {
-                        branches_to_delete.push(branch_name)
-                    } else {
-                        cliclack::log::remark(format!(
-                            "\x1b[90m  - Skipping '{branch_name}'.\x1b[0m"
-                        ))?
-                    }
-                } else {
-                    if confirm {
-                        branches_to_delete.push(branch_name)
-                    } else {
-                        println!("\x1b[90m  - Skipping '{branch_name}'.\x1b[0m")
-                    }
-                }
+            } else if ask_delete(
+                &branch_name,
+                &format!("Branch '{branch_name}' has no remote counterpart. Delete locally?"),
+                confirm,
+            )? {
+                branches_to_delete.push(branch_name);
+            }
+        } else if let Some((default, tip)) = &merged_target
+            && is_merged(&repo, &branch, *tip)?
+        {
+            if options.dry_run {
+                let tracked = tracked_branch(&repo, &branch_name);
+                stale.push((branch_name, tracked));
+                continue;
+            }
+
+            if ask_delete(
+                &branch_name,
+                &format!("Branch '{branch_name}' is merged into {default}. Delete locally?"),
+                confirm,
+            )? {
+                branches_to_delete.push(branch_name);
             }
         }
     }
 
+    // One branch per line so the list can be piped
+    for (name, tracked) in stale {
+        println!("{name}\t{}", tracked.as_deref().unwrap_or("-"));
+    }
+
     for name in branches_to_delete {
         let mut b = repo.find_branch(&name, git2::BranchType::Local)?;
 
@@ -201,10 +567,15 @@ fn delete_stale_local_branches(confirm: bool) -> anyhow::Result<()> {
     Ok(())
 }
 
-pub fn prune(confirm: bool) -> anyhow::Result<()> {
+pub fn prune(options: &PruneOptions) -> anyhow::Result<()> {
+    // Keep dry-run output to the bare list
+    if options.dry_run {
+        return delete_stale_local_branches(options);
+    }
+
     cliclack::intro("Cleaning up stale branches")?;
 
-    match delete_stale_local_branches(confirm) {
+    match delete_stale_local_branches(options) {
         Ok(()) => cliclack::outro("You're all set!")?,
         Err(e) => {
             eprintln!("Error managing branches: {e}");
@@ -38,7 +38,11 @@ fn test_wip_no_changes() {
     std::env::set_current_dir(dir.path()).unwrap();
 
     // wip should return Ok(()) immediately if nothing is staged/changed
-    let result = wip(true, true, None);
+    let result = wip(&WipOptions {
+        no_push: true,
+        no_diff: true,
+        ..WipOptions::default()
+    });
     assert!(result.is_ok());
 }
 
@@ -74,12 +78,118 @@ fn test_wip_with_changes_and_treefmt() {
     // Modify the file to make it "dirty"
     fs::write(repo_path.join("test.txt"), "dirty").unwrap();
 
-    let result = wip(true, true, Some(1));
+    let result = wip(&WipOptions {
+        no_push: true,
+        no_diff: true,
+        rewind: Some(1),
+        ..WipOptions::default()
+    });
 
     // If 'git' is installed in the test env, this should succeed
     assert!(result.is_ok());
 }
 
+#[test]
+fn test_wip_no_amend_adds_a_commit() {
+    let (dir, _repo) = setup_repo();
+    let repo_path = dir.path();
+    std::env::set_current_dir(repo_path).unwrap();
+
+    fs::write(repo_path.join("test.txt"), "hello").unwrap();
+    Command::new("git")
+        .args(["add", "."])
+        .current_dir(repo_path)
+        .status()
+        .unwrap();
+    Command::new("git")
+        .args(["commit", "-m", "initial"])
+        .current_dir(repo_path)
+        .status()
+        .unwrap();
+
+    fs::write(repo_path.join("test.txt"), "dirty").unwrap();
+
+    let result = wip(&WipOptions {
+        no_push: true,
+        no_diff: true,
+        message: Some("chore: wip".into()),
+        no_amend: true,
+        ..WipOptions::default()
+    });
+    assert!(result.is_ok());
+
+    let log = Command::new("git")
+        .args(["log", "--format=%s"])
+        .current_dir(repo_path)
+        .output()
+        .unwrap();
+    assert_eq!(
+        String::from_utf8_lossy(&log.stdout),
+        "chore: wip\ninitial\n"
+    );
+}
+
+#[test]
+fn test_wip_refuses_protected_branch_before_rewriting() {
+    let (dir, _repo) = setup_repo();
+    let repo_path = dir.path();
+    std::env::set_current_dir(repo_path).unwrap();
+
+    fs::write(repo_path.join("test.txt"), "hello").unwrap();
+    git_in(repo_path, &["add", "."]);
+    git_in(repo_path, &["commit", "-m", "initial"]);
+    git_in(repo_path, &["branch", "-M", "main"]);
+    fs::write(repo_path.join("test.txt"), "dirty").unwrap();
+
+    let result = wip(&WipOptions {
+        no_diff: true,
+        ..WipOptions::default()
+    });
+    assert!(
+        result
+            .unwrap_err()
+            .to_string()
+            .contains("protected branch 'main'")
+    );
+
+    // Neither committed nor amended
+    let log = Command::new("git")
+        .args(["log", "--format=%s"])
+        .current_dir(repo_path)
+        .output()
+        .unwrap();
+    assert_eq!(String::from_utf8_lossy(&log.stdout), "initial\n");
+    assert_eq!(
+        fs::read_to_string(repo_path.join("test.txt")).unwrap(),
+        "dirty"
+    );
+}
+
+#[test]
+fn test_changed_paths_from_porcelain() {
+    let status = concat!(
+        " M src/lib.rs\0",
+        "?? new.txt\0",
+        " D gone.rs\0",
+        "R  renamed.rs\0old.rs\0",
+        "C  copy.rs\0src/lib.rs\0",
+        "A  with space.md\0",
+        "A  a -> b.txt\0",
+    );
+    assert_eq!(
+        changed_paths(status),
+        [
+            "src/lib.rs",
+            "new.txt",
+            "renamed.rs",
+            "copy.rs",
+            "with space.md",
+            "a -> b.txt"
+        ]
+    );
+    assert!(changed_paths("").is_empty());
+}
+
 #[test]
 fn test_prune_logic_skips_protected() {
     let (dir, repo) = setup_repo();
@@ -93,7 +203,197 @@ fn test_prune_logic_skips_protected() {
     // A full integration test would involve `git2` creating a "remote" repo
     // on the local disk and linking them.
 
-    let result = prune(false);
+    let result = prune(&PruneOptions::default());
     // This will likely fail because 'origin' doesn't exist yet
     assert!(result.is_err());
 }
+
+#[test]
+fn test_is_protected_uses_default_branch() {
+    assert!(is_protected("trunk", Some("trunk")));
+    assert!(!is_protected("main", Some("trunk")));
+    assert!(!is_protected("feature/x", Some("main")));
+}
+
+#[test]
+fn test_is_protected_falls_back_to_main_and_master() {
+    assert!(is_protected("main", None));
+    assert!(is_protected("master", None));
+    assert!(!is_protected("feature/x", None));
+}
+
+/// Run git in `dir`, failing the test if it doesn't succeed
+fn git_in(dir: &std::path::Path, args: &[&str]) {
+    let status = Command::new("git")
+        .args(args)
+        .current_dir(dir)
+        .status()
+        .unwrap();
+    assert!(status.success(), "git {args:?} failed");
+}
+
+#[test]
+fn test_pick_remote_prefers_explicit_then_tracking() {
+    let remotes = ["origin", "upstream"];
+    assert_eq!(
+        pick_remote(Some("fork"), Some("upstream"), &remotes).as_deref(),
+        Some("fork")
+    );
+    assert_eq!(
+        pick_remote(None, Some("upstream"), &remotes).as_deref(),
+        Some("upstream")
+    );
+    assert_eq!(pick_remote(None, None, &remotes).as_deref(), Some("origin"));
+    assert_eq!(
+        pick_remote(None, None, &["upstream"]).as_deref(),
+        Some("upstream")
+    );
+    assert_eq!(pick_remote(None, None, &["a", "b"]), None);
+    assert_eq!(pick_remote(None, None, &[]), None);
+}
+
+#[test]
+fn test_prune_with_upstream_remote() {
+    let (dir, repo) = setup_repo();
+    let repo_path = dir.path();
+    let remote = TempDir::new().unwrap();
+    git_in(remote.path(), &["init", "--bare"]);
+
+    fs::write(repo_path.join("test.txt"), "hello").unwrap();
+    git_in(repo_path, &["add", "."]);
+    git_in(repo_path, &["commit", "-m", "initial"]);
+    git_in(
+        repo_path,
+        &["remote", "add", "upstream", remote.path().to_str().unwrap()],
+    );
+    git_in(repo_path, &["push", "-u", "upstream", "HEAD"]);
+
+    // A branch that was pushed and then deleted on the remote
+    git_in(repo_path, &["branch", "feature"]);
+    git_in(repo_path, &["push", "-u", "upstream", "feature"]);
+    git_in(remote.path(), &["branch", "-D", "feature"]);
+
+    std::env::set_current_dir(repo_path).unwrap();
+    let result = delete_stale_local_branches(&PruneOptions {
+        confirm: true,
+        ..PruneOptions::default()
+    });
+    assert!(result.is_ok(), "{result:?}");
+
+    assert!(
+        repo.find_branch("feature", git2::BranchType::Local)
+            .is_err()
+    );
+}
+
+#[test]
+fn test_prune_dry_run_keeps_branches() {
+    let (dir, repo) = setup_repo();
+    let repo_path = dir.path();
+    let remote = TempDir::new().unwrap();
+    git_in(remote.path(), &["init", "--bare"]);
+
+    fs::write(repo_path.join("test.txt"), "hello").unwrap();
+    git_in(repo_path, &["add", "."]);
+    git_in(repo_path, &["commit", "-m", "initial"]);
+    git_in(
+        repo_path,
+        &["remote", "add", "origin", remote.path().to_str().unwrap()],
+    );
+    git_in(repo_path, &["branch", "feature"]);
+    git_in(repo_path, &["push", "-u", "origin", "feature"]);
+    git_in(remote.path(), &["branch", "-D", "feature"]);
+
+    assert_eq!(
+        tracked_branch(&repo, "feature").as_deref(),
+        Some("origin/feature")
+    );
+
+    std::env::set_current_dir(repo_path).unwrap();
+    let result = prune(&PruneOptions {
+        dry_run: true,
+        ..PruneOptions::default()
+    });
+    assert!(result.is_ok(), "{result:?}");
+
+    assert!(repo.find_branch("feature", git2::BranchType::Local).is_ok());
+}
+
+#[test]
+fn test_prune_options_protect_globs() {
+    let options = PruneOptions {
+        protect: vec!["main".into(), "release/*".into(), "develop".into()],
+        ..PruneOptions::default()
+    };
+    assert!(options.protects("main"));
+    assert!(options.protects("release/1.2"));
+    assert!(options.protects("develop"));
+    assert!(!options.protects("feature/release"));
+    assert!(!options.protects("master"));
+
+    let defaults = PruneOptions::default();
+    assert!(defaults.protects("main"));
+    assert!(defaults.protects("master"));
+}
+
+#[test]
+fn test_prune_merged_deletes_merged_branches_only() {
+    let (dir, repo) = setup_repo();
+    let repo_path = dir.path();
+    let remote = TempDir::new().unwrap();
+    git_in(remote.path(), &["init", "--bare"]);
+
+    fs::write(repo_path.join("test.txt"), "hello").unwrap();
+    git_in(repo_path, &["add", "."]);
+    git_in(repo_path, &["commit", "-m", "initial"]);
+    git_in(repo_path, &["branch", "-M", "main"]);
+    git_in(
+        repo_path,
+        &["remote", "add", "origin", remote.path().to_str().unwrap()],
+    );
+
+    // "done" is merged into main, "open" has a commit main doesn't
+    git_in(repo_path, &["switch", "-c", "done"]);
+    fs::write(repo_path.join("done.txt"), "done").unwrap();
+    git_in(repo_path, &["add", "."]);
+    git_in(repo_path, &["commit", "-m", "done"]);
+    git_in(repo_path, &["switch", "main"]);
+    git_in(repo_path, &["merge", "--ff-only", "done"]);
+    git_in(repo_path, &["switch", "-c", "open"]);
+    fs::write(repo_path.join("open.txt"), "open").unwrap();
+    git_in(repo_path, &["add", "."]);
+    git_in(repo_path, &["commit", "-m", "open"]);
+    git_in(repo_path, &["switch", "main"]);
+    git_in(repo_path, &["push", "origin", "main", "done", "open"]);
+
+    std::env::set_current_dir(repo_path).unwrap();
+    let result = delete_stale_local_branches(&PruneOptions {
+        confirm: true,
+        merged: true,
+        ..PruneOptions::default()
+    });
+    assert!(result.is_ok(), "{result:?}");
+
+    let local = |name| repo.find_branch(name, git2::BranchType::Local).is_ok();
+    assert!(!local("done"));
+    assert!(local("open"));
+    assert!(local("main"));
+}
+
+#[test]
+fn test_https_url_from_remote_urls() {
+    assert_eq!(
+        https_url("git@github.com:owner/repo.git").as_deref(),
+        Some("https://github.com/owner/repo.git")
+    );
+    assert_eq!(
+        https_url("ssh://git@github.com:22/owner/repo").as_deref(),
+        Some("https://github.com/owner/repo")
+    );
+    assert_eq!(
+        https_url("https://github.com/owner/repo").as_deref(),
+        Some("https://github.com/owner/repo")
+    );
+    assert_eq!(https_url("/srv/git/repo.git"), None);
+    assert_eq!(https_url("../repo"), None);
+}
@@ -0,0 +1,105 @@
+use crate::github::{BranchCleanup, Cleanup, GitHubClient, delete_head_branch, deletion_bar};
+use crate::utils;
+use colored::Colorize;
+use serde::Deserialize;
+use std::collections::HashSet;
+
+/// Branches Dependabot creates its PRs from
+pub const DEPENDABOT_BRANCH_PREFIX: &str = "dependabot/";
+
+#[derive(Debug, Deserialize)]
+struct Branch {
+    name: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct OpenPull {
+    head: PullHead,
+}
+
+#[derive(Debug, Deserialize)]
+struct PullHead {
+    #[serde(rename = "ref")]
+    name: String,
+}
+
+/// Dependabot branches that no open PR uses, sorted.
+#[must_use]
+pub fn stale_dependabot_branches(
+    branches: Vec<String>,
+    open_heads: &HashSet<String>,
+) -> Vec<String> {
+    let mut stale: Vec<String> = branches
+        .into_iter()
+        .filter(|b| b.starts_with(DEPENDABOT_BRANCH_PREFIX) && !open_heads.contains(b))
+        .collect();
+    stale.sort();
+    stale
+}
+
+/// Deletes `dependabot/*` branches of `repo` that have no open PR, after asking
+/// (see `utils::confirm`; `yes` skips the question).
+///
+/// Listing the branches or PRs is an error; failed deletions are collected in the result.
+pub fn delete_stale_branches(
+    client: &GitHubClient,
+    repo: &str,
+    yes: bool,
+) -> anyhow::Result<Cleanup> {
+    eprintln!(
+        "{}",
+        format!("Deleting stale Dependabot branches for {repo}").yellow()
+    );
+
+    let branches = client
+        .fetch_paginated::<Branch>(&format!("repos/{repo}/branches"))?
+        .into_iter()
+        .map(|b| b.name)
+        .collect();
+    // Never delete a branch an open PR still points at
+    let open_heads: HashSet<String> = client
+        .fetch_paginated::<OpenPull>(&format!("repos/{repo}/pulls?state=open"))?
+        .into_iter()
+        .map(|p| p.head.name)
+        .collect();
+
+    let stale = stale_dependabot_branches(branches, &open_heads);
+    if stale.is_empty() {
+        eprintln!("{}", "No stale Dependabot branches found.".blue());
+        return Ok(Cleanup::default());
+    }
+
+    for branch in &stale {
+        eprintln!("  {branch}");
+    }
+    let question = format!("Delete these {} branches of {repo}?", stale.len());
+    if !utils::confirm(&question, yes)? {
+        eprintln!("Keeping them (pass --yes to skip this question)");
+        return Ok(Cleanup::default());
+    }
+
+    let bar = deletion_bar(stale.len());
+    let mut cleanup = Cleanup::default();
+    for branch in &stale {
+        if client.is_cancelled() {
+            cleanup.interrupted = true;
+            break;
+        }
+        match delete_head_branch(client, repo, branch) {
+            Ok(BranchCleanup::Deleted) => cleanup.deleted += 1,
+            Ok(BranchCleanup::AlreadyGone | BranchCleanup::Protected) => {}
+            Err(e) => cleanup
+                .errors
+                .push(format!("Error deleting branch {branch}: {e}")),
+        }
+        // Count every branch handled, so the bar reaches the end even when some are kept
+        bar.inc(1);
+    }
+    bar.finish_and_clear();
+
+    eprintln!(
+        "{}",
+        format!("{} stale Dependabot branches deleted.", cleanup.deleted).blue()
+    );
+    Ok(cleanup)
+}
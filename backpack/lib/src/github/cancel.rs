@@ -0,0 +1,55 @@
+//! Ctrl-C handling for long deletion runs.
+//!
+//! The first Ctrl-C only sets a flag. Deletion loops check it between batches,
+//! let the requests already in flight finish, and report what they got done.
+//! A second Ctrl-C exits right away.
+
+use crate::github::GitHubClient;
+use colored::Colorize;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// The process-wide flag set by Ctrl-C; every client checks it by default.
+pub(super) static CANCELLED: AtomicBool = AtomicBool::new(false);
+
+/// Ask running deletion loops to stop after their current batch.
+pub fn cancel() {
+    CANCELLED.store(true, Ordering::SeqCst);
+}
+
+/// Whether Ctrl-C was pressed (or `cancel` called) during this run.
+#[must_use]
+pub fn is_cancelled() -> bool {
+    CANCELLED.load(Ordering::SeqCst)
+}
+
+impl GitHubClient {
+    /// Check `flag` instead of the process-wide Ctrl-C flag, e.g. so a test
+    /// can cancel its own run without stopping others.
+    #[must_use]
+    pub const fn with_cancel(mut self, flag: &'static AtomicBool) -> Self {
+        self.cancel = flag;
+        self
+    }
+
+    /// Whether deletion loops using this client should stop after their current batch.
+    #[must_use]
+    pub fn is_cancelled(&self) -> bool {
+        self.cancel.load(Ordering::SeqCst)
+    }
+}
+
+/// Waits for Ctrl-C and cancels; meant to be spawned on the runtime.
+pub async fn cancel_on_ctrl_c() {
+    if tokio::signal::ctrl_c().await.is_err() {
+        return;
+    }
+    cancel();
+    eprintln!(
+        "{}",
+        "Interrupted: finishing the deletions in flight. Press Ctrl-C again to quit now.".yellow()
+    );
+
+    if tokio::signal::ctrl_c().await.is_ok() {
+        std::process::exit(130);
+    }
+}
@@ -0,0 +1,66 @@
+use reqwest::StatusCode;
+use thiserror::Error;
+
+/// Why a PR was not merged: GitHub refused, or a plugin vetoed it.
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum MergeError {
+    /// Someone pushed to the base branch mid-merge; trying again usually works
+    #[error("the base branch was modified during the merge")]
+    BaseBranchModified,
+
+    /// Blocked by something other than checks or conflicts, e.g. a missing review
+    #[error("not mergeable: {0}")]
+    NotMergeable(String),
+
+    #[error("required status checks have not passed")]
+    ChecksFailed,
+
+    #[error("the PR has merge conflicts")]
+    Conflict,
+
+    #[error("{0}")]
+    Unknown(String),
+
+    /// A `pr-merge-init` plugin blocked the merge: the plugin's name and reason
+    #[error("vetoed by {0}")]
+    Vetoed(String),
+}
+
+impl MergeError {
+    /// Classifies a failed merge from the API's status and error body, or from
+    /// `gh pr merge`'s stderr (which has no status).
+    ///
+    /// The wording is checked first since GitHub uses 405 for most refusals;
+    /// the status only decides when the message is unfamiliar.
+    #[must_use]
+    pub fn parse(status: Option<StatusCode>, body: &str) -> Self {
+        // The API wraps the message in JSON; gh prints it as is
+        let message = serde_json::from_str::<serde_json::Value>(body)
+            .ok()
+            .and_then(|v| v["message"].as_str().map(str::to_string))
+            .unwrap_or_else(|| body.trim().to_string());
+        let lower = message.to_ascii_lowercase();
+
+        if lower.contains("base branch was modified") {
+            Self::BaseBranchModified
+        } else if lower.contains("merge conflict") || lower.contains("cannot be cleanly created") {
+            Self::Conflict
+        } else if lower.contains("status check") || lower.contains("checks have not") {
+            Self::ChecksFailed
+        } else if lower.contains("not mergeable") {
+            Self::NotMergeable(message)
+        } else {
+            match status {
+                Some(StatusCode::CONFLICT) => Self::Conflict,
+                Some(StatusCode::METHOD_NOT_ALLOWED) => Self::NotMergeable(message),
+                _ => Self::Unknown(message),
+            }
+        }
+    }
+
+    /// Whether the same merge may succeed when tried again shortly.
+    #[must_use]
+    pub const fn is_retryable(&self) -> bool {
+        matches!(self, Self::BaseBranchModified)
+    }
+}
@@ -0,0 +1,135 @@
+use crate::github::{self, GitHubClient, MaintainReport, RerunMode};
+use crate::utils;
+
+/// Settings for a `maintain` run, shared by every repository.
+#[derive(Debug, Clone, Default)]
+pub struct MaintainOptions {
+    /// `rerun`, `ruleset`, `branches` or `release`; cleanup only when `None`
+    pub action: Option<String>,
+    /// Prune untagged versions from every container package in the org instead
+    pub all_packages: bool,
+    /// With `rerun`: rerun at most this many workflows
+    pub max_reruns: usize,
+    /// With `rerun`: only this workflow
+    pub workflow: Option<String>,
+    /// With `rerun`: rerun every job, not just the failed ones
+    pub all_jobs: bool,
+    /// Skip the confirmation before `release` deletes releases and tags, or
+    /// `branches` deletes stale Dependabot branches
+    pub yes: bool,
+    /// With `release`: update the release when it already exists instead of skipping it
+    pub update_existing: bool,
+}
+
+impl MaintainOptions {
+    fn is_action(&self, action: &str) -> bool {
+        self.action.as_deref() == Some(action)
+    }
+}
+
+/// Runs the selected maintenance action against one repository and reports what it changed.
+///
+/// Without an action the repo is cleaned up: failed workflow runs and untagged
+/// container versions are deleted. `release` additionally replaces every release
+/// and tag with a fresh v0.1.0, after asking (see `MaintainOptions::yes`).
+pub fn maintain_repo(
+    client: &GitHubClient,
+    repo: &str,
+    options: &MaintainOptions,
+) -> anyhow::Result<MaintainReport> {
+    let mut report = MaintainReport::new(repo);
+
+    // Initialize basic CLI output
+    eprintln!("Starting maintenance for {repo}");
+
+    // Org-wide package cleanup replaces the per-repo cleanup
+    if options.all_packages {
+        let org = repo.split('/').next().unwrap_or(repo);
+        report.containers_deleted =
+            report.absorb(github::delete_org_container_versions(client, org));
+        return Ok(report);
+    }
+
+    // Rerunning failed jobs is handled outside the main cleanup loop
+    if options.is_action("rerun") {
+        let mode = if options.all_jobs {
+            RerunMode::AllJobs
+        } else {
+            RerunMode::FailedJobs
+        };
+        github::rerun_failed_jobs(
+            client,
+            repo,
+            options.workflow.as_deref(),
+            options.max_reruns,
+            mode,
+        );
+        return Ok(report);
+    }
+
+    // Applying the ruleset is independent of the cleanup as well
+    if options.is_action("ruleset") {
+        github::ensure_default_ruleset(client, repo)?;
+        return Ok(report);
+    }
+
+    // Stale Dependabot branches are cleaned up on their own too
+    if options.is_action("branches") {
+        report.branches_deleted =
+            report.absorb(github::delete_stale_branches(client, repo, options.yes)?);
+        return Ok(report);
+    }
+
+    let is_release_action = options.is_action("release");
+    if is_release_action {
+        eprintln!(
+            "!!! DANGER: 'release' action selected. This will delete all existing releases and tags."
+        );
+
+        let question = format!("Delete every release and tag of {repo}?");
+        if !utils::confirm(&question, options.yes)? {
+            eprintln!("Exiting... (pass --yes to skip this question)");
+            return Ok(report);
+        }
+    }
+
+    // Cleanup Repo (Always executed unless 'rerun')
+    report.workflows_deleted = report.absorb(github::delete_failed_workflows(client, repo));
+    eprintln!("Deleted failed workflows");
+
+    // After Ctrl-C, report what was done rather than starting the next step
+    if client.is_cancelled() {
+        report.interrupted = true;
+        return Ok(report);
+    }
+
+    report.containers_deleted = report.absorb(github::delete_old_container_versions(client, repo));
+    eprintln!("Deleted old containers versions");
+
+    // Create new release (only if 'release' action is specified)
+    if is_release_action && !client.is_cancelled() {
+        eprintln!("Starting full release cleanup");
+
+        let cleanup = github::delete_all_releases(client, repo);
+        let complete = cleanup.is_complete();
+        report.releases_deleted = report.absorb(cleanup.releases);
+        report.tags_deleted = report.absorb(cleanup.tags);
+
+        if complete {
+            eprintln!("Deleted all releases and tags");
+
+            // Then create the new release; re-running is fine if it's already there
+            match github::create_release(client, repo, options.update_existing)? {
+                github::ReleaseOutcome::Created => eprintln!("Created new release"),
+                github::ReleaseOutcome::Updated => eprintln!("Updated existing release"),
+                github::ReleaseOutcome::AlreadyExists | github::ReleaseOutcome::EmptyRepo => {}
+            }
+        } else {
+            eprintln!("Failed to complete full release cleanup for {repo}");
+        }
+
+        eprintln!("Release cleanup complete");
+    }
+
+    Ok(report)
+}
@@ -1,22 +1,66 @@
+mod branches;
+mod cancel;
+mod error;
+mod maintain;
+mod org;
+mod policy;
 mod pr;
+mod ratelimit;
 mod release;
+mod ruleset;
+mod summary;
+mod trace;
 mod workflow;
 
+pub use branches::*;
+pub use cancel::*;
+pub use error::*;
+pub use maintain::*;
+pub use org::*;
+pub use policy::*;
 pub use pr::*;
+pub use ratelimit::*;
 pub use release::*;
+pub use ruleset::*;
+pub use summary::*;
+pub use trace::*;
 pub use workflow::*;
 
 use anyhow::Result;
-use reqwest::StatusCode;
-use reqwest::blocking::{Client, Response};
+use indicatif::{ProgressBar, ProgressStyle};
+use reqwest::blocking::{Client, RequestBuilder, Response};
+use reqwest::header::RETRY_AFTER;
+use reqwest::{Method, StatusCode};
 use serde::{Deserialize, Serialize};
+use std::ops::ControlFlow;
+use std::sync::OnceLock;
+use std::sync::atomic::AtomicBool;
 use std::time::Duration;
+use tracing::debug;
 
 // --- Constants ---
 pub const DEPENDABOT_USER: &str = "dependabot[bot]";
 pub const GITHUB_API_BASE: &str = "https://api.github.com";
+/// How many times a merge is tried when the base branch keeps moving under it
 pub const MAX_MERGE_ATTEMPTS: u8 = 2;
+/// Wait before trying such a merge again
 pub const UPDATE_WAIT_SECS: u64 = 5;
+/// How many deletions run at once; Ctrl-C is checked between batches
+pub const DELETE_BATCH: usize = 10;
+/// `User-Agent` sent with every request unless `--user-agent` overrides it
+pub const USER_AGENT_VALUE: &str = concat!("github-bot/", env!("CARGO_PKG_VERSION"));
+/// Default for `merge --jobs`: how many repositories are processed at once.
+pub const DEFAULT_MERGE_JOBS: usize = 4;
+/// Largest `per_page` the GitHub API accepts, and what `fetch_paginated` asks for
+pub const MAX_PER_PAGE: usize = 100;
+/// Longest PR diff `merge --interactive --diff` prints before truncating it
+pub const MAX_DIFF_LINES: usize = 400;
+/// How many times a request is retried after a 5xx or 429 answer
+pub const MAX_RETRIES: u32 = 3;
+/// First wait before a retry; doubled for each further attempt
+pub const RETRY_BASE_DELAY: Duration = Duration::from_secs(1);
+/// Longest wait before a retry, even when `Retry-After` asks for more
+pub const MAX_RETRY_DELAY: Duration = Duration::from_secs(60);
 
 // --- GitHub API Data Structures ---
 
@@ -34,6 +78,79 @@ pub struct GitHubClient {
     client: Client,
     token: String,
     api_base: Url,
+    print_curl: bool,
+    /// Ctrl-C flag the deletion loops check, see `with_cancel`
+    cancel: &'static AtomicBool,
+}
+
+/// Renders an equivalent `curl` command for an API request.
+///
+/// The token is always redacted as `$GITHUB_TOKEN` so the output can be shared.
+#[must_use]
+pub fn curl_command(method: &str, url: &str, body: Option<&str>) -> String {
+    use std::fmt::Write as _;
+
+    let mut cmd = format!("curl -X {method} '{url}'");
+    for header in [
+        "\"Authorization: Bearer $GITHUB_TOKEN\"",
+        "'Accept: application/vnd.github+json'",
+        "'X-GitHub-Api-Version: 2022-11-28'",
+    ] {
+        let _ = write!(cmd, " \\\n  -H {header}");
+    }
+    if let Some(b) = body {
+        let _ = write!(cmd, " \\\n  -d '{}'", b.replace('\'', "'\\''"));
+    }
+    cmd
+}
+
+static USER_AGENT_OVERRIDE: OnceLock<String> = OnceLock::new();
+
+/// Replace `USER_AGENT_VALUE` for the rest of the run (`--user-agent`).
+///
+/// Call it before building any client; only the first call has an effect.
+pub fn set_user_agent(user_agent: String) {
+    let _ = USER_AGENT_OVERRIDE.set(user_agent);
+}
+
+/// The `User-Agent` to send: the `--user-agent` override, else `USER_AGENT_VALUE`.
+#[must_use]
+pub fn user_agent() -> &'static str {
+    USER_AGENT_OVERRIDE
+        .get()
+        .map_or(USER_AGENT_VALUE, String::as_str)
+}
+
+/// A "deleted X of N" bar for bulk deletions; hidden with `--quiet`.
+///
+/// Worker threads share clones of it and call `inc(1)` per deletion.
+#[must_use]
+pub fn deletion_bar(total: usize) -> ProgressBar {
+    if crate::ghk::config::isquiet() {
+        return ProgressBar::hidden();
+    }
+    let bar = ProgressBar::new(total as u64);
+    bar.set_style(
+        ProgressStyle::default_bar()
+            .template("{bar:30.cyan/blue} deleted {pos} of {len} ({elapsed})")
+            .unwrap(),
+    );
+    bar
+}
+
+/// How long to wait before retry number `attempt + 1`.
+///
+/// A `Retry-After` in seconds wins; otherwise the wait doubles from
+/// `RETRY_BASE_DELAY`. Either way it never exceeds `MAX_RETRY_DELAY`.
+#[must_use]
+pub fn retry_delay(retry_after: Option<&str>, attempt: u32) -> Duration {
+    retry_after
+        .and_then(|v| v.trim().parse::<u64>().ok())
+        .map_or_else(
+            || RETRY_BASE_DELAY.saturating_mul(2u32.saturating_pow(attempt)),
+            Duration::from_secs,
+        )
+        .min(MAX_RETRY_DELAY)
 }
 
 impl GitHubClient {
@@ -48,67 +165,209 @@ impl GitHubClient {
                 "GITHUB_TOKEN required"
             })?;
 
+        Self::with_token(token)
+    }
+
+    /// Initializes the client with `token` (from `--token`) when given, else `GITHUB_TOKEN`.
+    pub fn from_token_or_env(token: Option<String>) -> Result<Self, Box<dyn std::error::Error>> {
+        token.map_or_else(Self::new, Self::with_token)
+    }
+
+    /// Initializes a client for api.github.com with the given token, or for
+    /// `GITHUB_API_URL` when set (as in GitHub Actions, including on Enterprise).
+    pub fn with_token(token: String) -> Result<Self, Box<dyn std::error::Error>> {
+        let base = env::var("GITHUB_API_URL").unwrap_or_else(|_| GITHUB_API_BASE.to_string());
+        Self::with_base(token, &base)
+    }
+
+    /// Initializes a client for another API endpoint, e.g. GitHub Enterprise
+    /// (`https://github.example.com/api/v3`) or a mock server in tests.
+    pub fn with_base(token: String, base_url: &str) -> Result<Self, Box<dyn std::error::Error>> {
         // Build the blocking client
         let client = Client::builder()
             .timeout(Duration::from_secs(30))
-            .user_agent("github-maintain-rs/1.0")
+            .user_agent(user_agent())
             .build()?;
 
-        let api_base = Url::parse("https://api.github.com/")?;
+        // Without the trailing slash, joining paths would drop the last segment (e.g. `v3`)
+        let api_base = Url::parse(&format!("{}/", base_url.trim_end_matches('/')))?;
 
         Ok(Self {
             client,
             token,
             api_base,
+            print_curl: false,
+            cancel: &cancel::CANCELLED,
         })
     }
 
+    /// Print an equivalent `curl` command to stderr before each request.
+    #[must_use]
+    pub const fn with_print_curl(mut self, enabled: bool) -> Self {
+        self.print_curl = enabled;
+        self
+    }
+
+    /// Prints the request as a `curl` command when `--print-curl` is enabled,
+    /// and logs it when `--trace-http` is.
+    pub(super) fn trace_request(&self, method: &str, url: &Url, body: Option<&str>) {
+        if self.print_curl {
+            eprintln!("{}", curl_command(method, url.as_str(), body));
+        }
+        trace_http_request(method, url, body, &self.token);
+    }
+
+    /// Whether the repository has no commits yet.
+    ///
+    /// GitHub answers `409 Conflict` when listing the commits of a freshly
+    /// created, empty repository.
+    pub fn is_empty_repo(&self, repo: &str) -> Result<bool, reqwest::Error> {
+        let mut url = self
+            .api_base
+            .join(&format!("repos/{repo}/commits"))
+            .unwrap();
+        url.query_pairs_mut().append_pair("per_page", "1");
+
+        self.trace_request("GET", &url, None);
+
+        let response = self
+            .client
+            .get(url)
+            .bearer_auth(&self.token)
+            .header("Accept", "application/vnd.github+json")
+            .header("X-GitHub-Api-Version", "2022-11-28")
+            .send()?;
+        let response = trace_http_response(response, &self.token)?;
+
+        if response.status() == StatusCode::CONFLICT {
+            return Ok(true);
+        }
+
+        let commits: Vec<serde_json::Value> = response.error_for_status()?.json()?;
+        Ok(commits.is_empty())
+    }
+
+    /// Sends a request, retrying 5xx and 429 answers up to `MAX_RETRIES` times.
+    ///
+    /// Waits as long as `Retry-After` asks (capped at `MAX_RETRY_DELAY`), or backs
+    /// off exponentially from `RETRY_BASE_DELAY` when it isn't given. The last
+    /// response is returned as is, so callers still see the final error status.
+    fn send_with_retry(
+        &self,
+        request: impl Fn() -> RequestBuilder,
+    ) -> Result<Response, reqwest::Error> {
+        let mut attempt = 0;
+        loop {
+            let response = trace_http_response(request().send()?, &self.token)?;
+            warn_if_low(response.headers());
+            let status = response.status();
+            if attempt >= MAX_RETRIES
+                || !(status.is_server_error() || status == StatusCode::TOO_MANY_REQUESTS)
+            {
+                return Ok(response);
+            }
+
+            let retry_after = response
+                .headers()
+                .get(RETRY_AFTER)
+                .and_then(|v| v.to_str().ok());
+            let delay = retry_delay(retry_after, attempt);
+            debug!(
+                "{} answered {status}, retrying in {delay:?} ({}/{MAX_RETRIES})",
+                response.url(),
+                attempt + 1
+            );
+            std::thread::sleep(delay);
+            attempt += 1;
+        }
+    }
+
     /// Performs a paginated GET request and collects all items.
     fn fetch_paginated<T: for<'de> Deserialize<'de>>(
         &self,
         path: &str,
     ) -> Result<Vec<T>, reqwest::Error> {
-        let url = self.api_base.join(path).unwrap();
+        self.fetch_paginated_with(path, MAX_PER_PAGE, None)
+    }
+
+    /// Like `fetch_paginated`, but with `per_page` items per request and at
+    /// most `max_items` in total. Stops requesting pages once the cap is hit.
+    pub fn fetch_paginated_with<T: for<'de> Deserialize<'de>>(
+        &self,
+        path: &str,
+        per_page: usize,
+        max_items: Option<usize>,
+    ) -> Result<Vec<T>, reqwest::Error> {
         let mut results = Vec::new();
+        self.for_each_page(path, per_page, |page| {
+            results.extend(page);
+            match max_items {
+                Some(max) if results.len() >= max => {
+                    results.truncate(max);
+                    ControlFlow::Break(())
+                }
+                _ => ControlFlow::Continue(()),
+            }
+        })?;
+        Ok(results)
+    }
+
+    /// Walks a paginated GET request one page at a time.
+    ///
+    /// `on_page` gets the items of each page in order and can return
+    /// `ControlFlow::Break` to stop before the remaining pages are fetched,
+    /// e.g. once a date cutoff is passed. `per_page` is clamped to 1..=100.
+    pub fn for_each_page<T, F>(
+        &self,
+        path: &str,
+        per_page: usize,
+        mut on_page: F,
+    ) -> Result<(), reqwest::Error>
+    where
+        T: for<'de> Deserialize<'de>,
+        F: FnMut(Vec<T>) -> ControlFlow<()>,
+    {
+        let url = self.api_base.join(path).unwrap();
+        let per_page = per_page.clamp(1, MAX_PER_PAGE);
         let mut page = 1;
 
         loop {
             let mut current_url = url.clone();
             current_url
                 .query_pairs_mut()
-                .append_pair("per_page", "100")
+                .append_pair("per_page", &per_page.to_string())
                 .append_pair("page", &page.to_string());
 
-            let response: Response = self
-                .client
-                .get(current_url)
-                .bearer_auth(&self.token)
-                .header("Accept", "application/vnd.github+json")
-                .header("X-GitHub-Api-Version", "2022-11-28")
-                .send()?;
+            self.trace_request("GET", &current_url, None);
+
+            let response = self.send_with_retry(|| {
+                self.client
+                    .get(current_url.clone())
+                    .bearer_auth(&self.token)
+                    .header("Accept", "application/vnd.github+json")
+                    .header("X-GitHub-Api-Version", "2022-11-28")
+            })?;
 
             if response.status().is_success() {
                 let json_data: serde_json::Value = response.json()?;
 
-                // Check for array response (common for listing items)
-                if let Some(array) = json_data.as_array() {
-                    for item in array {
-                        results.push(serde_json::from_value(item.clone()).unwrap());
-                    }
-                    if array.is_empty() || array.len() < 100 {
-                        break; // End of pagination
-                    }
-                }
-                // Check for object response with 'workflow_runs' field (specific to workflow API)
-                else if let Some(runs) = json_data["workflow_runs"].as_array() {
-                    for item in runs {
-                        results.push(serde_json::from_value(item.clone()).unwrap());
-                    }
-                    if runs.is_empty() || runs.len() < 100 {
-                        break; // End of pagination
-                    }
-                } else {
+                // Check for array response (common for listing items), then for an
+                // object response with 'workflow_runs' field (specific to workflow API)
+                let Some(items) = json_data
+                    .as_array()
+                    .or_else(|| json_data["workflow_runs"].as_array())
+                else {
                     break; // Unexpected response structure, stop
+                };
+
+                let last = items.len() < per_page; // End of pagination
+                let items = items
+                    .iter()
+                    .map(|item| serde_json::from_value(item.clone()).unwrap())
+                    .collect();
+
+                if on_page(items).is_break() || last {
+                    break;
                 }
 
                 page += 1;
@@ -119,14 +378,15 @@ impl GitHubClient {
             }
         }
 
-        Ok(results)
+        Ok(())
     }
 
-    // /// Performs a simple blocking DELETE request.
-    /*
+    /// Performs a simple blocking DELETE request.
     fn delete(&self, path: &str) -> Result<(), reqwest::Error> {
         let url = self.api_base.join(path).unwrap();
 
+        self.trace_request("DELETE", &url, None);
+
         let response = self
             .client
             .delete(url)
@@ -134,6 +394,7 @@ impl GitHubClient {
             .header("Accept", "application/vnd.github+json")
             .header("X-GitHub-Api-Version", "2022-11-28")
             .send()?;
+        let response = trace_http_response(response, &self.token)?;
 
         // Treat 204 No Content and 202 Accepted as success
         if response.status().is_success()
@@ -145,7 +406,39 @@ impl GitHubClient {
             Err(response.error_for_status().unwrap_err())
         }
     }
-    */
+
+    /// Performs a simple blocking GET request for a single resource.
+    fn get<R: for<'de> Deserialize<'de>>(&self, path: &str) -> Result<R, reqwest::Error> {
+        let url = self.api_base.join(path).unwrap();
+
+        self.trace_request("GET", &url, None);
+
+        let response = self.send_with_retry(|| {
+            self.client
+                .get(url.clone())
+                .bearer_auth(&self.token)
+                .header("Accept", "application/vnd.github+json")
+                .header("X-GitHub-Api-Version", "2022-11-28")
+        })?;
+
+        response.error_for_status()?.json()
+    }
+
+    /// Performs a GET request for a non-JSON representation, e.g. a raw file
+    /// or a diff, and returns the response whatever its status.
+    fn get_with_accept(&self, path: &str, accept: &str) -> Result<Response, reqwest::Error> {
+        let url = self.api_base.join(path).unwrap();
+
+        self.trace_request("GET", &url, None);
+
+        self.send_with_retry(|| {
+            self.client
+                .get(url.clone())
+                .bearer_auth(&self.token)
+                .header("Accept", accept)
+                .header("X-GitHub-Api-Version", "2022-11-28")
+        })
+    }
 
     /// Performs a simple blocking POST request.
     fn post<T: Serialize, R: for<'de> Deserialize<'de>>(
@@ -155,6 +448,11 @@ impl GitHubClient {
     ) -> Result<R, reqwest::Error> {
         let url = self.api_base.join(path).unwrap();
 
+        if self.print_curl || trace_http() {
+            let body = serde_json::to_string(body).unwrap_or_default();
+            self.trace_request("POST", &url, Some(&body));
+        }
+
         let response = self
             .client
             .post(url)
@@ -163,9 +461,100 @@ impl GitHubClient {
             .header("X-GitHub-Api-Version", "2022-11-28")
             .json(body)
             .send()?;
+        let response = trace_http_response(response, &self.token)?;
+
+        response.error_for_status()?.json()
+    }
+
+    /// Performs a simple blocking PUT request.
+    fn put<T: Serialize, R: for<'de> Deserialize<'de>>(
+        &self,
+        path: &str,
+        body: &T,
+    ) -> Result<R, reqwest::Error> {
+        let url = self.api_base.join(path).unwrap();
+
+        if self.print_curl || trace_http() {
+            let body = serde_json::to_string(body).unwrap_or_default();
+            self.trace_request("PUT", &url, Some(&body));
+        }
+
+        let response = self
+            .client
+            .put(url)
+            .bearer_auth(&self.token)
+            .header("Accept", "application/vnd.github+json")
+            .header("X-GitHub-Api-Version", "2022-11-28")
+            .json(body)
+            .send()?;
+        let response = trace_http_response(response, &self.token)?;
 
         response.error_for_status()?.json()
     }
+
+    /// Performs a simple blocking PATCH request.
+    fn patch<T: Serialize, R: for<'de> Deserialize<'de>>(
+        &self,
+        path: &str,
+        body: &T,
+    ) -> Result<R, reqwest::Error> {
+        self.send_json_with_retry(Method::PATCH, path, body)?
+            .error_for_status()?
+            .json()
+    }
+
+    /// Sends a JSON body once and returns the response whatever its status.
+    fn send_json<T: Serialize>(
+        &self,
+        method: Method,
+        path: &str,
+        body: &T,
+    ) -> Result<Response, reqwest::Error> {
+        let url = self.api_base.join(path).unwrap();
+
+        if self.print_curl || trace_http() {
+            let body = serde_json::to_string(body).unwrap_or_default();
+            self.trace_request(method.as_str(), &url, Some(&body));
+        }
+
+        let response = self
+            .client
+            .request(method, url)
+            .bearer_auth(&self.token)
+            .header("Accept", "application/vnd.github+json")
+            .header("X-GitHub-Api-Version", "2022-11-28")
+            .json(body)
+            .send()?;
+        warn_if_low(response.headers());
+        trace_http_response(response, &self.token)
+    }
+
+    /// Sends a JSON body, retrying like `send_with_retry`, and returns the
+    /// response whatever its status so callers can inspect validation errors.
+    ///
+    /// Only for requests that are safe to repeat.
+    fn send_json_with_retry<T: Serialize>(
+        &self,
+        method: Method,
+        path: &str,
+        body: &T,
+    ) -> Result<Response, reqwest::Error> {
+        let url = self.api_base.join(path).unwrap();
+
+        if self.print_curl || trace_http() {
+            let body = serde_json::to_string(body).unwrap_or_default();
+            self.trace_request(method.as_str(), &url, Some(&body));
+        }
+
+        self.send_with_retry(|| {
+            self.client
+                .request(method.clone(), url.clone())
+                .bearer_auth(&self.token)
+                .header("Accept", "application/vnd.github+json")
+                .header("X-GitHub-Api-Version", "2022-11-28")
+                .json(body)
+        })
+    }
 }
 
 #[cfg(test)]
@@ -0,0 +1,61 @@
+use crate::cli::OrgArgs;
+use crate::github::GitHubClient;
+use crate::utils::glob_match;
+use serde::Deserialize;
+
+#[derive(Debug, Deserialize)]
+pub struct OrgRepo {
+    pub name: String,
+    pub full_name: String,
+    #[serde(default)]
+    pub archived: bool,
+}
+
+/// Lists every repository in an organization.
+pub fn list_org_repos(client: &GitHubClient, org: &str) -> Result<Vec<OrgRepo>, reqwest::Error> {
+    client.fetch_paginated::<OrgRepo>(&format!("orgs/{org}/repos"))
+}
+
+/// Picks the repos to work on, as `owner/repo`.
+///
+/// A repo is kept when its name matches any `include` glob (or `include` is
+/// empty) and no `exclude` glob. Archived repos are dropped unless
+/// `include_archived` is set.
+#[must_use]
+pub fn filter_org_repos(
+    repos: Vec<OrgRepo>,
+    include: &[String],
+    exclude: &[String],
+    include_archived: bool,
+) -> Vec<String> {
+    let matches = |pattern: &String, repo: &OrgRepo| {
+        glob_match(pattern, &repo.name) || glob_match(pattern, &repo.full_name)
+    };
+
+    let mut names: Vec<String> = repos
+        .into_iter()
+        .filter(|r| include_archived || !r.archived)
+        .filter(|r| include.is_empty() || include.iter().any(|p| matches(p, r)))
+        .filter(|r| !exclude.iter().any(|p| matches(p, r)))
+        .map(|r| r.full_name)
+        .collect();
+    names.sort();
+    names
+}
+
+/// Lists and filters the repos selected by `--org`, `--include` and `--exclude`.
+pub fn select_org_repos(client: &GitHubClient, args: &OrgArgs) -> anyhow::Result<Vec<String>> {
+    let Some(org) = &args.org else {
+        return Ok(vec![]);
+    };
+
+    let repos = list_org_repos(client, org)?;
+    let total = repos.len();
+    let selected = filter_org_repos(repos, &args.include, &args.exclude, args.include_archived);
+    eprintln!(
+        "Found {total} repositories in {org}, {} selected",
+        selected.len()
+    );
+
+    Ok(selected)
+}
@@ -0,0 +1,119 @@
+use crate::cli::MergeMethod;
+use crate::github::{GitHubClient, PullRequest};
+use anyhow::Context;
+use reqwest::StatusCode;
+use serde::Deserialize;
+use strum::Display;
+
+/// Where a repo keeps its own `merge` settings.
+pub const REPO_POLICY_PATH: &str = ".github/automerge.toml";
+
+/// How far a dependency update moves the version.
+#[derive(Clone, Copy, Debug, Display, PartialEq, Eq, PartialOrd, Ord, Deserialize)]
+#[strum(serialize_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum Bump {
+    Patch,
+    Minor,
+    Major,
+}
+
+impl Bump {
+    /// The bump of a Dependabot title such as "Bump serde from 1.0.1 to 1.1.0".
+    ///
+    /// `None` when the title doesn't name both versions, e.g. for grouped updates.
+    #[must_use]
+    pub fn from_title(title: &str) -> Option<Self> {
+        let words: Vec<&str> = title.split_whitespace().collect();
+        let from = words.iter().rposition(|w| w.eq_ignore_ascii_case("from"))?;
+        let (old, to, new) = (
+            words.get(from + 1)?,
+            words.get(from + 2)?,
+            words.get(from + 3)?,
+        );
+        if !to.eq_ignore_ascii_case("to") {
+            return None;
+        }
+
+        let (old, new) = (version_parts(old)?, version_parts(new)?);
+        match old.iter().zip(&new).position(|(a, b)| a != b) {
+            Some(0) => Some(Self::Major),
+            Some(1) => Some(Self::Minor),
+            _ => Some(Self::Patch),
+        }
+    }
+}
+
+/// `v1.2.3-rc.1` -> `[1, 2, 3]`
+fn version_parts(version: &str) -> Option<Vec<u64>> {
+    let version = version.trim_start_matches(['v', 'V']);
+    let core = version.split(['-', '+']).next()?;
+    core.split('.').map(|part| part.parse().ok()).collect()
+}
+
+/// A repo's `.github/automerge.toml`. Whatever it sets wins over the flags
+/// and the config file for that repo.
+///
+/// ```toml
+/// merge_method = "rebase"
+/// max_bump = "minor"
+/// require_label = "automerge"
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+pub struct RepoPolicy {
+    pub merge_method: Option<MergeMethod>,
+    /// Skip updates bigger than this; updates of unknown size are skipped too
+    pub max_bump: Option<Bump>,
+    /// Only merge PRs that carry this label
+    pub require_label: Option<String>,
+}
+
+impl RepoPolicy {
+    pub fn parse(content: &str) -> Result<Self, toml::de::Error> {
+        toml::from_str(content)
+    }
+
+    /// Why the policy rules the PR out, if it does.
+    #[must_use]
+    pub fn skip_reason(&self, pr: &PullRequest) -> Option<String> {
+        if let Some(label) = &self.require_label
+            && !pr.labels.iter().any(|l| l.name == *label)
+        {
+            return Some(format!("missing the '{label}' label"));
+        }
+
+        if let Some(max) = self.max_bump {
+            match Bump::from_title(&pr.title) {
+                Some(bump) if bump > max => {
+                    return Some(format!("{bump} update, max_bump is {max}"));
+                }
+                Some(_) => {}
+                None => return Some(format!("can't tell the update size, max_bump is {max}")),
+            }
+        }
+
+        None
+    }
+}
+
+impl GitHubClient {
+    /// Reads the repo's `REPO_POLICY_PATH` from its default branch.
+    ///
+    /// `None` when the repo has no such file; an unreadable file is an error
+    /// rather than silently merging against the repo's wishes.
+    pub fn repo_policy(&self, repo: &str) -> anyhow::Result<Option<RepoPolicy>> {
+        let response = self.get_with_accept(
+            &format!("repos/{repo}/contents/{REPO_POLICY_PATH}"),
+            "application/vnd.github.raw+json",
+        )?;
+
+        if response.status() == StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+
+        let content = response.error_for_status()?.text()?;
+        RepoPolicy::parse(&content)
+            .map(Some)
+            .with_context(|| format!("Invalid {REPO_POLICY_PATH} in {repo}"))
+    }
+}
@@ -1,103 +1,563 @@
-use crate::github::{Client, DEPENDABOT_USER, User};
+use crate::cli::{MergeMethod, OnConflict};
+use crate::github::{
+    DEPENDABOT_USER, GitHubClient, MAX_MERGE_ATTEMPTS, MergeError, UPDATE_WAIT_SECS, User,
+};
+use crate::plugins::{self, Event, Plugin, Veto};
+use chrono::{DateTime, NaiveDate, NaiveTime, Utc};
+use reqwest::Method;
 use serde::Deserialize;
-use std::process::{Command, exit};
+use std::collections::HashMap;
+use std::thread;
+use std::time::Duration;
+use tokio::runtime::Handle;
 
 #[derive(Deserialize, Debug, PartialEq, Eq)]
 pub struct PullRequest {
     pub number: u64,
     pub title: String,
     pub user: User,
+    /// Branch the PR targets
+    #[serde(default)]
+    pub base_ref: Option<String>,
+    /// Branch the PR merges from, e.g. `dependabot/cargo/serde-1.0.200`
+    #[serde(default)]
+    pub head_ref: Option<String>,
+    /// Last time the PR was updated
+    #[serde(default, deserialize_with = "deserialize_timestamp")]
+    pub updated_at: Option<DateTime<Utc>>,
+    /// Draft PRs can't be merged until marked ready
+    #[serde(default)]
+    pub draft: bool,
+    #[serde(default)]
+    pub labels: Vec<Label>,
 }
 
-pub fn list_dependabot_prs(
-    _client: &Client,
-    repo: &str,
-    _token: &str,
-) -> anyhow::Result<Vec<PullRequest>> {
-    let output = Command::new("gh")
-        .args([
-            "pr",
-            "list",
-            "--repo",
-            repo,
-            "--state",
-            "open",
-            "--author",
-            DEPENDABOT_USER,
-            "--json",
-            "number,title,author",
-        ])
-        .output()?;
-
-    if !output.status.success() {
-        eprintln!(
-            "❌ `gh pr list` failed: {}",
-            String::from_utf8_lossy(&output.stderr)
-        );
-        exit(1);
+#[derive(Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct Label {
+    pub name: String,
+}
+
+fn deserialize_timestamp<'de, D>(deserializer: D) -> Result<Option<DateTime<Utc>>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    Option::<String>::deserialize(deserializer)?
+        .map(|s| parse_timestamp(&s).map_err(serde::de::Error::custom))
+        .transpose()
+}
+
+/// Parses an RFC 3339 timestamp (`2024-05-01T12:00:00Z`) or a plain date
+/// (`2024-05-01`, taken as midnight UTC).
+pub fn parse_timestamp(input: &str) -> Result<DateTime<Utc>, String> {
+    if let Ok(ts) = DateTime::parse_from_rfc3339(input) {
+        return Ok(ts.with_timezone(&Utc));
+    }
+    NaiveDate::parse_from_str(input, "%Y-%m-%d")
+        .map(|d| d.and_time(NaiveTime::MIN).and_utc())
+        .map_err(|_| {
+            format!("invalid date '{input}': use RFC 3339 (2024-05-01T00:00:00Z) or YYYY-MM-DD")
+        })
+}
+
+/// Settings for a merge run, shared by every repository.
+#[derive(Debug, Clone, Copy)]
+pub struct MergeOptions {
+    /// Also merge PRs that target branches other than the default branch
+    pub all_bases: bool,
+    pub merge_method: MergeMethod,
+    /// Leave PRs that change `.github/workflows/` alone
+    pub skip_workflow_changes: bool,
+    /// Only merge PRs updated at or after this time
+    pub since: Option<DateTime<Utc>>,
+    /// Try to merge draft PRs too
+    pub include_draft: bool,
+    /// Approve each PR before merging it, for rulesets that require a review
+    pub approve: bool,
+    /// What to ask Dependabot to do with conflicting PRs
+    pub on_conflict: OnConflict,
+    /// Delete the head branch after a successful merge
+    pub delete_branch: bool,
+    /// Ask before merging each PR
+    pub interactive: bool,
+    /// With `interactive`: print each PR's diff before asking
+    pub show_diff: bool,
+}
+
+impl MergeOptions {
+    /// Whether the PR was updated recently enough for `--since`.
+    ///
+    /// PRs without an update time are kept, since there is no telling.
+    #[must_use]
+    pub fn is_recent(&self, pr: &PullRequest) -> bool {
+        match (self.since, pr.updated_at) {
+            (Some(since), Some(updated)) => updated >= since,
+            _ => true,
+        }
+    }
+
+    /// Whether a draft PR should be attempted (see `--include-draft`).
+    #[must_use]
+    pub const fn allows_draft(&self, pr: &PullRequest) -> bool {
+        self.include_draft || !pr.draft
+    }
+
+    /// Why these options rule the PR out (a draft, or too old for `--since`), if they do.
+    #[must_use]
+    pub fn skip_reason(&self, pr: &PullRequest) -> Option<String> {
+        if !self.allows_draft(pr) {
+            Some("draft".into())
+        } else if !self.is_recent(pr) {
+            Some("not updated since the --since cutoff".into())
+        } else {
+            None
+        }
     }
+}
 
+/// Get the default branch of a repository (e.g. `main`) via `GET /repos/{repo}`
+pub fn default_branch(client: &GitHubClient, repo: &str) -> anyhow::Result<String> {
     #[derive(Deserialize)]
-    struct RawPR {
+    struct RepoInfo {
+        #[serde(default)]
+        default_branch: String,
+    }
+
+    let info: RepoInfo = client.get(&format!("repos/{repo}"))?;
+    if info.default_branch.is_empty() {
+        anyhow::bail!("Could not determine default branch for {repo}");
+    }
+
+    Ok(info.default_branch)
+}
+
+/// Merge methods a repository allows, as reported by `GET /repos/{repo}`
+#[derive(Deserialize, Debug, Default)]
+#[serde(default)]
+pub struct RepoMergeSettings {
+    pub allow_squash_merge: bool,
+    pub allow_rebase_merge: bool,
+    pub allow_merge_commit: bool,
+}
+
+impl RepoMergeSettings {
+    /// Pick the preferred allowed method: squash > rebase > merge
+    #[must_use]
+    pub const fn preferred(&self) -> Option<MergeMethod> {
+        if self.allow_squash_merge {
+            Some(MergeMethod::Squash)
+        } else if self.allow_rebase_merge {
+            Some(MergeMethod::Rebase)
+        } else if self.allow_merge_commit {
+            Some(MergeMethod::Merge)
+        } else {
+            None
+        }
+    }
+}
+
+/// Turn the requested method into a concrete one.
+///
+/// `Auto` consults the repo settings, so call this once per run rather than per PR.
+pub fn resolve_merge_method(
+    client: &GitHubClient,
+    repo: &str,
+    method: MergeMethod,
+) -> anyhow::Result<MergeMethod> {
+    if method != MergeMethod::Auto {
+        return Ok(method);
+    }
+
+    let settings: RepoMergeSettings = client.get(&format!("repos/{repo}"))?;
+    settings
+        .preferred()
+        .ok_or_else(|| anyhow::anyhow!("{repo} does not allow any merge method"))
+}
+
+/// Whether any of the paths are GitHub Actions workflow files.
+#[must_use]
+pub fn touches_workflows(files: &[String]) -> bool {
+    files.iter().any(|f| f.starts_with(".github/workflows/"))
+}
+
+/// Files changed by PRs, fetched once per PR via `GET /repos/{repo}/pulls/{n}/files`.
+#[derive(Debug, Default)]
+pub struct PrFileCache {
+    files: HashMap<(String, u64), Vec<String>>,
+}
+
+impl PrFileCache {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Paths changed by the PR, from the cache when already fetched.
+    pub fn get(
+        &mut self,
+        client: &GitHubClient,
+        repo: &str,
         number: u64,
-        title: String,
-        author: RawAuthor,
+    ) -> Result<&[String], reqwest::Error> {
+        let key = (repo.to_string(), number);
+        if !self.files.contains_key(&key) {
+            let files = pr_files(client, repo, number)?;
+            self.files.insert(key.clone(), files);
+        }
+        Ok(&self.files[&key])
     }
+}
 
+fn pr_files(client: &GitHubClient, repo: &str, number: u64) -> Result<Vec<String>, reqwest::Error> {
     #[derive(Deserialize)]
-    struct RawAuthor {
-        login: String,
+    struct PrFile {
+        filename: String,
     }
 
-    let raw: Vec<RawPR> = serde_json::from_slice(&output.stdout)?;
+    let files: Vec<PrFile> =
+        client.fetch_paginated(&format!("repos/{repo}/pulls/{number}/files"))?;
+    Ok(files.into_iter().map(|f| f.filename).collect())
+}
+
+/// Open Dependabot PRs of `repo`, via `GET /repos/{repo}/pulls?state=open`.
+pub fn list_dependabot_prs(client: &GitHubClient, repo: &str) -> anyhow::Result<Vec<PullRequest>> {
+    #[derive(Deserialize)]
+    struct RawRef {
+        #[serde(rename = "ref")]
+        name: String,
+    }
+
+    #[derive(Deserialize)]
+    struct RawPR {
+        number: u64,
+        title: String,
+        user: User,
+        base: Option<RawRef>,
+        head: Option<RawRef>,
+        #[serde(default, deserialize_with = "deserialize_timestamp")]
+        updated_at: Option<DateTime<Utc>>,
+        #[serde(default)]
+        draft: bool,
+        #[serde(default)]
+        labels: Vec<Label>,
+    }
+
+    let raw: Vec<RawPR> = client.fetch_paginated(&format!("repos/{repo}/pulls?state=open"))?;
 
     let prs = raw
         .into_iter()
+        .filter(|r| r.user.login == DEPENDABOT_USER)
         .map(|r| PullRequest {
             number: r.number,
             title: r.title,
-            user: User {
-                login: r.author.login,
-            },
+            user: r.user,
+            base_ref: r.base.map(|b| b.name),
+            head_ref: r.head.map(|h| h.name),
+            updated_at: r.updated_at,
+            draft: r.draft,
+            labels: r.labels,
         })
         .collect();
 
     Ok(prs)
 }
 
-pub fn process_pr(
-    _client: &Client,
+/// Whether GitHub reports merge conflicts for the PR (`mergeable_state: dirty`).
+///
+/// Other states, including `unknown` while GitHub is still checking, count as no.
+pub fn has_conflicts(
+    client: &GitHubClient,
     repo: &str,
-    _token: &str,
-    pr: &PullRequest,
-) -> anyhow::Result<bool> {
-    let pr_id = pr.number.to_string();
+    number: u64,
+) -> Result<bool, reqwest::Error> {
+    let pr: serde_json::Value = client.get(&format!("repos/{repo}/pulls/{number}"))?;
+    Ok(pr["mergeable_state"] == "dirty")
+}
+
+/// Comment `@dependabot <command>` on a PR, e.g. to have it rebased.
+pub fn comment_dependabot(
+    client: &GitHubClient,
+    repo: &str,
+    number: u64,
+    command: &str,
+) -> Result<(), reqwest::Error> {
+    client
+        .post::<_, serde_json::Value>(
+            &format!("repos/{repo}/issues/{number}/comments"),
+            &serde_json::json!({ "body": format!("@dependabot {command}") }),
+        )
+        .map(drop)
+}
+
+/// What `delete_head_branch` did with a merged PR's branch.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BranchCleanup {
+    Deleted,
+    /// Already gone, e.g. the repo deletes head branches on merge
+    AlreadyGone,
+    /// Protected branches are left alone
+    Protected,
+}
+
+/// Delete a merged PR's head branch via `DELETE /repos/{repo}/git/refs/heads/{branch}`.
+pub fn delete_head_branch(
+    client: &GitHubClient,
+    repo: &str,
+    branch: &str,
+) -> Result<BranchCleanup, reqwest::Error> {
+    let info = match client.get::<serde_json::Value>(&format!("repos/{repo}/branches/{branch}")) {
+        Ok(info) => info,
+        Err(e) if e.status() == Some(reqwest::StatusCode::NOT_FOUND) => {
+            return Ok(BranchCleanup::AlreadyGone);
+        }
+        Err(e) => return Err(e),
+    };
+    if info["protected"] == true {
+        return Ok(BranchCleanup::Protected);
+    }
+
+    match client.delete(&format!("repos/{repo}/git/refs/heads/{branch}")) {
+        Ok(()) => Ok(BranchCleanup::Deleted),
+        // GitHub answers 422 for a ref that no longer exists
+        Err(e) if e.status() == Some(reqwest::StatusCode::UNPROCESSABLE_ENTITY) => {
+            Ok(BranchCleanup::AlreadyGone)
+        }
+        Err(e) => Err(e),
+    }
+}
+
+/// Approve a PR by posting an `APPROVE` review.
+///
+/// GitHub rejects approving your own PR, so the token must belong to someone
+/// other than the author. That always holds for Dependabot PRs.
+pub fn approve_pr(client: &GitHubClient, repo: &str, number: u64) -> Result<(), reqwest::Error> {
+    client
+        .post::<_, serde_json::Value>(
+            &format!("repos/{repo}/pulls/{number}/reviews"),
+            &serde_json::json!({ "event": "APPROVE" }),
+        )
+        .map(drop)
+}
+
+/// The PR's changes as a unified diff.
+pub fn pr_diff(client: &GitHubClient, repo: &str, number: u64) -> Result<String, reqwest::Error> {
+    client
+        .get_with_accept(
+            &format!("repos/{repo}/pulls/{number}"),
+            "application/vnd.github.v3.diff",
+        )?
+        .error_for_status()?
+        .text()
+}
+
+/// Colors a unified diff for the terminal like `ghk diff`, keeping at most
+/// `max_lines` lines and noting how many were left out.
+#[must_use]
+pub fn render_diff(diff: &str, max_lines: usize, color: bool) -> String {
+    use std::fmt::Write as _;
+
+    let mut out = String::new();
+    for line in diff.lines().take(max_lines) {
+        let code = if line.starts_with("diff --git") {
+            "1;35" // file header
+        } else if line.starts_with("+++")
+            || line.starts_with("---")
+            || line.starts_with("index ")
+            || line.starts_with("new file")
+            || line.starts_with("deleted file")
+            || line.starts_with("similarity")
+            || line.starts_with("rename ")
+        {
+            "33" // metadata
+        } else if line.starts_with("@@") {
+            "1;36" // hunk header
+        } else if line.starts_with('+') {
+            "32"
+        } else if line.starts_with('-') {
+            "31"
+        } else {
+            ""
+        };
+
+        if color && !code.is_empty() {
+            let _ = writeln!(out, "\x1b[{code}m{line}\x1b[0m");
+        } else {
+            let _ = writeln!(out, "{line}");
+        }
+    }
+
+    let total = diff.lines().count();
+    if total > max_lines {
+        let _ = writeln!(
+            out,
+            "... {} more lines not shown, see the PR on GitHub",
+            total - max_lines
+        );
+    }
+    out
+}
+
+/// Body of a successful `PUT /repos/{repo}/pulls/{number}/merge`.
+#[derive(Deserialize, Debug)]
+pub struct MergeResponse {
+    /// The merge commit
+    pub sha: Option<String>,
+    #[serde(default)]
+    pub merged: bool,
+    #[serde(default)]
+    pub message: String,
+}
+
+/// Merge a PR through the API, classifying a refusal as a `MergeError`.
+pub fn attempt_merge(
+    client: &GitHubClient,
+    repo: &str,
+    number: u64,
+    method: MergeMethod,
+) -> Result<MergeResponse, MergeError> {
+    let method = match method {
+        MergeMethod::Rebase => "rebase",
+        MergeMethod::Merge => "merge",
+        MergeMethod::Squash | MergeMethod::Auto => "squash",
+    };
 
-    let merged = merge_pr(repo, &pr_id);
-    if merged {
-        println!("✅ Successfully merged #{}", pr_id);
+    // Not retried: a merge that went through but timed out would fail the second time
+    let response = client
+        .send_json(
+            Method::PUT,
+            &format!("repos/{repo}/pulls/{number}/merge"),
+            &serde_json::json!({ "merge_method": method }),
+        )
+        .map_err(|e| MergeError::Unknown(e.to_string()))?;
+
+    let status = response.status();
+    if status.is_success() {
+        response
+            .json()
+            .map_err(|e| MergeError::Unknown(e.to_string()))
     } else {
-        println!("❌ Failed to merge #{}", pr_id);
+        Err(MergeError::parse(
+            Some(status),
+            &response.text().unwrap_or_default(),
+        ))
     }
+}
 
-    Ok(merged)
+/// Bring the PR's head branch up to date with its base.
+///
+/// GitHub answers `202 Accepted` and updates the branch in the background.
+pub fn update_pr_branch(
+    client: &GitHubClient,
+    repo: &str,
+    number: u64,
+) -> Result<(), reqwest::Error> {
+    client
+        .send_json(
+            Method::PUT,
+            &format!("repos/{repo}/pulls/{number}/update-branch"),
+            &serde_json::json!({}),
+        )?
+        .error_for_status()
+        .map(drop)
 }
 
-fn merge_pr(repo: &str, pr_id: &str) -> bool {
-    println!("🚀 Merging PR #{}...", pr_id);
+/// How long to give GitHub to update a branch before merging again.
+fn update_wait() -> Duration {
+    if cfg!(test) {
+        Duration::ZERO
+    } else {
+        Duration::from_secs(UPDATE_WAIT_SECS)
+    }
+}
 
-    let status = Command::new("gh")
-        .args([
-            "pr",
-            "merge",
-            pr_id,
-            "--repo",
-            repo,
-            "--squash",
-            "--delete-branch",
-        ])
-        .status()
-        .expect("Failed to execute `gh pr merge`.");
+/// Merge a PR, telling plugins before and after via the `pr-merge-*` events.
+/// A plugin can veto the merge from `pr-merge-init` (`MergeError::Vetoed`).
+///
+/// When the base branch moved meanwhile, the PR branch is updated and the
+/// merge tried again, up to `MAX_MERGE_ATTEMPTS` times in all.
+pub fn process_pr(
+    client: &GitHubClient,
+    repo: &str,
+    pr: &PullRequest,
+    method: MergeMethod,
+    plugins: &[Plugin],
+) -> Result<(), MergeError> {
+    let init = Event::PrMergeInit {
+        repo: repo.to_string(),
+        pr_number: pr.number,
+        title: pr.title.clone(),
+    };
+    if let Some(veto) = gate(plugins, init) {
+        println!("⏭️ #{}: {veto}", pr.number);
+        let by = if veto.reason.is_empty() {
+            veto.plugin
+        } else {
+            format!("{}: {}", veto.plugin, veto.reason)
+        };
+        return Err(MergeError::Vetoed(by));
+    }
+
+    let mut attempt = 1;
+    let result = loop {
+        println!("🚀 Merging PR #{}...", pr.number);
+        match attempt_merge(client, repo, pr.number, method) {
+            Err(e) if e.is_retryable() && attempt < MAX_MERGE_ATTEMPTS => {
+                println!("⏳ #{}: {e}, updating the branch and retrying", pr.number);
+                if let Err(e) = update_pr_branch(client, repo, pr.number) {
+                    println!("Could not update the branch of #{}: {e}", pr.number);
+                }
+                thread::sleep(update_wait());
+                attempt += 1;
+            }
+            result => break result,
+        }
+    };
+
+    match result {
+        Ok(merged) => {
+            println!("✅ Successfully merged #{}", pr.number);
+            notify(
+                plugins,
+                Event::PrMerged {
+                    repo: repo.to_string(),
+                    pr_number: pr.number,
+                    sha: merged.sha,
+                },
+            );
+            Ok(())
+        }
+        Err(e) => {
+            println!("❌ Failed to merge #{}: {e}", pr.number);
+            notify(
+                plugins,
+                Event::PrMergeFailed {
+                    repo: repo.to_string(),
+                    pr_number: pr.number,
+                    reason: e.to_string(),
+                },
+            );
+            Err(e)
+        }
+    }
+}
+
+/// Like `notify`, but stops at the first plugin that vetoes and returns its veto.
+fn gate(plugins: &[Plugin], event: Event) -> Option<Veto> {
+    if !plugins.iter().any(|p| p.subscribes_to(&event)) {
+        return None;
+    }
+    let runtime = Handle::current();
+    tokio::task::block_in_place(|| runtime.block_on(plugins::find_veto(plugins, event)))
+}
 
-    status.success()
+/// Broadcast from synchronous merge code. Skipped entirely when no plugin wants the event.
+///
+/// Runs on the caller's tokio runtime, whose reactor Bash plugins need; `merge`
+/// enters it on its worker threads.
+fn notify(plugins: &[Plugin], event: Event) {
+    if plugins.iter().any(|p| p.subscribes_to(&event)) {
+        let runtime = Handle::current();
+        tokio::task::block_in_place(|| {
+            runtime.block_on(plugins::broadcast_event(plugins, event));
+        });
+    }
 }
@@ -0,0 +1,128 @@
+use crate::github::GitHubClient;
+use chrono::{DateTime, Local, Utc};
+use colored::Colorize;
+use reqwest::header::HeaderMap;
+use serde::{Deserialize, Serialize};
+use std::fmt::Write as _;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// Below this many remaining requests, long-running commands print a warning.
+pub const LOW_RATE_LIMIT: u64 = 100;
+
+/// One API budget, as reported by `GET /rate_limit`.
+#[derive(Debug, Clone, Copy, Deserialize, Serialize, PartialEq, Eq)]
+pub struct RateLimit {
+    pub limit: u64,
+    pub remaining: u64,
+    pub used: u64,
+    /// When the budget resets, in seconds since the epoch
+    pub reset: i64,
+}
+
+impl RateLimit {
+    /// When the budget resets.
+    #[must_use]
+    pub fn resets_at(&self) -> DateTime<Utc> {
+        DateTime::from_timestamp(self.reset, 0).unwrap_or_default()
+    }
+}
+
+/// The budgets worth knowing about before a big run.
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
+pub struct RateLimits {
+    pub core: RateLimit,
+    pub search: RateLimit,
+    #[serde(default)]
+    pub graphql: Option<RateLimit>,
+}
+
+#[derive(Deserialize)]
+struct RateLimitResponse {
+    resources: RateLimits,
+}
+
+impl GitHubClient {
+    /// Fetches the token's rate limits. This request doesn't count against them.
+    pub fn rate_limit(&self) -> Result<RateLimits, reqwest::Error> {
+        self.get::<RateLimitResponse>("rate_limit")
+            .map(|r| r.resources)
+    }
+}
+
+impl RateLimits {
+    /// A table of the budgets, with reset times relative to `now`.
+    #[must_use]
+    pub fn to_table(&self, now: DateTime<Utc>) -> String {
+        let mut out = format!(
+            "{:<8} {:>10} {:>8}  {}\n",
+            "resource", "remaining", "limit", "resets"
+        );
+        for (name, limit) in [
+            ("core", Some(self.core)),
+            ("search", Some(self.search)),
+            ("graphql", self.graphql),
+        ] {
+            let Some(limit) = limit else { continue };
+            let reset = limit.resets_at();
+            let minutes = (reset - now).num_minutes().max(0);
+            let _ = writeln!(
+                out,
+                "{name:<8} {:>10} {:>8}  {} (in {minutes} min)",
+                limit.remaining,
+                limit.limit,
+                reset.with_timezone(&Local).format("%H:%M:%S")
+            );
+        }
+        out
+    }
+
+    /// Prints the table, or JSON with `json`.
+    pub fn render(&self, json: bool) {
+        if json {
+            println!("{}", serde_json::to_string_pretty(self).unwrap_or_default());
+            return;
+        }
+
+        print!("{}", self.to_table(Utc::now()));
+        if self.core.remaining < LOW_RATE_LIMIT {
+            eprintln!(
+                "{}",
+                format!(
+                    "Only {} core requests left; wait for the reset before a big run.",
+                    self.core.remaining
+                )
+                .yellow()
+            );
+        }
+    }
+}
+
+/// Set once the low rate limit warning has been shown, so it only shows once per run.
+static WARNED: AtomicBool = AtomicBool::new(false);
+
+/// Remaining requests according to the `x-ratelimit-remaining` header.
+#[must_use]
+pub fn remaining_from_headers(headers: &HeaderMap) -> Option<u64> {
+    headers
+        .get("x-ratelimit-remaining")?
+        .to_str()
+        .ok()?
+        .parse()
+        .ok()
+}
+
+/// Warns (once) when a response says fewer than `LOW_RATE_LIMIT` requests are left.
+pub(crate) fn warn_if_low(headers: &HeaderMap) {
+    if let Some(remaining) = remaining_from_headers(headers)
+        && remaining < LOW_RATE_LIMIT
+        && !WARNED.swap(true, Ordering::Relaxed)
+    {
+        eprintln!(
+            "{}",
+            format!(
+                "Warning: only {remaining} GitHub API requests left; see `github-bot ratelimit`."
+            )
+            .yellow()
+        );
+    }
+}
@@ -1,5 +1,7 @@
-use crate::github::GitHubClient;
+use crate::github::{Cleanup, DELETE_BATCH, GitHubClient, deletion_bar};
 use colored::Colorize;
+use indicatif::ProgressBar;
+use reqwest::{Method, StatusCode};
 use serde::{Deserialize, Serialize};
 use std::process::{Command, Stdio};
 use std::thread;
@@ -38,150 +40,317 @@ pub struct CreateRelease {
     generate_release_notes: bool,
 }
 
+#[derive(Debug, Deserialize)]
+pub struct Package {
+    name: String,
+}
+
 /// Deletes untagged container versions.
-pub fn delete_old_container_versions(client: &GitHubClient, repo: &str) {
-    println!("{}", format!("Deleting old containers for {repo}").yellow());
+pub fn delete_old_container_versions(client: &GitHubClient, repo: &str) -> Cleanup {
+    eprintln!("{}", format!("Deleting old containers for {repo}").yellow());
 
     let parts: Vec<&str> = repo.split('/').collect();
     if parts.len() != 2 {
-        eprintln!("{}", format!("Error: Repository format '{repo}' is invalid. Expected 'owner/project'. Skipping container deletion.").red());
-        return;
+        let msg = format!(
+            "Repository format '{repo}' is invalid. Expected 'owner/project'. Skipping container deletion."
+        );
+        eprintln!("{}", format!("Error: {msg}").red());
+        return Cleanup::failed(msg);
     }
     let org = parts[0];
     let project = parts[1];
 
-    let path = &format!("orgs/{org}/packages/container/{project}/versions");
-    match client.fetch_paginated::<PackageVersion>(path) {
-        Ok(versions) => {
-            let untagged_versions: Vec<u64> = versions
-                .into_iter()
-                .filter_map(|v| {
-                    let tags = v
-                        .metadata
-                        .and_then(|m| m.container)
-                        .and_then(|c| c.tags)
-                        .unwrap_or_default();
-
-                    if tags.is_empty() { Some(v.id) } else { None }
-                })
-                .collect();
-
-            let count = untagged_versions.len();
-            if count > 0 {
-                let mut handles = Vec::new();
-                for id in untagged_versions {
-                    // Clone necessary parts for thread ownership
-                    let client_clone = client.client.clone();
-                    let token_clone = client.token.clone();
-                    let api_base_clone = client.api_base.clone();
-                    let org_str = org.to_string();
-                    let project_str = project.to_string();
-
-                    handles.push(thread::spawn(move || {
-                        let delete_path = format!(
-                            "orgs/{org_str}/packages/container/{project_str}/versions/{id}"
-                        );
-                        let url = api_base_clone.join(&delete_path).unwrap();
-
-                        let res = client_clone
-                            .delete(url)
-                            .bearer_auth(token_clone)
-                            .header("Accept", "application/vnd.github+json")
-                            .header("X-GitHub-Api-Version", "2022-11-28")
-                            .send();
-
-                        if let Err(e) = res {
-                            eprintln!(
-                                "{}",
-                                format!("Error deleting container version {id}: {e}").red()
-                            );
-                        }
-                    }));
-                }
-
-                // Wait for all deletions to complete
-                for h in handles {
-                    let _ = h.join();
-                }
-
-                println!(
-                    "{}",
-                    format!("{count} untagged container versions deleted.").blue()
-                );
-            } else {
-                println!(
+    let cleanup = match delete_untagged_versions(client, org, project) {
+        Ok(cleanup) if cleanup.deleted > 0 => {
+            eprintln!(
+                "{}",
+                format!("{} untagged container versions deleted.", cleanup.deleted).blue()
+            );
+            cleanup
+        }
+        Ok(cleanup) => {
+            if cleanup.errors.is_empty() {
+                eprintln!(
                     "{}",
                     "No untagged container versions found to delete.".blue()
                 );
             }
+            cleanup
         }
         Err(e) => {
-            eprintln!(
-                "{}",
-                format!(
-                    "Error fetching container versions: {e}. Check if the repo is an org package."
-                )
-                .red()
+            let msg = format!(
+                "Error fetching container versions: {e}. Check if the repo is an org package."
             );
+            eprintln!("{}", msg.red());
+            Cleanup::failed(msg)
+        }
+    };
+    eprintln!("{}", "Done.".yellow());
+    cleanup
+}
+
+/// Deletes untagged versions from every container package in an organization.
+pub fn delete_org_container_versions(client: &GitHubClient, org: &str) -> Cleanup {
+    eprintln!(
+        "{}",
+        format!("Deleting old containers for all packages in {org}").yellow()
+    );
+
+    let path = &format!("orgs/{org}/packages?package_type=container");
+    let packages = match client.fetch_paginated::<Package>(path) {
+        Ok(packages) => packages,
+        Err(e) => {
+            let msg = format!("Error listing container packages for {org}: {e}");
+            eprintln!("{}", msg.red());
+            return Cleanup::failed(msg);
+        }
+    };
+
+    if packages.is_empty() {
+        eprintln!(
+            "{}",
+            format!("No container packages found in {org}.").blue()
+        );
+        eprintln!("{}", "Done.".yellow());
+        return Cleanup::default();
+    }
+
+    let mut cleanup = Cleanup::default();
+    let mut failed = Vec::new();
+    for package in &packages {
+        if client.is_cancelled() {
+            cleanup.interrupted = true;
+            break;
+        }
+
+        match delete_untagged_versions(client, org, &package.name) {
+            Ok(package_cleanup) => {
+                eprintln!(
+                    "  {}: {} untagged versions deleted",
+                    package.name, package_cleanup.deleted
+                );
+                if !package_cleanup.errors.is_empty() {
+                    failed.push(package.name.as_str());
+                }
+                cleanup.merge(package_cleanup);
+            }
+            Err(e) => {
+                eprintln!("{}", format!("  {}: {e}", package.name).red());
+                failed.push(package.name.as_str());
+                cleanup.errors.push(format!("{}: {e}", package.name));
+            }
         }
     }
-    println!("{}", "Done.".yellow());
+
+    eprintln!(
+        "{}",
+        format!(
+            "{} packages processed, {} untagged container versions deleted, {} failed.",
+            packages.len(),
+            cleanup.deleted,
+            failed.len()
+        )
+        .blue()
+    );
+    if !failed.is_empty() {
+        eprintln!("{}", format!("Failed: {}", failed.join(", ")).red());
+    }
+    eprintln!("{}", "Done.".yellow());
+    cleanup
 }
 
-/// Deletes all releases and their corresponding Git tags.
-pub fn delete_all_releases(
+/// Deletes the untagged versions of one container package, `DELETE_BATCH` at a time.
+///
+/// Only listing the versions is an error; failed deletions are collected in the result.
+/// After Ctrl-C no new batch is started.
+fn delete_untagged_versions(
     client: &GitHubClient,
-    repo: &str,
-) -> Result<(), Box<dyn std::error::Error>> {
+    org: &str,
+    package: &str,
+) -> Result<Cleanup, reqwest::Error> {
+    let path = &format!("orgs/{org}/packages/container/{package}/versions");
+    let versions = client.fetch_paginated::<PackageVersion>(path)?;
+
+    let untagged_versions: Vec<u64> = versions
+        .into_iter()
+        .filter_map(|v| {
+            let tags = v
+                .metadata
+                .and_then(|m| m.container)
+                .and_then(|c| c.tags)
+                .unwrap_or_default();
+
+            if tags.is_empty() { Some(v.id) } else { None }
+        })
+        .collect();
+
+    let bar = deletion_bar(untagged_versions.len());
+    let mut cleanup = Cleanup::default();
+    for batch in untagged_versions.chunks(DELETE_BATCH) {
+        if client.is_cancelled() {
+            cleanup.interrupted = true;
+            break;
+        }
+        cleanup.merge(delete_versions(client, org, package, batch, &bar));
+    }
+    bar.finish_and_clear();
+
+    Ok(cleanup)
+}
+
+/// Deletes container versions concurrently and waits for all of them.
+fn delete_versions(
+    client: &GitHubClient,
+    org: &str,
+    package: &str,
+    ids: &[u64],
+    bar: &ProgressBar,
+) -> Cleanup {
+    thread::scope(|scope| {
+        let mut handles = Vec::new();
+        for &id in ids {
+            handles.push(scope.spawn(move || {
+                client
+                    .delete(&format!(
+                        "orgs/{org}/packages/container/{package}/versions/{id}"
+                    ))
+                    .map(|()| bar.inc(1))
+                    .map_err(|e| {
+                        let msg = format!("Error deleting container version {id}: {e}");
+                        eprintln!("{}", msg.red());
+                        msg
+                    })
+            }));
+        }
+
+        // Wait for all deletions to complete
+        Cleanup::join(handles)
+    })
+}
+
+/// What `delete_all_releases` removed.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct ReleaseCleanup {
+    pub releases: Cleanup,
+    pub tags: Cleanup,
+}
+
+impl ReleaseCleanup {
+    /// Whether everything was deleted without errors or a Ctrl-C.
+    #[must_use]
+    pub const fn is_complete(&self) -> bool {
+        self.releases.errors.is_empty()
+            && self.tags.errors.is_empty()
+            && !self.releases.interrupted
+            && !self.tags.interrupted
+    }
+}
+
+/// Deletes all releases and their corresponding Git tags.
+pub fn delete_all_releases(client: &GitHubClient, repo: &str) -> ReleaseCleanup {
+    // An empty repo has no releases or tags, and cloning it yields nothing
+    if matches!(client.is_empty_repo(repo), Ok(true)) {
+        eprintln!(
+            "{}",
+            format!("Repository {repo} is empty, no releases or tags to delete.").blue()
+        );
+        return ReleaseCleanup::default();
+    }
+
     // 1. Delete releases
-    println!("{}", format!("Deleting all releases for {repo}").yellow());
+    eprintln!("{}", format!("Deleting all releases for {repo}").yellow());
     let releases_path = &format!("repos/{repo}/releases");
 
-    match client.fetch_paginated::<Release>(releases_path) {
+    let releases = match client.fetch_paginated::<Release>(releases_path) {
         Ok(releases) => {
-            let count = releases.len();
-            let mut handles = Vec::new();
-            for r in releases {
-                let client_clone = client.client.clone();
-                let token_clone = client.token.clone();
-                let api_base_clone = client.api_base.clone();
-                let repo_str = repo.to_string();
-
-                handles.push(thread::spawn(move || {
-                    let delete_path = format!("repos/{}/releases/{}", repo_str, r.id);
-                    let url = api_base_clone.join(&delete_path).unwrap();
-
-                    let res = client_clone
-                        .delete(url)
-                        .bearer_auth(token_clone)
-                        .header("Accept", "application/vnd.github+json")
-                        .header("X-GitHub-Api-Version", "2022-11-28")
-                        .send();
-
-                    if let Err(e) = res {
-                        eprintln!(
-                            "{}",
-                            format!("Error deleting release {}: {}", r.tag_name, e).red()
-                        );
-                    }
-                }));
+            let bar = deletion_bar(releases.len());
+            let mut cleanup = Cleanup::default();
+            for batch in releases.chunks(DELETE_BATCH) {
+                if client.is_cancelled() {
+                    cleanup.interrupted = true;
+                    break;
+                }
+                cleanup.merge(delete_releases(client, repo, batch, &bar));
             }
+            bar.finish_and_clear();
 
-            for h in handles {
-                let _ = h.join();
+            if cleanup.interrupted {
+                eprintln!(
+                    "{}",
+                    format!(
+                        "Interrupted: {} of {} releases deleted.",
+                        cleanup.deleted,
+                        releases.len()
+                    )
+                    .yellow()
+                );
+            } else {
+                eprintln!(
+                    "{}",
+                    format!("{} releases deleted.", cleanup.deleted).blue()
+                );
             }
-
-            println!("{}", format!("{count} releases deleted.").blue());
+            cleanup
         }
         Err(e) => {
-            eprintln!("{}", format!("Error fetching releases: {e}").red());
+            let msg = format!("Error fetching releases: {e}");
+            eprintln!("{}", msg.red());
+            Cleanup::failed(msg)
         }
+    };
+    eprintln!("{}", "Done.".yellow());
+
+    // Tags go in one push, so there is nothing to finish after Ctrl-C
+    if client.is_cancelled() {
+        let tags = Cleanup {
+            interrupted: true,
+            ..Cleanup::default()
+        };
+        return ReleaseCleanup { releases, tags };
     }
-    println!("{}", "Done.".yellow());
 
     // 2. Delete tags (using external git commands, like the original script)
-    println!("{}", format!("Deleting all tags for {repo}").yellow());
+    eprintln!("{}", format!("Deleting all tags for {repo}").yellow());
+    let tags = match delete_all_tags(repo) {
+        Ok(count) => Cleanup {
+            deleted: count,
+            ..Cleanup::default()
+        },
+        Err(e) => Cleanup::failed(e.to_string()),
+    };
+    eprintln!("{}", "Done.".yellow());
+
+    ReleaseCleanup { releases, tags }
+}
 
+/// Deletes releases concurrently and waits for all of them.
+fn delete_releases(
+    client: &GitHubClient,
+    repo: &str,
+    releases: &[Release],
+    bar: &ProgressBar,
+) -> Cleanup {
+    thread::scope(|scope| {
+        let mut handles = Vec::new();
+        for r in releases {
+            handles.push(scope.spawn(move || {
+                client
+                    .delete(&format!("repos/{repo}/releases/{}", r.id))
+                    .map(|()| bar.inc(1))
+                    .map_err(|e| {
+                        let msg = format!("Error deleting release {}: {e}", r.tag_name);
+                        eprintln!("{}", msg.red());
+                        msg
+                    })
+            }));
+        }
+
+        Cleanup::join(handles)
+    })
+}
+
+/// Deletes every tag of `repo` with one `git push --delete`, returning how many there were.
+fn delete_all_tags(repo: &str) -> Result<usize, Box<dyn std::error::Error>> {
     // Create a temporary directory
     let temp_dir = tempfile::tempdir()?;
     let temp_path = temp_dir.path();
@@ -222,12 +391,11 @@ pub fn delete_all_releases(
         .collect::<Vec<String>>();
 
     if tags.is_empty() {
-        println!("{}", "No tags found to delete.".blue());
-        println!("{}", "Done.".yellow());
-        return Ok(());
+        eprintln!("{}", "No tags found to delete.".blue());
+        return Ok(0);
     }
 
-    println!(
+    eprintln!(
         "{}",
         format!("Found {} tags. Deleting...", tags.len()).blue()
     );
@@ -257,37 +425,120 @@ pub fn delete_all_releases(
         return Err("Git push --delete failed".into());
     }
 
-    println!("{}", "Done.".yellow());
-    Ok(())
+    Ok(tags.len())
+}
+
+/// Tag of the release that the `release` action creates.
+pub const INITIAL_RELEASE_TAG: &str = "v0.1.0";
+
+/// What `create_release` did.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReleaseOutcome {
+    Created,
+    /// The release already existed and was updated (`--update-existing`)
+    Updated,
+    /// The release already existed and was left alone
+    AlreadyExists,
+    /// The repo has no commits to tag yet
+    EmptyRepo,
 }
 
-/// Creates a new v0.1.0 release.
-pub fn create_release(client: &GitHubClient, repo: &str) -> Result<(), reqwest::Error> {
+/// The body of a 422 Validation Failed answer.
+#[derive(Debug, Default, Deserialize)]
+struct ValidationFailed {
+    #[serde(default)]
+    errors: Vec<ValidationError>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ValidationError {
+    #[serde(default)]
+    code: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct ExistingRelease {
+    id: u64,
+    html_url: Option<String>,
+}
+
+/// Creates a new `INITIAL_RELEASE_TAG` release.
+///
+/// Safe to re-run: when the release already exists it is updated with
+/// `update_existing`, and otherwise left alone. Server errors are retried.
+pub fn create_release(
+    client: &GitHubClient,
+    repo: &str,
+    update_existing: bool,
+) -> Result<ReleaseOutcome, reqwest::Error> {
+    if matches!(client.is_empty_repo(repo), Ok(true)) {
+        eprintln!(
+            "{}",
+            format!("Repository {repo} is empty, push a commit before creating a release.").blue()
+        );
+        return Ok(ReleaseOutcome::EmptyRepo);
+    }
+
     let release_data = CreateRelease {
-        tag_name: "v0.1.0".to_string(),
+        tag_name: INITIAL_RELEASE_TAG.to_string(),
         target_commitish: "main".to_string(),
-        name: "v0.1.0".to_string(),
+        name: INITIAL_RELEASE_TAG.to_string(),
         body: "First release of github-rs.".to_string(),
         draft: false,
         prerelease: false,
         generate_release_notes: true,
     };
 
-    let path = &format!("repos/{repo}/releases");
-    match client.post::<_, serde_json::Value>(path, &release_data) {
-        Ok(res) => {
-            println!(
-                "{}",
-                format!("Successfully created release v0.1.0 for {repo}.").green()
-            );
-            if let Some(url) = res["html_url"].as_str() {
-                println!("Release URL: {}", url.cyan());
-            }
-        }
-        Err(e) => {
-            eprintln!("{}", format!("Error creating release: {e}").red());
+    // A retried POST whose first attempt went through also ends up as already_exists
+    let response = client.send_json_with_retry(
+        Method::POST,
+        &format!("repos/{repo}/releases"),
+        &release_data,
+    )?;
+    let Some(error) = response.error_for_status_ref().err() else {
+        let created: ExistingRelease = response.json()?;
+        eprintln!(
+            "{}",
+            format!("Successfully created release {INITIAL_RELEASE_TAG} for {repo}.").green()
+        );
+        if let Some(url) = created.html_url {
+            eprintln!("Release URL: {}", url.cyan());
         }
+        return Ok(ReleaseOutcome::Created);
+    };
+
+    // Only the tag-exists validation error is expected; anything else is a real failure
+    if response.status() != StatusCode::UNPROCESSABLE_ENTITY {
+        return Err(error);
+    }
+    let failed: ValidationFailed = response.json().unwrap_or_default();
+    if !failed.errors.iter().any(|e| e.code == "already_exists") {
+        return Err(error);
     }
 
-    Ok(())
+    if !update_existing {
+        eprintln!(
+            "{}",
+            format!(
+                "Release {INITIAL_RELEASE_TAG} already exists for {repo}, skipping (use --update-existing to update it)."
+            )
+            .yellow()
+        );
+        return Ok(ReleaseOutcome::AlreadyExists);
+    }
+
+    let existing: ExistingRelease =
+        client.get(&format!("repos/{repo}/releases/tags/{INITIAL_RELEASE_TAG}"))?;
+    let updated: ExistingRelease = client.patch(
+        &format!("repos/{repo}/releases/{}", existing.id),
+        &release_data,
+    )?;
+    eprintln!(
+        "{}",
+        format!("Updated the existing release {INITIAL_RELEASE_TAG} for {repo}.").green()
+    );
+    if let Some(url) = updated.html_url {
+        eprintln!("Release URL: {}", url.cyan());
+    }
+    Ok(ReleaseOutcome::Updated)
 }
@@ -0,0 +1,105 @@
+use crate::github::GitHubClient;
+use colored::Colorize;
+use serde::Deserialize;
+use serde_json::json;
+
+/// Name of the ruleset created for new repos and by `maintain ruleset`.
+pub const DEFAULT_RULESET_NAME: &str = "default";
+
+#[derive(Debug, Deserialize)]
+pub struct Ruleset {
+    id: u64,
+    name: String,
+}
+
+/// Whether applying the default ruleset added a new one or replaced an existing one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RulesetChange {
+    Created,
+    Updated,
+}
+
+/// The default branch ruleset: signed commits, PRs (squash or rebase), no
+/// force-pushes or deletion. Organization admins can always bypass it.
+#[must_use]
+pub fn default_ruleset() -> serde_json::Value {
+    json!({
+        "name": DEFAULT_RULESET_NAME,
+        "target": "branch",
+        "enforcement": "active",
+        "conditions": {
+            "ref_name": {
+                "include": ["~DEFAULT_BRANCH"],
+                "exclude": []
+            }
+        },
+        "bypass_actors": [
+            {
+                "actor_type": "OrganizationAdmin",
+                "bypass_mode": "always"
+            }
+        ],
+        "rules": [
+            { "type": "required_signatures", "parameters": {} },
+            { "type": "pull_request", "parameters": {
+                "dismiss_stale_reviews_on_push": false,
+                "require_code_owner_review": false,
+                "require_last_push_approval": false,
+                "required_approving_review_count": 0,
+                "required_review_thread_resolution": false,
+                "allowed_merge_methods": ["squash", "rebase"]
+            }},
+            { "type": "non_fast_forward", "parameters": {} },
+            { "type": "deletion", "parameters": {} }
+        ]
+    })
+}
+
+/// Creates the default ruleset on a repository that does not have one yet.
+pub fn create_ruleset(client: &GitHubClient, repo: &str) -> Result<(), reqwest::Error> {
+    let path = format!("repos/{repo}/rulesets");
+    client.post::<_, serde_json::Value>(&path, &default_ruleset())?;
+    Ok(())
+}
+
+/// Creates the default ruleset, or updates it in place if the repo already has one.
+pub fn apply_default_ruleset(
+    client: &GitHubClient,
+    repo: &str,
+) -> Result<RulesetChange, reqwest::Error> {
+    let path = format!("repos/{repo}/rulesets");
+    let existing = client
+        .fetch_paginated::<Ruleset>(&path)?
+        .into_iter()
+        .find(|r| r.name == DEFAULT_RULESET_NAME);
+
+    if let Some(ruleset) = existing {
+        let body = default_ruleset();
+        client.put::<_, serde_json::Value>(&format!("{path}/{}", ruleset.id), &body)?;
+        Ok(RulesetChange::Updated)
+    } else {
+        create_ruleset(client, repo)?;
+        Ok(RulesetChange::Created)
+    }
+}
+
+/// Applies the default ruleset to an existing repository, reporting the result.
+///
+/// A failure is returned as well as reported, so `maintain ruleset` fails with it.
+pub fn ensure_default_ruleset(
+    client: &GitHubClient,
+    repo: &str,
+) -> Result<RulesetChange, reqwest::Error> {
+    eprintln!("{}", format!("Applying default ruleset to {repo}").yellow());
+
+    let change = apply_default_ruleset(client, repo)
+        .inspect_err(|e| eprintln!("{}", format!("Error applying ruleset to {repo}: {e}").red()))?;
+    match change {
+        RulesetChange::Created => eprintln!("{}", "Created the default ruleset.".green()),
+        RulesetChange::Updated => {
+            eprintln!("{}", "Updated the existing default ruleset.".green());
+        }
+    }
+    eprintln!("{}", "Done.".yellow());
+    Ok(change)
+}
@@ -0,0 +1,448 @@
+use colored::Colorize;
+use serde::Serialize;
+use std::io::{IsTerminal, Write};
+use std::path::Path;
+use std::thread::ScopedJoinHandle;
+
+/// Appends `markdown` to the GitHub Actions job summary.
+///
+/// Writes to `$GITHUB_STEP_SUMMARY` when it is set. With `force` and no such
+/// file (i.e. outside Actions), the Markdown is printed to stdout instead.
+pub fn write_step_summary(markdown: &str, force: bool) -> std::io::Result<()> {
+    match std::env::var_os("GITHUB_STEP_SUMMARY") {
+        Some(path) if !path.is_empty() => append_markdown(Path::new(&path), markdown),
+        _ if force => {
+            println!("{markdown}");
+            Ok(())
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Appends `markdown` to `path`, creating it if needed.
+fn append_markdown(path: &Path, markdown: &str) -> std::io::Result<()> {
+    let mut file = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)?;
+    writeln!(file, "{markdown}")
+}
+
+/// The final state of a single PR after a merge run.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MergeOutcome {
+    Merged,
+    Skipped(String),
+    Failed(String),
+}
+
+/// A PR and what happened to it during a merge run.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MergeRecord {
+    pub repo: String,
+    pub number: u64,
+    pub title: String,
+    pub outcome: MergeOutcome,
+}
+
+/// Collects per-PR outcomes so they can be rendered once the run finishes.
+#[derive(Debug, Default)]
+pub struct MergeSummary {
+    pub records: Vec<MergeRecord>,
+}
+
+impl MergeSummary {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn record(&mut self, repo: &str, number: u64, title: &str, outcome: MergeOutcome) {
+        self.records.push(MergeRecord {
+            repo: repo.to_string(),
+            number,
+            title: title.to_string(),
+            outcome,
+        });
+    }
+
+    #[must_use]
+    pub fn merged(&self) -> Vec<&MergeRecord> {
+        self.records
+            .iter()
+            .filter(|r| r.outcome == MergeOutcome::Merged)
+            .collect()
+    }
+
+    #[must_use]
+    pub fn skipped(&self) -> Vec<&MergeRecord> {
+        self.records
+            .iter()
+            .filter(|r| matches!(r.outcome, MergeOutcome::Skipped(_)))
+            .collect()
+    }
+
+    #[must_use]
+    pub fn failed(&self) -> Vec<&MergeRecord> {
+        self.records
+            .iter()
+            .filter(|r| matches!(r.outcome, MergeOutcome::Failed(_)))
+            .collect()
+    }
+
+    /// Prints the summary.
+    ///
+    /// Uses a structured `cliclack` block on an interactive terminal and falls
+    /// back to plain text when `plain` is set or stdout is not a TTY.
+    pub fn render(&self, plain: bool) {
+        if plain || !std::io::stdout().is_terminal() {
+            self.render_plain();
+        } else if self.render_tui().is_err() {
+            self.render_plain();
+        }
+    }
+
+    /// Renders the summary as a GitHub-flavored Markdown table.
+    #[must_use]
+    pub fn to_markdown(&self) -> String {
+        use std::fmt::Write as _;
+
+        let mut md = format!(
+            "### Merge summary\n\n{} merged, {} skipped, {} failed\n",
+            self.merged().len(),
+            self.skipped().len(),
+            self.failed().len()
+        );
+
+        if self.records.is_empty() {
+            return md;
+        }
+
+        md.push_str("\n| Result | PR | Title | Details |\n|---|---|---|---|\n");
+        for (label, records) in [
+            ("✅ merged", self.merged()),
+            ("⏭️ skipped", self.skipped()),
+            ("❌ failed", self.failed()),
+        ] {
+            for r in records {
+                let _ = writeln!(
+                    md,
+                    "| {label} | {}#{} | {} | {} |",
+                    r.repo,
+                    r.number,
+                    escape_cell(&r.title),
+                    escape_cell(reason(r))
+                );
+            }
+        }
+
+        md
+    }
+
+    /// Appends the Markdown summary to the GitHub Actions job summary, see
+    /// `write_step_summary`.
+    pub fn write_step_summary(&self, force: bool) -> std::io::Result<()> {
+        write_step_summary(&self.to_markdown(), force)
+    }
+
+    /// Appends the Markdown summary to `path`, creating it if needed.
+    pub fn append_markdown(&self, path: &Path) -> std::io::Result<()> {
+        append_markdown(path, &self.to_markdown())
+    }
+
+    fn render_plain(&self) {
+        println!("\n--- Merge Summary ---");
+        println!(
+            "Merged: {}  Skipped: {}  Failed: {}",
+            self.merged().len(),
+            self.skipped().len(),
+            self.failed().len()
+        );
+
+        for r in self.merged() {
+            println!("  merged   {}#{} {}", r.repo, r.number, r.title);
+        }
+        for r in self.skipped() {
+            println!("  skipped  {}#{} {}", r.repo, r.number, r.title);
+            println!("           {}", reason(r));
+        }
+        for r in self.failed() {
+            println!("  failed   {}#{} {}", r.repo, r.number, r.title);
+            println!("           {}", reason(r));
+        }
+    }
+
+    fn render_tui(&self) -> std::io::Result<()> {
+        cliclack::intro("Merge summary")?;
+
+        let merged = self.merged();
+        if !merged.is_empty() {
+            let lines: Vec<String> = merged
+                .iter()
+                .map(|r| format!("{}#{} {}", r.repo, r.number, r.title))
+                .collect();
+            cliclack::log::success(format!(
+                "{}\n{}",
+                format!("Merged ({})", merged.len()).green().bold(),
+                lines.join("\n")
+            ))?;
+        }
+
+        let skipped = self.skipped();
+        if !skipped.is_empty() {
+            cliclack::log::warning(format!(
+                "{}\n{}",
+                format!("Skipped ({})", skipped.len()).yellow().bold(),
+                grouped_lines(&skipped)
+            ))?;
+        }
+
+        let failed = self.failed();
+        if !failed.is_empty() {
+            cliclack::log::error(format!(
+                "{}\n{}",
+                format!("Failed ({})", failed.len()).red().bold(),
+                grouped_lines(&failed)
+            ))?;
+        }
+
+        cliclack::outro(format!(
+            "{} merged, {} skipped, {} failed",
+            merged.len(),
+            skipped.len(),
+            failed.len()
+        ))?;
+
+        Ok(())
+    }
+}
+
+fn reason(r: &MergeRecord) -> &str {
+    match &r.outcome {
+        MergeOutcome::Merged => "",
+        MergeOutcome::Skipped(why) | MergeOutcome::Failed(why) => why,
+    }
+}
+
+fn grouped_lines(records: &[&MergeRecord]) -> String {
+    records
+        .iter()
+        .map(|r| {
+            format!(
+                "{}#{} {}\n  {}",
+                r.repo,
+                r.number,
+                r.title,
+                reason(r).dimmed()
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Keeps titles and reasons from breaking the Markdown table.
+fn escape_cell(text: &str) -> String {
+    text.replace('|', "\\|").replace('\n', " ")
+}
+
+/// How many things one maintenance step deleted, and the errors along the way.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize)]
+pub struct Cleanup {
+    pub deleted: usize,
+    pub errors: Vec<String>,
+    /// Stopped early by Ctrl-C, so there may be more left to delete
+    pub interrupted: bool,
+}
+
+impl Cleanup {
+    /// A step that could not run at all.
+    #[must_use]
+    pub fn failed(error: String) -> Self {
+        Self {
+            errors: vec![error],
+            ..Self::default()
+        }
+    }
+
+    /// Waits for deletion threads, counting each success and keeping each error.
+    #[must_use]
+    pub fn join(handles: Vec<ScopedJoinHandle<'_, Result<(), String>>>) -> Self {
+        let mut cleanup = Self::default();
+        for h in handles {
+            match h.join() {
+                Ok(Ok(())) => cleanup.deleted += 1,
+                Ok(Err(e)) => cleanup.errors.push(e),
+                Err(_) => cleanup.errors.push("a deletion thread panicked".into()),
+            }
+        }
+        cleanup
+    }
+
+    pub fn merge(&mut self, other: Self) {
+        self.deleted += other.deleted;
+        self.errors.extend(other.errors);
+        self.interrupted |= other.interrupted;
+    }
+}
+
+/// What `maintain` changed in one repository (or organization).
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize)]
+pub struct MaintainReport {
+    pub repo: String,
+    pub workflows_deleted: usize,
+    pub containers_deleted: usize,
+    pub releases_deleted: usize,
+    pub tags_deleted: usize,
+    pub branches_deleted: usize,
+    pub errors: Vec<String>,
+    /// Stopped early by Ctrl-C
+    pub interrupted: bool,
+}
+
+impl MaintainReport {
+    #[must_use]
+    pub fn new(repo: &str) -> Self {
+        Self {
+            repo: repo.to_string(),
+            ..Self::default()
+        }
+    }
+
+    /// Keeps the step's errors and returns how many it deleted.
+    pub fn absorb(&mut self, cleanup: Cleanup) -> usize {
+        self.errors.extend(cleanup.errors);
+        self.interrupted |= cleanup.interrupted;
+        cleanup.deleted
+    }
+}
+
+/// Collects per-repository maintenance reports so they can be rendered at the end.
+#[derive(Debug, Default, Serialize)]
+pub struct MaintainSummary {
+    pub repos: Vec<MaintainReport>,
+}
+
+impl MaintainSummary {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Counts summed over every repository; errors are prefixed with their repo.
+    #[must_use]
+    pub fn totals(&self) -> MaintainReport {
+        let mut total = MaintainReport::new("total");
+        for r in &self.repos {
+            total.workflows_deleted += r.workflows_deleted;
+            total.containers_deleted += r.containers_deleted;
+            total.releases_deleted += r.releases_deleted;
+            total.tags_deleted += r.tags_deleted;
+            total.branches_deleted += r.branches_deleted;
+            total.interrupted |= r.interrupted;
+            total
+                .errors
+                .extend(r.errors.iter().map(|e| format!("{}: {e}", r.repo)));
+        }
+        total
+    }
+
+    /// The reports and their totals as one JSON object, for `--json`.
+    #[must_use]
+    pub fn to_json(&self) -> serde_json::Value {
+        serde_json::json!({
+            "repos": self.repos,
+            "total": self.totals(),
+        })
+    }
+
+    /// Renders the summary as a GitHub-flavored Markdown table, one row per
+    /// repository plus the totals.
+    #[must_use]
+    pub fn to_markdown(&self) -> String {
+        use std::fmt::Write as _;
+
+        let total = self.totals();
+        let mut md = String::from(
+            "### Maintenance summary\n\n\
+             | Repository | Workflows | Containers | Releases | Tags | Branches | Errors |\n\
+             |---|---|---|---|---|---|---|\n",
+        );
+        for r in self.repos.iter().chain([&total]) {
+            let _ = writeln!(
+                md,
+                "| {} | {} | {} | {} | {} | {} | {} |",
+                escape_cell(&r.repo),
+                r.workflows_deleted,
+                r.containers_deleted,
+                r.releases_deleted,
+                r.tags_deleted,
+                r.branches_deleted,
+                r.errors.len()
+            );
+        }
+
+        if total.interrupted {
+            md.push_str("\nInterrupted by Ctrl-C: only the deletions above were done.\n");
+        }
+        if !total.errors.is_empty() {
+            md.push_str("\n**Errors**\n\n");
+            for e in &total.errors {
+                let _ = writeln!(md, "- {e}");
+            }
+        }
+
+        md
+    }
+
+    /// Appends the Markdown summary to the GitHub Actions job summary, see
+    /// `write_step_summary`.
+    pub fn write_step_summary(&self, force: bool) -> std::io::Result<()> {
+        write_step_summary(&self.to_markdown(), force)
+    }
+
+    /// Prints the summary, as JSON with `json`.
+    pub fn render(&self, json: bool) {
+        if json {
+            println!("{}", self.to_json());
+            return;
+        }
+
+        let total = self.totals();
+        println!("\n--- Maintenance Summary ---");
+        for r in &self.repos {
+            println!(
+                "  {}: {} workflows, {} containers, {} releases, {} tags, {} branches deleted, {} errors",
+                r.repo,
+                r.workflows_deleted,
+                r.containers_deleted,
+                r.releases_deleted,
+                r.tags_deleted,
+                r.branches_deleted,
+                r.errors.len()
+            );
+        }
+        println!(
+            "Deleted: {} workflows, {} containers, {} releases, {} tags, {} branches",
+            total.workflows_deleted,
+            total.containers_deleted,
+            total.releases_deleted,
+            total.tags_deleted,
+            total.branches_deleted
+        );
+        if total.interrupted {
+            eprintln!(
+                "{}",
+                "Interrupted by Ctrl-C: only the deletions above were done; run again for the rest."
+                    .yellow()
+            );
+        }
+        if total.errors.is_empty() {
+            println!("{}", "No errors".green());
+        } else {
+            eprintln!("{}", format!("Errors ({}):", total.errors.len()).red());
+            for e in &total.errors {
+                eprintln!("  {e}");
+            }
+        }
+    }
+}
@@ -4,7 +4,7 @@ use reqwest::blocking::Client;
 use reqwest::header::{ACCEPT, AUTHORIZATION, CONTENT_TYPE, USER_AGENT};
 
 use crate::github::pr::{MergeResponse, PullRequest};
-use crate::github::{DEPENDABOT_USER, User};
+use crate::github::{DEPENDABOT_USER, GitHubClient, RerunMode, User};
 
 const REPO: &str = "test_owner/test_repo";
 const TOKEN: &str = "test_token";
@@ -64,7 +64,7 @@ fn test_list_dependabot_prs_success() -> Result<()> {
         .get(&url)
         .header(AUTHORIZATION, format!("Bearer {}", TOKEN))
         .header(ACCEPT, "application/vnd.github.v3+json")
-        .header(USER_AGENT, "DependabotAutoMerger")
+        .header(USER_AGENT, super::USER_AGENT_VALUE)
         .send()
         .context("Failed to send list PRs request")?;
 
@@ -117,6 +117,11 @@ fn test_attempt_merge_success() -> Result<()> {
         user: User {
             login: DEPENDABOT_USER.to_string(),
         },
+        base_ref: Some("main".to_string()),
+        head_ref: Some("dependabot/cargo/serde-1.0.200".to_string()),
+        updated_at: None,
+        draft: false,
+        labels: vec![],
     };
 
     let mock_base = server.url();
@@ -134,7 +139,7 @@ fn test_attempt_merge_success() -> Result<()> {
         .header(AUTHORIZATION, format!("Bearer {}", TOKEN))
         .header(ACCEPT, "application/vnd.github.v3+json")
         .header(CONTENT_TYPE, "application/json")
-        .header(USER_AGENT, "DependabotAutoMerger")
+        .header(USER_AGENT, super::USER_AGENT_VALUE)
         .json(&merge_body_json)
         .send()?;
 
@@ -149,6 +154,7 @@ fn test_attempt_merge_success() -> Result<()> {
 
 // use super::*; // not needed here
 use serde_json::json;
+use std::sync::atomic::AtomicBool;
 
 async fn setup_mock_server() -> mockito::ServerGuard {
     mockito::Server::new_async().await
@@ -194,16 +200,12 @@ async fn test_get_workflow_runs_success() {
         .create_async()
         .await;
 
-    // This is a conceptual test - you'd need to modify get_workflow_runs
-    // to accept a base_url parameter for testing
-    // let runs = get_workflow_runs("test-token", "owner/repo", "abc123", Some(&server.url())).await.unwrap();
+    let client = GitHubClient::with_base("test-token".into(), &server.url()).unwrap();
+    let runs = super::workflow::get_workflow_runs(&client, "owner/repo", "abc123")
+        .await
+        .unwrap();
 
-    // assert_eq!(runs.len(), 2);
-    // assert_eq!(runs[0].id, 1);
-    // assert_eq!(runs[0].name, "CI");
-    // assert_eq!(runs[1].conclusion, Some("failure".to_string()));
-
-    // placeholder: no HTTP request is made in this unit test
+    assert_eq!(runs.len(), 2);
 }
 
 #[tokio::test]
@@ -222,10 +224,11 @@ async fn test_get_workflow_runs_empty_response() {
         .create_async()
         .await;
 
-    // let runs = get_workflow_runs("test-token", "owner/repo", "abc123", Some(&server.url())).await.unwrap();
-    // assert_eq!(runs.len(), 0);
-
-    // placeholder: no HTTP request is made in this unit test
+    let client = GitHubClient::with_base("test-token".into(), &server.url()).unwrap();
+    let runs = super::workflow::get_workflow_runs(&client, "owner/repo", "abc123")
+        .await
+        .unwrap();
+    assert!(runs.is_empty());
 }
 
 #[tokio::test]
@@ -240,10 +243,9 @@ async fn test_get_workflow_runs_api_error() {
         .create_async()
         .await;
 
-    // let result = get_workflow_runs("bad-token", "owner/repo", "abc123", Some(&server.url())).await;
-    // assert!(result.is_err());
-
-    // placeholder: no HTTP request is made in this unit test
+    let client = GitHubClient::with_base("bad-token".into(), &server.url()).unwrap();
+    let result = super::workflow::get_workflow_runs(&client, "owner/repo", "abc123").await;
+    assert!(result.is_err());
 }
 
 #[tokio::test]
@@ -260,10 +262,10 @@ async fn test_rerun_workflow_success() {
         .create_async()
         .await;
 
-    // let result = rerun_workflow("test-token", "owner/repo", 123, Some(&server.url())).await;
-    // assert!(result.is_ok());
-
-    // placeholder: no HTTP request is made in this unit test
+    let client = GitHubClient::with_base("test-token".into(), &server.url()).unwrap();
+    let result =
+        super::workflow::rerun_workflow(&client, "owner/repo", 123, RerunMode::FailedJobs).await;
+    assert!(result.is_ok());
 }
 
 #[tokio::test]
@@ -281,10 +283,10 @@ async fn test_rerun_workflow_failure() {
         .create_async()
         .await;
 
-    // let result = rerun_workflow("test-token", "owner/repo", 123, Some(&server.url())).await;
-    // assert!(result.is_err());
-
-    // placeholder: no HTTP request is made in this unit test
+    let client = GitHubClient::with_base("test-token".into(), &server.url()).unwrap();
+    let result =
+        super::workflow::rerun_workflow(&client, "owner/repo", 123, RerunMode::FailedJobs).await;
+    assert!(result.is_err());
 }
 
 #[test]
@@ -427,3 +429,1675 @@ mod integration_tests {
         }
     }
 }
+
+// --- Merge Summary Tests ---
+
+#[test]
+fn test_merge_summary_groups_outcomes() {
+    use crate::github::{MergeOutcome, MergeSummary};
+
+    let mut summary = MergeSummary::new();
+    summary.record(REPO, 1, "bump a", MergeOutcome::Merged);
+    summary.record(REPO, 2, "bump b", MergeOutcome::Skipped("draft".into()));
+    summary.record(REPO, 3, "bump c", MergeOutcome::Failed("conflict".into()));
+    summary.record(REPO, 4, "bump d", MergeOutcome::Merged);
+
+    assert_eq!(summary.merged().len(), 2);
+    assert_eq!(summary.skipped().len(), 1);
+    assert_eq!(summary.failed().len(), 1);
+    assert_eq!(summary.failed()[0].number, 3);
+}
+
+#[test]
+fn test_merge_summary_plain_render_does_not_panic() {
+    use crate::github::{MergeOutcome, MergeSummary};
+
+    let mut summary = MergeSummary::new();
+    summary.record(REPO, 1, "bump a", MergeOutcome::Failed("conflict".into()));
+    summary.render(true);
+}
+
+#[test]
+fn test_merge_summary_markdown_table() {
+    use crate::github::{MergeOutcome, MergeSummary};
+
+    let mut summary = MergeSummary::new();
+    summary.record(REPO, 1, "bump a | b", MergeOutcome::Merged);
+    summary.record(REPO, 2, "bump c", MergeOutcome::Failed("conflict".into()));
+
+    let md = summary.to_markdown();
+
+    assert!(md.contains("1 merged, 0 skipped, 1 failed"));
+    assert!(md.contains("| Result | PR | Title | Details |"));
+    assert!(md.contains(&format!("| {REPO}#1 | bump a \\| b |")));
+    assert!(md.contains(&format!("| ❌ failed | {REPO}#2 | bump c | conflict |")));
+}
+
+#[test]
+fn test_merge_summary_appends_to_step_summary_file() {
+    use crate::github::{MergeOutcome, MergeSummary};
+
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("step_summary.md");
+    std::fs::write(&path, "existing\n").unwrap();
+
+    let mut summary = MergeSummary::new();
+    summary.record(REPO, 1, "bump a", MergeOutcome::Merged);
+    summary.append_markdown(&path).unwrap();
+
+    let contents = std::fs::read_to_string(&path).unwrap();
+    assert!(contents.starts_with("existing\n"));
+    assert!(contents.contains("### Merge summary"));
+}
+
+#[test]
+fn test_maintain_summary_markdown_table() {
+    use crate::github::{MaintainReport, MaintainSummary};
+
+    let mut report = MaintainReport::new("acme/a");
+    report.workflows_deleted = 2;
+    report.errors.push("run 9 failed".into());
+    let mut summary = MaintainSummary::new();
+    summary.repos.push(report);
+
+    let md = summary.to_markdown();
+
+    assert!(md.contains("### Maintenance summary"));
+    assert!(md.contains("| acme/a | 2 | 0 | 0 | 0 | 0 | 1 |"));
+    assert!(md.contains("| total | 2 | 0 | 0 | 0 | 0 | 1 |"));
+    assert!(md.contains("- acme/a: run 9 failed"));
+}
+
+// --- Curl Export Tests ---
+
+#[test]
+fn test_curl_command_redacts_token() {
+    let cmd = crate::github::curl_command(
+        "POST",
+        "https://api.github.com/repos/owner/repo/releases",
+        Some(r#"{"tag_name":"v0.1.0"}"#),
+    );
+
+    assert!(cmd.starts_with("curl -X POST 'https://api.github.com/repos/owner/repo/releases'"));
+    assert!(cmd.contains("\"Authorization: Bearer $GITHUB_TOKEN\""));
+    assert!(cmd.contains(r#"-d '{"tag_name":"v0.1.0"}'"#));
+    assert!(!cmd.contains(TOKEN));
+}
+
+// --- Merge Method Tests ---
+
+#[test]
+fn test_auto_merge_method_prefers_squash_then_rebase_then_merge() {
+    use crate::cli::MergeMethod;
+    use crate::github::RepoMergeSettings;
+
+    let all: RepoMergeSettings = serde_json::from_value(json!({
+        "allow_squash_merge": true,
+        "allow_rebase_merge": true,
+        "allow_merge_commit": true
+    }))
+    .unwrap();
+    assert_eq!(all.preferred(), Some(MergeMethod::Squash));
+
+    let no_squash = RepoMergeSettings {
+        allow_squash_merge: false,
+        allow_rebase_merge: true,
+        allow_merge_commit: true,
+    };
+    assert_eq!(no_squash.preferred(), Some(MergeMethod::Rebase));
+
+    let merge_only = RepoMergeSettings {
+        allow_merge_commit: true,
+        ..Default::default()
+    };
+    assert_eq!(merge_only.preferred(), Some(MergeMethod::Merge));
+
+    assert_eq!(RepoMergeSettings::default().preferred(), None);
+}
+
+#[test]
+fn test_resolve_explicit_merge_method_skips_lookup() {
+    use crate::cli::MergeMethod;
+
+    // No mocks, so any request would fail
+    let server = mockito::Server::new();
+    let method =
+        crate::github::resolve_merge_method(&mock_client(&server), REPO, MergeMethod::Rebase)
+            .unwrap();
+    assert_eq!(method, MergeMethod::Rebase);
+}
+
+#[test]
+fn test_resolve_auto_merge_method_from_repo_settings() {
+    use crate::cli::MergeMethod;
+
+    let mut server = mockito::Server::new();
+    let _repo = server
+        .mock("GET", format!("/repos/{REPO}").as_str())
+        .with_status(200)
+        .with_header("content-type", "application/json")
+        .with_body(
+            json!({
+                "default_branch": "develop",
+                "allow_squash_merge": false,
+                "allow_rebase_merge": true,
+                "allow_merge_commit": true
+            })
+            .to_string(),
+        )
+        .create();
+    let client = mock_client(&server);
+
+    let method = crate::github::resolve_merge_method(&client, REPO, MergeMethod::Auto).unwrap();
+    assert_eq!(method, MergeMethod::Rebase);
+    assert_eq!(
+        crate::github::default_branch(&client, REPO).unwrap(),
+        "develop"
+    );
+}
+
+#[test]
+fn test_pr_file_cache_fetches_each_pr_once() {
+    let mut server = mockito::Server::new();
+    let files = server
+        .mock("GET", format!("/repos/{REPO}/pulls/7/files").as_str())
+        .match_query(mockito::Matcher::Any)
+        .with_status(200)
+        .with_header("content-type", "application/json")
+        .with_body(
+            json!([
+                {"filename": "Cargo.lock"},
+                {"filename": ".github/workflows/ci.yml"}
+            ])
+            .to_string(),
+        )
+        .expect(1)
+        .create();
+    let client = mock_client(&server);
+
+    let mut cache = crate::github::PrFileCache::new();
+    assert_eq!(
+        cache.get(&client, REPO, 7).unwrap(),
+        ["Cargo.lock", ".github/workflows/ci.yml"]
+    );
+    assert!(crate::github::touches_workflows(
+        cache.get(&client, REPO, 7).unwrap()
+    ));
+    files.assert();
+}
+
+fn mock_client(server: &mockito::ServerGuard) -> GitHubClient {
+    GitHubClient::with_base(TOKEN.to_string(), &server.url()).unwrap()
+}
+
+#[test]
+fn test_with_base_keeps_api_path() {
+    let client = GitHubClient::with_base(TOKEN.into(), "https://ghe.example.com/api/v3").unwrap();
+    assert_eq!(
+        client.api_base.join("repos/o/r").unwrap().as_str(),
+        "https://ghe.example.com/api/v3/repos/o/r"
+    );
+
+    let client = GitHubClient::with_token(TOKEN.into()).unwrap();
+    assert_eq!(client.api_base.as_str(), "https://api.github.com/");
+}
+
+#[test]
+fn test_is_empty_repo_on_conflict() {
+    let mut server = mockito::Server::new();
+    let _mock = server
+        .mock("GET", "/repos/owner/repo/commits")
+        .match_query(mockito::Matcher::UrlEncoded("per_page".into(), "1".into()))
+        .with_status(409)
+        .with_body(json!({"message": "Git Repository is empty."}).to_string())
+        .create();
+
+    let client = mock_client(&server);
+    assert!(client.is_empty_repo("owner/repo").unwrap());
+}
+
+#[test]
+fn test_is_empty_repo_with_commits() {
+    let mut server = mockito::Server::new();
+    let _mock = server
+        .mock("GET", "/repos/owner/repo/commits")
+        .match_query(mockito::Matcher::Any)
+        .with_status(200)
+        .with_header("content-type", "application/json")
+        .with_body(json!([{"sha": "abc123"}]).to_string())
+        .create();
+
+    let client = mock_client(&server);
+    assert!(!client.is_empty_repo("owner/repo").unwrap());
+}
+
+#[test]
+fn test_retry_delay_honours_retry_after_and_backs_off() {
+    use super::{MAX_RETRY_DELAY, RETRY_BASE_DELAY, retry_delay};
+    use std::time::Duration;
+
+    assert_eq!(retry_delay(Some("7"), 0), Duration::from_secs(7));
+    assert_eq!(retry_delay(Some("3600"), 0), MAX_RETRY_DELAY);
+    assert_eq!(retry_delay(None, 0), RETRY_BASE_DELAY);
+    assert_eq!(retry_delay(None, 2), RETRY_BASE_DELAY * 4);
+    // Dates aren't parsed; fall back to backing off
+    assert_eq!(
+        retry_delay(Some("Wed, 21 Oct 2015 07:28:00 GMT"), 1),
+        RETRY_BASE_DELAY * 2
+    );
+    assert_eq!(retry_delay(None, 30), MAX_RETRY_DELAY);
+}
+
+#[test]
+fn test_fetch_paginated_retries_server_errors() {
+    let mut server = mockito::Server::new();
+    let unavailable = server
+        .mock("GET", "/repos/owner/repo/releases")
+        .match_query(mockito::Matcher::Any)
+        .with_status(503)
+        .with_header("retry-after", "0")
+        .expect(1)
+        .create();
+    let ok = server
+        .mock("GET", "/repos/owner/repo/releases")
+        .match_query(mockito::Matcher::Any)
+        .with_status(200)
+        .with_header("content-type", "application/json")
+        .with_body(json!([{"sha": "abc123"}]).to_string())
+        .expect(1)
+        .create();
+
+    let client = mock_client(&server);
+    let items: Vec<serde_json::Value> =
+        client.fetch_paginated("repos/owner/repo/releases").unwrap();
+
+    assert_eq!(items.len(), 1);
+    unavailable.assert();
+    ok.assert();
+}
+
+#[test]
+fn test_fetch_paginated_gives_up_after_max_retries() {
+    let mut server = mockito::Server::new();
+    let limited = server
+        .mock("GET", "/repos/owner/repo/releases")
+        .match_query(mockito::Matcher::Any)
+        .with_status(429)
+        .with_header("retry-after", "0")
+        .expect(super::MAX_RETRIES as usize + 1)
+        .create();
+
+    let client = mock_client(&server);
+    let err = client
+        .fetch_paginated::<serde_json::Value>("repos/owner/repo/releases")
+        .unwrap_err();
+
+    assert_eq!(err.status(), Some(reqwest::StatusCode::TOO_MANY_REQUESTS));
+    limited.assert();
+}
+
+/// Mocks `pages` pages of `repos/owner/repo/releases`, two items each.
+fn mock_release_pages(server: &mut mockito::ServerGuard, pages: u64) -> Vec<mockito::Mock> {
+    (1..=pages)
+        .map(|page| {
+            server
+                .mock("GET", "/repos/owner/repo/releases")
+                .match_query(mockito::Matcher::AllOf(vec![
+                    mockito::Matcher::UrlEncoded("per_page".into(), "2".into()),
+                    mockito::Matcher::UrlEncoded("page".into(), page.to_string()),
+                ]))
+                .with_status(200)
+                .with_header("content-type", "application/json")
+                .with_body(json!([{"id": page * 10}, {"id": page * 10 + 1}]).to_string())
+                .create()
+        })
+        .collect()
+}
+
+#[test]
+fn test_fetch_paginated_with_stops_at_max_items() {
+    let mut server = mockito::Server::new();
+    let pages = mock_release_pages(&mut server, 3);
+    let client = mock_client(&server);
+
+    let items: Vec<serde_json::Value> = client
+        .fetch_paginated_with("repos/owner/repo/releases", 2, Some(3))
+        .unwrap();
+
+    let ids: Vec<_> = items.iter().map(|i| i["id"].as_u64().unwrap()).collect();
+    assert_eq!(ids, [10, 11, 20]);
+    pages[0].assert();
+    pages[1].assert();
+    // Never asked for the third page
+    assert!(!pages[2].matched());
+}
+
+#[test]
+fn test_for_each_page_breaks_early() {
+    let mut server = mockito::Server::new();
+    let pages = mock_release_pages(&mut server, 2);
+    let client = mock_client(&server);
+
+    let mut seen = 0;
+    client
+        .for_each_page::<serde_json::Value, _>("repos/owner/repo/releases", 2, |page| {
+            seen += page.len();
+            std::ops::ControlFlow::Break(())
+        })
+        .unwrap();
+
+    assert_eq!(seen, 2);
+    pages[0].assert();
+    assert!(!pages[1].matched());
+}
+
+#[test]
+fn test_approve_pr_posts_review() {
+    let mut server = mockito::Server::new();
+    let mock = server
+        .mock("POST", "/repos/owner/repo/pulls/7/reviews")
+        .match_header("authorization", format!("Bearer {TOKEN}").as_str())
+        .match_body(mockito::Matcher::Json(json!({"event": "APPROVE"})))
+        .with_status(200)
+        .with_header("content-type", "application/json")
+        .with_body(json!({"id": 1, "state": "APPROVED"}).to_string())
+        .expect(1)
+        .create();
+
+    let client = mock_client(&server);
+    super::approve_pr(&client, "owner/repo", 7).unwrap();
+    mock.assert();
+}
+
+#[test]
+fn test_approve_pr_surfaces_rejection() {
+    let mut server = mockito::Server::new();
+    let _mock = server
+        .mock("POST", "/repos/owner/repo/pulls/7/reviews")
+        .with_status(422)
+        .with_body(json!({"message": "Can not approve your own pull request"}).to_string())
+        .create();
+
+    let client = mock_client(&server);
+    let err = super::approve_pr(&client, "owner/repo", 7).unwrap_err();
+    assert_eq!(
+        err.status(),
+        Some(reqwest::StatusCode::UNPROCESSABLE_ENTITY)
+    );
+}
+
+#[test]
+fn test_has_conflicts_reads_mergeable_state() {
+    let mut server = mockito::Server::new();
+    let _dirty = server
+        .mock("GET", "/repos/owner/repo/pulls/1")
+        .with_status(200)
+        .with_header("content-type", "application/json")
+        .with_body(json!({"number": 1, "mergeable_state": "dirty"}).to_string())
+        .create();
+    let _unknown = server
+        .mock("GET", "/repos/owner/repo/pulls/2")
+        .with_status(200)
+        .with_header("content-type", "application/json")
+        .with_body(json!({"number": 2, "mergeable_state": "unknown"}).to_string())
+        .create();
+
+    let client = mock_client(&server);
+    assert!(super::has_conflicts(&client, "owner/repo", 1).unwrap());
+    assert!(!super::has_conflicts(&client, "owner/repo", 2).unwrap());
+}
+
+#[test]
+fn test_comment_dependabot_posts_command() {
+    use crate::cli::OnConflict;
+
+    let mut server = mockito::Server::new();
+    let mock = server
+        .mock("POST", "/repos/owner/repo/issues/3/comments")
+        .match_body(mockito::Matcher::Json(
+            json!({"body": "@dependabot recreate"}),
+        ))
+        .with_status(201)
+        .with_header("content-type", "application/json")
+        .with_body(json!({"id": 1}).to_string())
+        .expect(1)
+        .create();
+
+    let client = mock_client(&server);
+    let command = OnConflict::Recreate.dependabot_command().unwrap();
+    super::comment_dependabot(&client, "owner/repo", 3, command).unwrap();
+    mock.assert();
+
+    assert_eq!(OnConflict::default().dependabot_command(), None);
+}
+
+#[test]
+fn test_delete_head_branch() {
+    use super::{BranchCleanup, delete_head_branch};
+
+    let mut server = mockito::Server::new();
+    let branch =
+        |name: &str, protected: bool| json!({"name": name, "protected": protected}).to_string();
+    let _stale = server
+        .mock("GET", "/repos/owner/repo/branches/stale")
+        .with_status(200)
+        .with_body(branch("stale", false))
+        .create();
+    let delete = server
+        .mock("DELETE", "/repos/owner/repo/git/refs/heads/stale")
+        .with_status(204)
+        .expect(1)
+        .create();
+    let _kept = server
+        .mock("GET", "/repos/owner/repo/branches/release")
+        .with_status(200)
+        .with_body(branch("release", true))
+        .create();
+    let _gone = server
+        .mock("GET", "/repos/owner/repo/branches/gone")
+        .with_status(404)
+        .create();
+
+    let client = mock_client(&server);
+    assert_eq!(
+        delete_head_branch(&client, "owner/repo", "stale").unwrap(),
+        BranchCleanup::Deleted
+    );
+    delete.assert();
+    assert_eq!(
+        delete_head_branch(&client, "owner/repo", "release").unwrap(),
+        BranchCleanup::Protected
+    );
+    assert_eq!(
+        delete_head_branch(&client, "owner/repo", "gone").unwrap(),
+        BranchCleanup::AlreadyGone
+    );
+}
+
+#[test]
+fn test_touches_workflows() {
+    let files = |paths: &[&str]| paths.iter().map(|p| (*p).to_string()).collect::<Vec<_>>();
+
+    assert!(super::touches_workflows(&files(&[
+        "Cargo.lock",
+        ".github/workflows/ci.yml"
+    ])));
+    assert!(!super::touches_workflows(&files(&[
+        "Cargo.lock",
+        ".github/dependabot.yml"
+    ])));
+    assert!(!super::touches_workflows(&[]));
+}
+
+#[test]
+fn test_latest_failed_runs_dedups_by_workflow() {
+    let run = |id, name, conclusion| {
+        serde_json::from_value::<super::WorkflowRun>(create_workflow_run_json(
+            id,
+            name,
+            "completed",
+            Some(conclusion),
+        ))
+        .unwrap()
+    };
+    // Newest first, as the API returns them
+    let runs = || {
+        vec![
+            run(5, "CI", "failure"),
+            run(4, "Lint", "failure"),
+            run(3, "CI", "failure"),
+            run(2, "Docs", "success"),
+            run(1, "Release", "failure"),
+        ]
+    };
+
+    let ids = |runs: Vec<super::WorkflowRun>| runs.iter().map(|r| r.id).collect::<Vec<_>>();
+
+    assert_eq!(ids(super::latest_failed_runs(runs(), None, 10)), [5, 4, 1]);
+    assert_eq!(ids(super::latest_failed_runs(runs(), None, 2)), [5, 4]);
+    assert_eq!(ids(super::latest_failed_runs(runs(), Some("ci"), 10)), [5]);
+    assert!(super::latest_failed_runs(runs(), Some("Docs"), 10).is_empty());
+}
+
+#[test]
+fn test_rerun_mode_endpoints() {
+    assert_eq!(RerunMode::default(), RerunMode::FailedJobs);
+    assert_eq!(RerunMode::FailedJobs.endpoint(), "rerun-failed-jobs");
+    assert_eq!(RerunMode::AllJobs.endpoint(), "rerun");
+}
+
+#[test]
+fn test_apply_default_ruleset_creates_when_missing() {
+    let mut server = mockito::Server::new();
+    let _list = server
+        .mock("GET", "/repos/owner/repo/rulesets")
+        .match_query(mockito::Matcher::Any)
+        .with_status(200)
+        .with_header("content-type", "application/json")
+        .with_body(json!([{"id": 7, "name": "release"}]).to_string())
+        .create();
+    let create = server
+        .mock("POST", "/repos/owner/repo/rulesets")
+        .match_body(mockito::Matcher::PartialJson(json!({"name": "default"})))
+        .with_status(201)
+        .with_header("content-type", "application/json")
+        .with_body(json!({"id": 8, "name": "default"}).to_string())
+        .create();
+
+    let client = mock_client(&server);
+    let change = super::apply_default_ruleset(&client, "owner/repo").unwrap();
+
+    assert_eq!(change, super::RulesetChange::Created);
+    create.assert();
+}
+
+#[test]
+fn test_maintain_ruleset_fails_when_not_applied() {
+    let mut server = mockito::Server::new();
+    let _list = server
+        .mock("GET", "/repos/owner/repo/rulesets")
+        .match_query(mockito::Matcher::Any)
+        .with_status(200)
+        .with_header("content-type", "application/json")
+        .with_body("[]")
+        .create();
+    let _create = server
+        .mock("POST", "/repos/owner/repo/rulesets")
+        .with_status(403)
+        .with_header("content-type", "application/json")
+        .with_body(json!({"message": "Resource not accessible by integration"}).to_string())
+        .create();
+
+    let client = mock_client(&server);
+    let options = super::MaintainOptions {
+        action: Some("ruleset".into()),
+        ..Default::default()
+    };
+    let err = super::maintain_repo(&client, "owner/repo", &options).unwrap_err();
+
+    assert!(err.to_string().contains("403"), "{err}");
+}
+
+#[test]
+fn test_apply_default_ruleset_updates_existing() {
+    let mut server = mockito::Server::new();
+    let _list = server
+        .mock("GET", "/repos/owner/repo/rulesets")
+        .match_query(mockito::Matcher::Any)
+        .with_status(200)
+        .with_header("content-type", "application/json")
+        .with_body(json!([{"id": 42, "name": "default"}]).to_string())
+        .create();
+    let update = server
+        .mock("PUT", "/repos/owner/repo/rulesets/42")
+        .with_status(200)
+        .with_header("content-type", "application/json")
+        .with_body(json!({"id": 42, "name": "default"}).to_string())
+        .create();
+    let create = server
+        .mock("POST", "/repos/owner/repo/rulesets")
+        .expect(0)
+        .create();
+
+    let client = mock_client(&server);
+    let change = super::apply_default_ruleset(&client, "owner/repo").unwrap();
+
+    assert_eq!(change, super::RulesetChange::Updated);
+    update.assert();
+    create.assert();
+}
+
+#[test]
+fn test_create_ruleset_posts_default_body() {
+    let mut server = mockito::Server::new();
+    let create = server
+        .mock("POST", "/repos/owner/repo/rulesets")
+        .match_header("authorization", format!("Bearer {TOKEN}").as_str())
+        .match_body(mockito::Matcher::Json(super::default_ruleset()))
+        .with_status(201)
+        .with_header("content-type", "application/json")
+        .with_body(json!({"id": 1, "name": "default"}).to_string())
+        .create();
+
+    let client = mock_client(&server);
+    super::create_ruleset(&client, "owner/repo").unwrap();
+
+    create.assert();
+
+    // The body is the ruleset ghk has always created
+    let body = super::default_ruleset();
+    assert_eq!(body["name"], "default");
+    assert_eq!(
+        body["conditions"]["ref_name"]["include"][0],
+        "~DEFAULT_BRANCH"
+    );
+    let rules: Vec<&str> = body["rules"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|r| r["type"].as_str().unwrap())
+        .collect();
+    assert_eq!(
+        rules,
+        [
+            "required_signatures",
+            "pull_request",
+            "non_fast_forward",
+            "deletion"
+        ]
+    );
+}
+
+#[test]
+fn test_filter_org_repos() {
+    let repos = || {
+        serde_json::from_value::<Vec<super::OrgRepo>>(json!([
+            {"name": "api", "full_name": "acme/api", "archived": false},
+            {"name": "api-legacy", "full_name": "acme/api-legacy", "archived": true},
+            {"name": "web", "full_name": "acme/web"},
+            {"name": "docs", "full_name": "acme/docs", "archived": false}
+        ]))
+        .unwrap()
+    };
+    let globs = |g: &[&str]| g.iter().map(|s| (*s).to_string()).collect::<Vec<_>>();
+
+    assert_eq!(
+        super::filter_org_repos(repos(), &[], &[], false),
+        ["acme/api", "acme/docs", "acme/web"]
+    );
+    assert_eq!(
+        super::filter_org_repos(repos(), &globs(&["api*"]), &[], true),
+        ["acme/api", "acme/api-legacy"]
+    );
+    assert_eq!(
+        super::filter_org_repos(repos(), &[], &globs(&["d*", "acme/web"]), false),
+        ["acme/api"]
+    );
+}
+
+#[test]
+fn test_list_org_repos_paginates() {
+    let mut server = mockito::Server::new();
+    let page: Vec<_> = (0..100)
+        .map(|i| json!({"name": format!("r{i}"), "full_name": format!("acme/r{i}")}))
+        .collect();
+    let _first = server
+        .mock("GET", "/orgs/acme/repos")
+        .match_query(mockito::Matcher::UrlEncoded("page".into(), "1".into()))
+        .with_status(200)
+        .with_header("content-type", "application/json")
+        .with_body(json!(page).to_string())
+        .create();
+    let _second = server
+        .mock("GET", "/orgs/acme/repos")
+        .match_query(mockito::Matcher::UrlEncoded("page".into(), "2".into()))
+        .with_status(200)
+        .with_header("content-type", "application/json")
+        .with_body(
+            json!([{"name": "last", "full_name": "acme/last", "archived": true}]).to_string(),
+        )
+        .create();
+
+    let client = mock_client(&server);
+    let repos = super::list_org_repos(&client, "acme").unwrap();
+
+    assert_eq!(repos.len(), 101);
+    assert!(repos[100].archived);
+}
+
+#[test]
+fn test_parse_timestamp_formats() {
+    let ts = super::parse_timestamp("2024-05-01T12:30:00+02:00").unwrap();
+    assert_eq!(ts.to_rfc3339(), "2024-05-01T10:30:00+00:00");
+
+    let day = super::parse_timestamp("2024-05-01").unwrap();
+    assert_eq!(day.to_rfc3339(), "2024-05-01T00:00:00+00:00");
+
+    let err = super::parse_timestamp("last tuesday").unwrap_err();
+    assert!(err.contains("RFC 3339"));
+}
+
+#[test]
+fn test_merge_options_since_filters_old_prs() -> Result<()> {
+    use crate::cli::MergeMethod;
+
+    let pr = |updated: Option<&str>| -> Result<PullRequest> {
+        Ok(serde_json::from_value(serde_json::json!({
+            "number": 1,
+            "title": "bump",
+            "user": {"login": DEPENDABOT_USER},
+            "updated_at": updated
+        }))?)
+    };
+    let options = super::MergeOptions {
+        all_bases: false,
+        merge_method: MergeMethod::Squash,
+        skip_workflow_changes: false,
+        since: Some(super::parse_timestamp("2024-05-01").unwrap()),
+        include_draft: false,
+        approve: false,
+        on_conflict: crate::cli::OnConflict::Skip,
+        delete_branch: true,
+        interactive: false,
+        show_diff: false,
+    };
+
+    assert!(options.is_recent(&pr(Some("2024-05-02T00:00:00Z"))?));
+    assert!(!options.is_recent(&pr(Some("2024-04-30T23:59:59Z"))?));
+    // Unknown update time: keep it
+    assert!(options.is_recent(&pr(None)?));
+    Ok(())
+}
+
+#[test]
+fn test_draft_prs_skipped_unless_included() -> Result<()> {
+    use crate::cli::MergeMethod;
+
+    let mut server = mockito::Server::new();
+    let _mock = server
+        .mock("GET", format!("/repos/{REPO}/pulls").as_str())
+        .match_query(mockito::Matcher::UrlEncoded("state".into(), "open".into()))
+        .with_status(200)
+        .with_header("content-type", "application/json")
+        .with_body(
+            json!([
+                {"number": 1, "title": "bump a", "user": {"login": DEPENDABOT_USER}, "draft": true,
+                 "base": {"ref": "main"}, "head": {"ref": "dependabot/cargo/a-2"}},
+                {"number": 2, "title": "bump b", "user": {"login": DEPENDABOT_USER}, "draft": false},
+                {"number": 3, "title": "my change", "user": {"login": "octocat"}, "draft": true}
+            ])
+            .to_string(),
+        )
+        .create();
+
+    let prs = super::list_dependabot_prs(&mock_client(&server), REPO)?;
+    assert_eq!(prs[0].base_ref.as_deref(), Some("main"));
+    assert_eq!(prs[0].head_ref.as_deref(), Some("dependabot/cargo/a-2"));
+
+    let mut options = super::MergeOptions {
+        all_bases: false,
+        merge_method: MergeMethod::Squash,
+        skip_workflow_changes: false,
+        since: None,
+        include_draft: false,
+        approve: false,
+        on_conflict: crate::cli::OnConflict::Skip,
+        delete_branch: true,
+        interactive: false,
+        show_diff: false,
+    };
+    let attempted = |options: &super::MergeOptions| -> Vec<u64> {
+        prs.iter()
+            .filter(|pr| options.skip_reason(pr).is_none())
+            .map(|pr| pr.number)
+            .collect()
+    };
+
+    // Drafts are skipped by default...
+    assert_eq!(attempted(&options), [2]);
+
+    // ...and attempted alongside the rest with --include-draft
+    options.include_draft = true;
+    assert_eq!(attempted(&options), [1, 2]);
+    Ok(())
+}
+
+#[test]
+fn test_container_cleanup_counts_deletions_and_errors() {
+    let mut server = mockito::Server::new();
+    let _list = server
+        .mock("GET", "/orgs/acme/packages/container/app/versions")
+        .match_query(mockito::Matcher::Any)
+        .with_status(200)
+        .with_header("content-type", "application/json")
+        .with_body(
+            json!([
+                {"id": 1, "metadata": {"container": {"tags": []}}},
+                {"id": 2, "metadata": {"container": {"tags": []}}},
+                {"id": 3, "metadata": {"container": {"tags": ["v1"]}}}
+            ])
+            .to_string(),
+        )
+        .create();
+    let _ok = server
+        .mock("DELETE", "/orgs/acme/packages/container/app/versions/1")
+        .with_status(204)
+        .create();
+    let _fail = server
+        .mock("DELETE", "/orgs/acme/packages/container/app/versions/2")
+        .with_status(500)
+        .create();
+
+    let client = mock_client(&server);
+    let cleanup = super::delete_old_container_versions(&client, "acme/app");
+
+    assert_eq!(cleanup.deleted, 1);
+    assert_eq!(cleanup.errors.len(), 1);
+    assert!(cleanup.errors[0].contains("version 2"));
+}
+
+#[test]
+fn test_maintain_summary_totals() {
+    let mut summary = super::MaintainSummary::new();
+
+    let mut a = super::MaintainReport::new("acme/a");
+    a.workflows_deleted = a.absorb(super::Cleanup {
+        deleted: 3,
+        errors: vec!["run 9 failed".into()],
+        interrupted: false,
+    });
+    a.containers_deleted = 2;
+    summary.repos.push(a);
+
+    let mut b = super::MaintainReport::new("acme/b");
+    b.releases_deleted = 1;
+    b.tags_deleted = 4;
+    summary.repos.push(b);
+
+    let total = summary.totals();
+    assert_eq!(total.workflows_deleted, 3);
+    assert_eq!(total.containers_deleted, 2);
+    assert_eq!(total.releases_deleted, 1);
+    assert_eq!(total.tags_deleted, 4);
+    assert_eq!(total.errors, ["acme/a: run 9 failed"]);
+    assert!(!total.interrupted);
+
+    let json = summary.to_json();
+    assert_eq!(json["repos"][1]["repo"], "acme/b");
+    assert_eq!(json["total"]["workflows_deleted"], 3);
+}
+
+#[test]
+fn test_maintain_repo_cleans_up_against_mock_api() {
+    let mut server = mockito::Server::new();
+    let _commits = server
+        .mock("GET", "/repos/acme/app/commits")
+        .match_query(mockito::Matcher::Any)
+        .with_status(200)
+        .with_header("content-type", "application/json")
+        .with_body(json!([{"sha": "abc123"}]).to_string())
+        .create();
+    let _runs = server
+        .mock("GET", "/repos/acme/app/actions/runs")
+        .match_query(mockito::Matcher::Any)
+        .with_status(200)
+        .with_header("content-type", "application/json")
+        .with_body(
+            json!({"workflow_runs": [
+                create_workflow_run_json(1, "CI", "completed", Some("failure")),
+                create_workflow_run_json(2, "CI", "completed", Some("success")),
+                create_workflow_run_json(3, "Lint", "completed", Some("cancelled")),
+            ]})
+            .to_string(),
+        )
+        .create();
+    let delete_runs = server
+        .mock(
+            "DELETE",
+            mockito::Matcher::Regex(r"^/repos/acme/app/actions/runs/[13]$".into()),
+        )
+        .with_status(204)
+        .expect(2)
+        .create();
+    let _versions = server
+        .mock("GET", "/orgs/acme/packages/container/app/versions")
+        .match_query(mockito::Matcher::Any)
+        .with_status(200)
+        .with_header("content-type", "application/json")
+        .with_body(json!([{"id": 7, "metadata": {"container": {"tags": []}}}]).to_string())
+        .create();
+    let _delete_version = server
+        .mock("DELETE", "/orgs/acme/packages/container/app/versions/7")
+        .with_status(204)
+        .create();
+
+    let client = mock_client(&server);
+    let report =
+        super::maintain_repo(&client, "acme/app", &super::MaintainOptions::default()).unwrap();
+
+    assert_eq!(report.workflows_deleted, 2);
+    assert_eq!(report.containers_deleted, 1);
+    assert_eq!(report.releases_deleted, 0);
+    assert!(report.errors.is_empty(), "{:?}", report.errors);
+    delete_runs.assert();
+}
+
+#[test]
+fn test_maintain_repo_stops_after_ctrl_c() {
+    let mut server = mockito::Server::new();
+    let _commits = server
+        .mock("GET", "/repos/acme/app/commits")
+        .match_query(mockito::Matcher::Any)
+        .with_status(200)
+        .with_header("content-type", "application/json")
+        .with_body(json!([{"sha": "abc123"}]).to_string())
+        .create();
+    let _runs = server
+        .mock("GET", "/repos/acme/app/actions/runs")
+        .match_query(mockito::Matcher::Any)
+        .with_status(200)
+        .with_header("content-type", "application/json")
+        .with_body(
+            json!({"workflow_runs": [
+                create_workflow_run_json(1, "CI", "completed", Some("failure")),
+            ]})
+            .to_string(),
+        )
+        .create();
+    let delete_runs = server
+        .mock("DELETE", mockito::Matcher::Any)
+        .with_status(204)
+        .expect(0)
+        .create();
+
+    // Its own flag, so tests running alongside aren't cancelled too
+    static CANCELLED: AtomicBool = AtomicBool::new(true);
+    let client = mock_client(&server).with_cancel(&CANCELLED);
+    let report =
+        super::maintain_repo(&client, "acme/app", &super::MaintainOptions::default()).unwrap();
+
+    assert!(report.interrupted);
+    assert_eq!(report.workflows_deleted, 0);
+    assert!(report.errors.is_empty(), "{:?}", report.errors);
+    delete_runs.assert();
+
+    let mut summary = super::MaintainSummary::new();
+    summary.repos.push(report);
+    assert_eq!(summary.to_json()["total"]["interrupted"], true);
+}
+
+#[test]
+fn test_rate_limit_table() {
+    let mut server = mockito::Server::new();
+    let limit = |remaining| json!({"limit": 5000, "remaining": remaining, "used": 5000 - remaining, "reset": 1_700_000_600});
+    let _mock = server
+        .mock("GET", "/rate_limit")
+        .with_status(200)
+        .with_header("content-type", "application/json")
+        .with_header("x-ratelimit-remaining", "4990")
+        .with_body(
+            json!({
+                "resources": {"core": limit(4990), "search": limit(30), "graphql": limit(5000)},
+                "rate": limit(4990)
+            })
+            .to_string(),
+        )
+        .create();
+
+    let client = mock_client(&server);
+    let limits = client.rate_limit().unwrap();
+    assert_eq!(limits.core.remaining, 4990);
+    assert_eq!(limits.graphql.map(|g| g.remaining), Some(5000));
+
+    let now = chrono::DateTime::from_timestamp(1_700_000_000, 0).unwrap();
+    let table = limits.to_table(now);
+    assert_eq!(table.lines().count(), 4);
+    assert!(table.lines().nth(1).unwrap().starts_with("core"));
+    assert!(table.contains("(in 10 min)"));
+}
+
+#[test]
+fn test_remaining_from_headers() {
+    let mut headers = reqwest::header::HeaderMap::new();
+    assert_eq!(super::remaining_from_headers(&headers), None);
+    headers.insert("x-ratelimit-remaining", "42".parse().unwrap());
+    assert_eq!(super::remaining_from_headers(&headers), Some(42));
+}
+
+#[test]
+fn test_client_sends_versioned_user_agent() {
+    assert!(super::USER_AGENT_VALUE.starts_with("github-bot/"));
+    assert_eq!(super::user_agent(), super::USER_AGENT_VALUE);
+
+    let mut server = mockito::Server::new();
+    let mock = server
+        .mock("GET", "/repos/owner/repo/releases")
+        .match_query(mockito::Matcher::Any)
+        .match_header("user-agent", super::USER_AGENT_VALUE)
+        .with_status(200)
+        .with_header("content-type", "application/json")
+        .with_body("[]")
+        .expect(1)
+        .create();
+
+    let client = mock_client(&server);
+    let releases: Vec<serde_json::Value> =
+        client.fetch_paginated("repos/owner/repo/releases").unwrap();
+    assert!(releases.is_empty());
+    mock.assert();
+}
+
+#[test]
+fn test_deletion_bar_hidden_when_quiet() {
+    crate::ghk::config::setquiet(true);
+    let bar = super::deletion_bar(5);
+    crate::ghk::config::setquiet(false);
+    assert!(bar.is_hidden());
+
+    let bar = super::deletion_bar(5);
+    assert_eq!(bar.length(), Some(5));
+    bar.inc(2);
+    assert_eq!(bar.position(), 2);
+}
+
+#[test]
+fn test_stale_dependabot_branches_skip_open_prs() {
+    let branches = [
+        "main",
+        "dependabot/cargo/serde-1.0.200",
+        "dependabot/npm/left-pad-2",
+        "feature/x",
+    ]
+    .map(String::from)
+    .to_vec();
+    let open: std::collections::HashSet<String> = ["dependabot/npm/left-pad-2".to_string()].into();
+
+    assert_eq!(
+        super::stale_dependabot_branches(branches, &open),
+        ["dependabot/cargo/serde-1.0.200"]
+    );
+}
+
+#[test]
+fn test_maintain_branches_deletes_only_stale_dependabot_branches() {
+    let mut server = mockito::Server::new();
+    let _commits = server
+        .mock("GET", "/repos/acme/app/commits")
+        .match_query(mockito::Matcher::Any)
+        .with_status(200)
+        .with_header("content-type", "application/json")
+        .with_body(json!([{"sha": "abc123"}]).to_string())
+        .create();
+    let _branches = server
+        .mock("GET", "/repos/acme/app/branches")
+        .match_query(mockito::Matcher::Any)
+        .with_status(200)
+        .with_header("content-type", "application/json")
+        .with_body(
+            json!([
+                {"name": "main"},
+                {"name": "dependabot/cargo/old"},
+                {"name": "dependabot/cargo/open"}
+            ])
+            .to_string(),
+        )
+        .create();
+    let _pulls = server
+        .mock("GET", "/repos/acme/app/pulls")
+        .match_query(mockito::Matcher::UrlEncoded("state".into(), "open".into()))
+        .with_status(200)
+        .with_header("content-type", "application/json")
+        .with_body(json!([{"number": 5, "head": {"ref": "dependabot/cargo/open"}}]).to_string())
+        .create();
+    let _old = server
+        .mock("GET", "/repos/acme/app/branches/dependabot/cargo/old")
+        .with_status(200)
+        .with_header("content-type", "application/json")
+        .with_body(json!({"name": "dependabot/cargo/old", "protected": false}).to_string())
+        .create();
+    let delete = server
+        .mock(
+            "DELETE",
+            "/repos/acme/app/git/refs/heads/dependabot/cargo/old",
+        )
+        .with_status(204)
+        .expect(1)
+        .create();
+
+    let client = mock_client(&server);
+    let options = super::MaintainOptions {
+        action: Some("branches".into()),
+        yes: true,
+        ..Default::default()
+    };
+    let report = super::maintain_repo(&client, "acme/app", &options).unwrap();
+
+    assert_eq!(report.branches_deleted, 1);
+    assert!(report.errors.is_empty(), "{:?}", report.errors);
+    delete.assert();
+}
+
+#[test]
+fn test_redact_hides_the_token() {
+    use super::redact;
+
+    assert_eq!(
+        redact("Bearer ghp_secret123 for ghp_secret123", "ghp_secret123"),
+        "Bearer [REDACTED] for [REDACTED]"
+    );
+    assert_eq!(redact("nothing to hide", ""), "nothing to hide");
+}
+
+#[test]
+fn test_trace_http_keeps_responses_readable() {
+    use super::set_trace_http;
+
+    let mut server = mockito::Server::new();
+    let release = server
+        .mock("GET", "/repos/owner/repo/releases/1")
+        .with_status(200)
+        .with_header("content-type", "application/json")
+        .with_body(json!({"id": 1, "tag_name": "v1.0.0"}).to_string())
+        .create();
+    let missing = server
+        .mock("GET", "/repos/owner/repo/releases/2")
+        .with_status(404)
+        .with_body(json!({"message": "Not Found"}).to_string())
+        .create();
+
+    set_trace_http(true);
+    let client = mock_client(&server);
+    let found: Result<serde_json::Value, _> = client.get("repos/owner/repo/releases/1");
+    let not_found: Result<serde_json::Value, _> = client.get("repos/owner/repo/releases/2");
+    set_trace_http(false);
+
+    // The body was read for the log, yet the caller still gets it
+    assert_eq!(found.unwrap()["tag_name"], "v1.0.0");
+    // and errors still carry the status
+    assert_eq!(
+        not_found.unwrap_err().status(),
+        Some(reqwest::StatusCode::NOT_FOUND)
+    );
+    release.assert();
+    missing.assert();
+}
+
+/// A repo with commits whose `v0.1.0` release already exists.
+fn mock_existing_release(server: &mut mockito::ServerGuard) -> Vec<mockito::Mock> {
+    vec![
+        server
+            .mock("GET", "/repos/owner/repo/commits")
+            .match_query(mockito::Matcher::Any)
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body(json!([{"sha": "abc123"}]).to_string())
+            .create(),
+        server
+            .mock("POST", "/repos/owner/repo/releases")
+            .with_status(422)
+            .with_header("content-type", "application/json")
+            .with_body(
+                json!({
+                    "message": "Validation Failed",
+                    "errors": [{"resource": "Release", "code": "already_exists", "field": "tag_name"}]
+                })
+                .to_string(),
+            )
+            .create(),
+    ]
+}
+
+#[test]
+fn test_create_release_skips_existing_release() {
+    use super::{ReleaseOutcome, create_release};
+
+    let mut server = mockito::Server::new();
+    let _mocks = mock_existing_release(&mut server);
+    let patch = server
+        .mock("PATCH", mockito::Matcher::Any)
+        .with_status(200)
+        .create();
+
+    let client = mock_client(&server);
+    assert_eq!(
+        create_release(&client, "owner/repo", false).unwrap(),
+        ReleaseOutcome::AlreadyExists
+    );
+    assert!(!patch.matched());
+}
+
+#[test]
+fn test_create_release_updates_existing_release() {
+    use super::{ReleaseOutcome, create_release};
+
+    let mut server = mockito::Server::new();
+    let _mocks = mock_existing_release(&mut server);
+    let _existing = server
+        .mock("GET", "/repos/owner/repo/releases/tags/v0.1.0")
+        .with_status(200)
+        .with_header("content-type", "application/json")
+        .with_body(
+            json!({"id": 42, "html_url": "https://github.com/owner/repo/releases/v0.1.0"})
+                .to_string(),
+        )
+        .create();
+    let patch = server
+        .mock("PATCH", "/repos/owner/repo/releases/42")
+        .match_body(mockito::Matcher::PartialJson(json!({"tag_name": "v0.1.0"})))
+        .with_status(200)
+        .with_header("content-type", "application/json")
+        .with_body(json!({"id": 42}).to_string())
+        .expect(1)
+        .create();
+
+    let client = mock_client(&server);
+    assert_eq!(
+        create_release(&client, "owner/repo", true).unwrap(),
+        ReleaseOutcome::Updated
+    );
+    patch.assert();
+}
+
+#[test]
+fn test_create_release_reports_other_validation_errors() {
+    use super::create_release;
+
+    let mut server = mockito::Server::new();
+    let _commits = server
+        .mock("GET", "/repos/owner/repo/commits")
+        .match_query(mockito::Matcher::Any)
+        .with_status(200)
+        .with_header("content-type", "application/json")
+        .with_body(json!([{"sha": "abc123"}]).to_string())
+        .create();
+    let _create = server
+        .mock("POST", "/repos/owner/repo/releases")
+        .with_status(422)
+        .with_body(
+            json!({"errors": [{"code": "invalid", "field": "target_commitish"}]}).to_string(),
+        )
+        .create();
+
+    let client = mock_client(&server);
+    let err = create_release(&client, "owner/repo", true).unwrap_err();
+    assert_eq!(
+        err.status(),
+        Some(reqwest::StatusCode::UNPROCESSABLE_ENTITY)
+    );
+}
+
+#[test]
+fn test_bump_from_dependabot_titles() {
+    use super::Bump;
+
+    assert_eq!(
+        Bump::from_title("Bump serde from 1.0.1 to 1.0.2"),
+        Some(Bump::Patch)
+    );
+    assert_eq!(
+        Bump::from_title("chore(deps): bump tokio from 1.38.0 to 1.49.0"),
+        Some(Bump::Minor)
+    );
+    assert_eq!(
+        Bump::from_title("Bump actions/checkout from v4 to v5"),
+        Some(Bump::Major)
+    );
+    assert_eq!(
+        Bump::from_title("Bump the cargo group with 3 updates"),
+        None
+    );
+}
+
+#[test]
+fn test_repo_policy_skips_by_label_and_bump() {
+    use super::{Label, RepoPolicy};
+
+    let policy =
+        RepoPolicy::parse("max_bump = \"minor\"\nrequire_label = \"automerge\"\n").unwrap();
+    let mut pr: PullRequest = serde_json::from_value(json!({
+        "number": 1,
+        "title": "Bump serde from 1.0.1 to 2.0.0",
+        "user": {"login": DEPENDABOT_USER},
+    }))
+    .unwrap();
+
+    assert_eq!(
+        policy.skip_reason(&pr).as_deref(),
+        Some("missing the 'automerge' label")
+    );
+
+    pr.labels.push(Label {
+        name: "automerge".into(),
+    });
+    assert_eq!(
+        policy.skip_reason(&pr).as_deref(),
+        Some("major update, max_bump is minor")
+    );
+
+    pr.title = "Bump serde from 1.0.1 to 1.1.0".into();
+    assert_eq!(policy.skip_reason(&pr), None);
+}
+
+#[test]
+fn test_repo_policy_is_read_from_the_repo() {
+    use crate::cli::MergeMethod;
+
+    let mut server = mockito::Server::new();
+    let _with = server
+        .mock("GET", "/repos/acme/app/contents/.github/automerge.toml")
+        .match_header("accept", "application/vnd.github.raw+json")
+        .with_status(200)
+        .with_body("merge_method = \"rebase\"\n")
+        .create();
+    let _without = server
+        .mock("GET", "/repos/acme/web/contents/.github/automerge.toml")
+        .with_status(404)
+        .create();
+    let _invalid = server
+        .mock("GET", "/repos/acme/api/contents/.github/automerge.toml")
+        .with_status(200)
+        .with_body("merge_method = \"fast-forward\"\n")
+        .create();
+
+    let client = mock_client(&server);
+    let policy = client.repo_policy("acme/app").unwrap().unwrap();
+    assert_eq!(policy.merge_method, Some(MergeMethod::Rebase));
+    assert_eq!(client.repo_policy("acme/web").unwrap(), None);
+    assert!(client.repo_policy("acme/api").is_err());
+}
+
+#[test]
+fn test_merge_error_from_api_bodies() {
+    use super::MergeError;
+    use reqwest::StatusCode;
+
+    let api = |status, message: &str| {
+        MergeError::parse(
+            Some(status),
+            &json!({
+                "message": message,
+                "documentation_url": "https://docs.github.com/rest/pulls/pulls#merge-a-pull-request"
+            })
+            .to_string(),
+        )
+    };
+
+    assert_eq!(
+        api(
+            StatusCode::METHOD_NOT_ALLOWED,
+            "Base branch was modified. Review and try the merge again."
+        ),
+        MergeError::BaseBranchModified
+    );
+    assert_eq!(
+        api(
+            StatusCode::METHOD_NOT_ALLOWED,
+            "Pull Request is not mergeable"
+        ),
+        MergeError::NotMergeable("Pull Request is not mergeable".into())
+    );
+    assert_eq!(
+        api(
+            StatusCode::METHOD_NOT_ALLOWED,
+            "Required status check \"ci\" is expected."
+        ),
+        MergeError::ChecksFailed
+    );
+    // Unfamiliar wording falls back to the status
+    assert_eq!(
+        api(StatusCode::CONFLICT, "Head branch was modified."),
+        MergeError::Conflict
+    );
+    assert_eq!(
+        api(StatusCode::INTERNAL_SERVER_ERROR, "Something went wrong"),
+        MergeError::Unknown("Something went wrong".into())
+    );
+}
+
+#[test]
+fn test_merge_error_from_gh_stderr() {
+    use super::MergeError;
+
+    let gh = |stderr| MergeError::parse(None, stderr);
+
+    assert_eq!(
+        gh(
+            "GraphQL: Base branch was modified. Review and try the merge again. (mergePullRequest)\n"
+        ),
+        MergeError::BaseBranchModified
+    );
+    assert_eq!(
+        gh(
+            "X Pull request acme/app#7 is not mergeable: the merge commit cannot be cleanly created.\n"
+        ),
+        MergeError::Conflict
+    );
+    assert_eq!(
+        gh("GraphQL: Required status checks have not succeeded: 2 expected. (mergePullRequest)"),
+        MergeError::ChecksFailed
+    );
+    assert_eq!(
+        gh("X Pull request acme/app#7 is not mergeable: the base branch policy prohibits the merge."),
+        MergeError::NotMergeable(
+            "X Pull request acme/app#7 is not mergeable: the base branch policy prohibits the merge."
+                .into()
+        )
+    );
+    assert!(gh("GraphQL: Base branch was modified.").is_retryable());
+    assert!(!gh("Pull Request is not mergeable").is_retryable());
+}
+
+/// A Dependabot PR as `list_dependabot_prs` returns it.
+fn dependabot_pr(number: u64) -> PullRequest {
+    serde_json::from_value(json!({
+        "number": number,
+        "title": "Bump serde from 1.0.1 to 1.0.2",
+        "user": {"login": DEPENDABOT_USER},
+    }))
+    .unwrap()
+}
+
+#[test]
+fn test_process_pr_updates_branch_and_retries_after_base_branch_moved() {
+    use crate::cli::MergeMethod;
+
+    let mut server = mockito::Server::new();
+    let moved = server
+        .mock("PUT", "/repos/acme/app/pulls/7/merge")
+        .match_body(mockito::Matcher::PartialJson(
+            json!({"merge_method": "squash"}),
+        ))
+        .with_status(405)
+        .with_header("content-type", "application/json")
+        .with_body(
+            json!({"message": "Base branch was modified. Review and try the merge again."})
+                .to_string(),
+        )
+        .expect(1)
+        .create();
+    let update = server
+        .mock("PUT", "/repos/acme/app/pulls/7/update-branch")
+        .with_status(202)
+        .with_header("content-type", "application/json")
+        .with_body(json!({"message": "Updating pull request branch."}).to_string())
+        .expect(1)
+        .create();
+    let merged = server
+        .mock("PUT", "/repos/acme/app/pulls/7/merge")
+        .with_status(200)
+        .with_header("content-type", "application/json")
+        .with_body(
+            json!({"sha": "abc123", "merged": true, "message": "Pull Request successfully merged"})
+                .to_string(),
+        )
+        .expect(1)
+        .create();
+
+    let client = mock_client(&server);
+    let result = super::process_pr(
+        &client,
+        "acme/app",
+        &dependabot_pr(7),
+        MergeMethod::Squash,
+        &[],
+    );
+
+    assert_eq!(result, Ok(()));
+    moved.assert();
+    update.assert();
+    merged.assert();
+}
+
+#[test]
+fn test_process_pr_does_not_retry_other_refusals() {
+    use super::MergeError;
+    use crate::cli::MergeMethod;
+
+    let mut server = mockito::Server::new();
+    let refused = server
+        .mock("PUT", "/repos/acme/app/pulls/7/merge")
+        .with_status(405)
+        .with_header("content-type", "application/json")
+        .with_body(json!({"message": "Pull Request is not mergeable"}).to_string())
+        .expect(1)
+        .create();
+    let update = server
+        .mock("PUT", "/repos/acme/app/pulls/7/update-branch")
+        .with_status(202)
+        .create();
+
+    let client = mock_client(&server);
+    let result = super::process_pr(
+        &client,
+        "acme/app",
+        &dependabot_pr(7),
+        MergeMethod::Rebase,
+        &[],
+    );
+
+    assert_eq!(
+        result,
+        Err(MergeError::NotMergeable(
+            "Pull Request is not mergeable".into()
+        ))
+    );
+    refused.assert();
+    assert!(!update.matched());
+}
+
+#[cfg(unix)]
+#[tokio::test(flavor = "multi_thread")]
+async fn test_process_pr_runs_bash_plugins_from_merge_workers() {
+    use crate::cli::MergeMethod;
+    use crate::plugins::Plugin;
+
+    let dir = tempfile::tempdir().unwrap();
+    let seen = dir.path().join("seen.json");
+    std::fs::write(
+        dir.path().join("manifest.toml"),
+        "name = \"notify\"\ndescription = \"d\"\nauthor = \"a\"\nevents = [\"pr-merged\"]\n",
+    )
+    .unwrap();
+    std::fs::write(
+        dir.path().join("run.sh"),
+        format!("echo \"$1\" > '{}'\n", seen.display()),
+    )
+    .unwrap();
+    let plugin = Plugin::from_dir(dir.path()).unwrap();
+
+    let mut server = mockito::Server::new_async().await;
+    server
+        .mock("PUT", "/repos/acme/app/pulls/7/merge")
+        .with_status(200)
+        .with_header("content-type", "application/json")
+        .with_body(json!({"sha": "abc123", "merged": true, "message": "merged"}).to_string())
+        .create_async()
+        .await;
+
+    // Like `merge`'s workers: a plain thread that has entered the runtime
+    let runtime = tokio::runtime::Handle::current();
+    let result = std::thread::scope(|scope| {
+        scope
+            .spawn(|| {
+                let _runtime = runtime.enter();
+                super::process_pr(
+                    &mock_client(&server),
+                    "acme/app",
+                    &dependabot_pr(7),
+                    MergeMethod::Squash,
+                    std::slice::from_ref(&plugin),
+                )
+            })
+            .join()
+            .unwrap()
+    });
+
+    assert_eq!(result, Ok(()));
+    assert!(std::fs::read_to_string(&seen).unwrap().contains("abc123"));
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_process_pr_skips_merge_vetoed_by_plugin() {
+    use crate::cli::MergeMethod;
+    use crate::plugins::Plugin;
+
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(
+        dir.path().join("manifest.toml"),
+        "name = \"freeze\"\ndescription = \"d\"\nauthor = \"a\"\nevents = [\"pr-merge-init\"]\n",
+    )
+    .unwrap();
+    std::fs::write(dir.path().join("run.rhai"), r#"veto("release freeze")"#).unwrap();
+    let plugin = Plugin::from_dir(dir.path()).unwrap();
+
+    let mut server = mockito::Server::new_async().await;
+    let merge = server
+        .mock("PUT", "/repos/acme/app/pulls/7/merge")
+        .expect(0)
+        .create_async()
+        .await;
+
+    let result = tokio::task::block_in_place(|| {
+        super::process_pr(
+            &mock_client(&server),
+            "acme/app",
+            &dependabot_pr(7),
+            MergeMethod::Squash,
+            std::slice::from_ref(&plugin),
+        )
+    });
+
+    assert_eq!(
+        result,
+        Err(super::MergeError::Vetoed("freeze: release freeze".into()))
+    );
+    assert_eq!(
+        result.unwrap_err().to_string(),
+        "vetoed by freeze: release freeze"
+    );
+    merge.assert_async().await;
+}
+
+const SAMPLE_DIFF: &str = "diff --git a/Cargo.toml b/Cargo.toml
+index 1111111..2222222 100644
+--- a/Cargo.toml
++++ b/Cargo.toml
+@@ -1,3 +1,3 @@
+ [dependencies]
+-serde = \"1.0.1\"
++serde = \"1.0.2\"
+";
+
+#[test]
+fn test_render_diff_colors_and_truncates() {
+    use super::render_diff;
+
+    let colored = render_diff(SAMPLE_DIFF, 100, true);
+    assert!(colored.contains("\x1b[1;35mdiff --git a/Cargo.toml b/Cargo.toml\x1b[0m"));
+    assert!(colored.contains("\x1b[33m+++ b/Cargo.toml\x1b[0m"));
+    assert!(colored.contains("\x1b[1;36m@@ -1,3 +1,3 @@\x1b[0m"));
+    assert!(colored.contains("\x1b[31m-serde = \"1.0.1\"\x1b[0m"));
+    assert!(colored.contains("\x1b[32m+serde = \"1.0.2\"\x1b[0m"));
+    assert!(colored.contains("\n [dependencies]\n"));
+
+    let short = render_diff(SAMPLE_DIFF, 5, false);
+    assert_eq!(short.lines().count(), 6);
+    assert!(!short.contains('\x1b'));
+    assert!(short.ends_with("... 3 more lines not shown, see the PR on GitHub\n"));
+}
+
+#[test]
+fn test_pr_diff_asks_for_the_diff_media_type() {
+    let mut server = mockito::Server::new();
+    let mock = server
+        .mock("GET", "/repos/acme/app/pulls/7")
+        .match_header("accept", "application/vnd.github.v3.diff")
+        .with_status(200)
+        .with_body(SAMPLE_DIFF)
+        .expect(1)
+        .create();
+
+    let client = mock_client(&server);
+    assert_eq!(super::pr_diff(&client, "acme/app", 7).unwrap(), SAMPLE_DIFF);
+    mock.assert();
+}
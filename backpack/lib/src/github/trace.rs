@@ -0,0 +1,111 @@
+use reqwest::ResponseBuilderExt;
+use reqwest::StatusCode;
+use std::sync::atomic::{AtomicBool, Ordering};
+use url::Url;
+
+/// `tracing` target of the `--trace-http` logs, e.g. for `RUST_LOG=github_bot::http=trace`.
+pub const HTTP_TRACE_TARGET: &str = "github_bot::http";
+
+static TRACE_HTTP: AtomicBool = AtomicBool::new(false);
+
+/// Log every API request and response at trace level for the rest of the run (`--trace-http`).
+pub fn set_trace_http(enabled: bool) {
+    TRACE_HTTP.store(enabled, Ordering::Relaxed);
+}
+
+/// Whether `--trace-http` is on.
+#[must_use]
+pub fn trace_http() -> bool {
+    TRACE_HTTP.load(Ordering::Relaxed)
+}
+
+/// `text` with every occurrence of `token` replaced, so it is safe to log.
+#[must_use]
+pub fn redact(text: &str, token: &str) -> String {
+    if token.is_empty() {
+        text.to_string()
+    } else {
+        text.replace(token, "[REDACTED]")
+    }
+}
+
+/// Logs an outgoing request with `--trace-http`. The `Authorization` header is
+/// always shown redacted.
+pub(crate) fn trace_http_request(method: &str, url: &Url, body: Option<&str>, token: &str) {
+    if !trace_http() {
+        return;
+    }
+    tracing::trace!(
+        target: HTTP_TRACE_TARGET,
+        "--> {method} {} (Authorization: Bearer [REDACTED]){}",
+        redact(url.as_str(), token),
+        body.map(|b| format!("\n{}", redact(b, token)))
+            .unwrap_or_default()
+    );
+}
+
+fn log_response(status: StatusCode, url: &Url, body: &[u8], token: &str) {
+    tracing::trace!(
+        target: HTTP_TRACE_TARGET,
+        "<-- {status} {}\n{}",
+        redact(url.as_str(), token),
+        redact(&String::from_utf8_lossy(body), token)
+    );
+}
+
+/// With `--trace-http`, logs the status and body of a response.
+///
+/// Reading the body consumes it, so an equivalent response is handed back
+/// for the caller to use as usual.
+pub(crate) fn trace_http_response(
+    response: reqwest::blocking::Response,
+    token: &str,
+) -> Result<reqwest::blocking::Response, reqwest::Error> {
+    if !trace_http() {
+        return Ok(response);
+    }
+    let (status, version, url) = (
+        response.status(),
+        response.version(),
+        response.url().clone(),
+    );
+    let headers = response.headers().clone();
+    let body = response.bytes()?;
+    log_response(status, &url, &body, token);
+
+    let mut rebuilt = http::Response::builder()
+        .status(status)
+        .version(version)
+        .url(url)
+        .body(body)
+        .expect("parts of a valid response");
+    *rebuilt.headers_mut() = headers;
+    Ok(rebuilt.into())
+}
+
+/// Async version of `trace_http_response`.
+pub(crate) async fn trace_http_response_async(
+    response: reqwest::Response,
+    token: &str,
+) -> Result<reqwest::Response, reqwest::Error> {
+    if !trace_http() {
+        return Ok(response);
+    }
+    let (status, version, url) = (
+        response.status(),
+        response.version(),
+        response.url().clone(),
+    );
+    let headers = response.headers().clone();
+    let body = response.bytes().await?;
+    log_response(status, &url, &body, token);
+
+    let mut rebuilt = http::Response::builder()
+        .status(status)
+        .version(version)
+        .url(url)
+        .body(body)
+        .expect("parts of a valid response");
+    *rebuilt.headers_mut() = headers;
+    Ok(rebuilt.into())
+}
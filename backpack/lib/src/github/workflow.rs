@@ -1,8 +1,16 @@
 use serde::Deserialize;
+use std::collections::HashSet;
 use std::error::Error;
+use std::ops::ControlFlow;
 use std::process::Command;
 
-use crate::{github::GitHubClient, log::log};
+use crate::{
+    github::{
+        Cleanup, DELETE_BATCH, GitHubClient, MAX_PER_PAGE, deletion_bar, trace_http_response_async,
+        user_agent,
+    },
+    log::log,
+};
 use colored::Colorize;
 use std::thread;
 use std::time::Duration;
@@ -16,6 +24,27 @@ pub struct WorkflowRun {
     html_url: String,
 }
 
+/// Which jobs of a workflow run to rerun.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum RerunMode {
+    /// Only the jobs that failed (`/rerun-failed-jobs`)
+    #[default]
+    FailedJobs,
+    /// Every job in the run (`/rerun`)
+    AllJobs,
+}
+
+impl RerunMode {
+    /// Endpoint under `repos/{repo}/actions/runs/{id}/`
+    #[must_use]
+    pub const fn endpoint(self) -> &'static str {
+        match self {
+            Self::FailedJobs => "rerun-failed-jobs",
+            Self::AllJobs => "rerun",
+        }
+    }
+}
+
 #[derive(Debug, Deserialize)]
 struct WorkflowRunsResponse {
     workflow_runs: Vec<WorkflowRun>,
@@ -57,22 +86,27 @@ fn get_repo_from_git() -> Result<String, Box<dyn Error>> {
     Ok(repo)
 }
 
-async fn get_workflow_runs(
+pub(super) async fn get_workflow_runs(
     client: &GitHubClient,
     repo: &str,
     commit: &str,
 ) -> Result<Vec<WorkflowRun>, Box<dyn Error>> {
-    let url = format!("https://api.github.com/repos/{repo}/actions/runs?head_sha={commit}");
+    let url = client
+        .api_base
+        .join(&format!("repos/{repo}/actions/runs?head_sha={commit}"))?;
+
+    client.trace_request("GET", &url, None);
 
     let http_client = reqwest::Client::new();
     let response = http_client
-        .get(&url)
+        .get(url)
         .header("Authorization", format!("Bearer {}", client.token))
-        .header("User-Agent", "github-workflow-rerunner")
+        .header("User-Agent", user_agent())
         .header("Accept", "application/vnd.github+json")
         .header("X-GitHub-Api-Version", "2022-11-28")
         .send()
         .await?;
+    let response = trace_http_response_async(response, &client.token).await?;
 
     if !response.status().is_success() {
         return Err(format!("GitHub API error: {}", response.status()).into());
@@ -82,23 +116,29 @@ async fn get_workflow_runs(
     Ok(runs.workflow_runs)
 }
 
-async fn rerun_workflow(
+pub(super) async fn rerun_workflow(
     client: &GitHubClient,
     repo: &str,
     run_id: u64,
+    mode: RerunMode,
 ) -> Result<(), Box<dyn Error>> {
-    let url =
-        format!("https://api.github.com/repos/{repo}/actions/runs/{run_id}/rerun-failed-jobs");
+    let url = client.api_base.join(&format!(
+        "repos/{repo}/actions/runs/{run_id}/{}",
+        mode.endpoint()
+    ))?;
+
+    client.trace_request("POST", &url, None);
 
     let http_client = reqwest::Client::new();
     let response = http_client
-        .post(&url)
+        .post(url)
         .header("Authorization", format!("Bearer {}", client.token))
-        .header("User-Agent", "github-workflow-rerunner")
+        .header("User-Agent", user_agent())
         .header("Accept", "application/vnd.github+json")
         .header("X-GitHub-Api-Version", "2022-11-28")
         .send()
         .await?;
+    let response = trace_http_response_async(response, &client.token).await?;
 
     if !response.status().is_success() {
         return Err(format!("Failed to rerun workflow: {}", response.status()).into());
@@ -111,6 +151,7 @@ pub async fn rerun_workflows(
     client: &GitHubClient,
     commit: Option<String>,
     repo: Option<String>,
+    mode: RerunMode,
 ) -> Result<(), Box<dyn Error>> {
     // Get commit SHA
     let commit = if let Some(c) = commit {
@@ -173,7 +214,7 @@ pub async fn rerun_workflows(
     println!("Re-running failed workflows...\n");
     for run in &failed_runs {
         print!("Re-running '{}'... ", run.name);
-        match rerun_workflow(client, &repo, run.id).await {
+        match rerun_workflow(client, &repo, run.id, mode).await {
             Ok(()) => log().success(""),
             Err(e) => log().fail(&format!("Failed: {e}")),
         }
@@ -184,9 +225,20 @@ pub async fn rerun_workflows(
 }
 
 /// Deletes failed/cancelled workflows concurrently using standard threads (max 10 at a time).
-pub fn delete_failed_workflows(client: &GitHubClient, repo: &str) {
+///
+/// Stops between batches after Ctrl-C, once the deletions in flight are done.
+pub fn delete_failed_workflows(client: &GitHubClient, repo: &str) -> Cleanup {
     log().intro(&format!("Deleting failed workflows for {repo}"));
 
+    if matches!(client.is_empty_repo(repo), Ok(true)) {
+        log().info(&format!(
+            "Repository {repo} is empty, no workflows to delete."
+        ));
+        log().done("Done");
+        return Cleanup::default();
+    }
+
+    let mut cleanup = Cleanup::default();
     let path = &format!("repos/{repo}/actions/runs");
     match client.fetch_paginated::<WorkflowRun>(path) {
         Ok(runs) => {
@@ -199,84 +251,151 @@ pub fn delete_failed_workflows(client: &GitHubClient, repo: &str) {
                 .map(|r| r.id)
                 .collect();
 
-            let count = failed_or_cancelled_runs.len();
-            if count > 0 {
+            if !failed_or_cancelled_runs.is_empty() {
+                let bar = deletion_bar(failed_or_cancelled_runs.len());
+
                 // Chunk the runs into groups of 10 for concurrent deletion
-                let chunked_runs = failed_or_cancelled_runs.chunks(10);
+                let chunked_runs = failed_or_cancelled_runs.chunks(DELETE_BATCH);
                 for chunk in chunked_runs {
-                    let mut handles = Vec::new();
-
-                    for id in chunk {
-                        // Clone necessary parts for thread ownership
-                        let client_clone = client.client.clone();
-                        let token_clone = client.token.clone();
-                        let api_base_clone = client.api_base.clone();
-                        let repo_str = repo.to_string();
-                        let id_copy = *id;
-
-                        // Spawn a standard OS thread for deletion
-                        handles.push(thread::spawn(move || {
-                            let delete_path = format!("repos/{repo_str}/actions/runs/{id_copy}");
-                            let url = api_base_clone.join(&delete_path).unwrap();
-
-                            let res = client_clone
-                                .delete(url)
-                                .bearer_auth(token_clone)
-                                .header("Accept", "application/vnd.github+json")
-                                .header("X-GitHub-Api-Version", "2022-11-28")
-                                .send();
-
-                            if let Err(e) = res {
-                                log().err(&format!(
-                                    "{}",
-                                    format!("Error deleting workflow run {id_copy}: {e}").red()
-                                ));
-                            }
-                        }));
+                    if client.is_cancelled() {
+                        cleanup.interrupted = true;
+                        break;
                     }
 
                     // Wait for the current chunk of threads to finish (blocking)
-                    for h in handles {
-                        let _ = h.join();
-                    }
+                    cleanup.merge(thread::scope(|scope| {
+                        let mut handles = Vec::new();
+
+                        for &id in chunk {
+                            let bar = &bar;
+                            // Spawn a standard OS thread for deletion
+                            handles.push(scope.spawn(move || {
+                                client
+                                    .delete(&format!("repos/{repo}/actions/runs/{id}"))
+                                    .map(|()| bar.inc(1))
+                                    .map_err(|e| {
+                                        let msg = format!("Error deleting workflow run {id}: {e}");
+                                        log().err(&format!("{}", msg.red()));
+                                        msg
+                                    })
+                            }));
+                        }
+
+                        Cleanup::join(handles)
+                    }));
+                }
+                bar.finish_and_clear();
+
+                if cleanup.interrupted {
+                    log().warn(&format!(
+                        "Interrupted: {} of {} failed/cancelled workflows deleted.",
+                        cleanup.deleted,
+                        failed_or_cancelled_runs.len()
+                    ));
+                } else {
+                    log().ok(&format!(
+                        "{} failed/cancelled workflows deleted.",
+                        cleanup.deleted
+                    ));
                 }
-
-                log().ok(&format!("{count} failed/cancelled workflows deleted."));
             } else {
                 log().info("No failed/cancelled workflows found.");
             }
         }
         Err(e) => {
-            log().err(&format!("Error fetching workflow runs: {e}"));
+            let msg = format!("Error fetching workflow runs: {e}");
+            log().err(&msg);
+            cleanup.errors.push(msg);
         }
     }
     log().done("Done");
+    cleanup
 }
 
-/// Reruns failed workflow jobs.
-pub fn rerun_failed_jobs(client: &GitHubClient, repo: &str) {
-    println!("{}", format!("Rerun failed jobs for {repo}").yellow());
+/// Default for `maintain rerun --max-reruns`.
+pub const DEFAULT_MAX_RERUNS: usize = 10;
+
+/// Picks which failed runs to rerun: the newest failed run of each workflow
+/// (optionally only `workflow`), at most `max` of them.
+///
+/// `runs` must be newest first, as the runs API returns them.
+#[must_use]
+pub fn latest_failed_runs(
+    runs: Vec<WorkflowRun>,
+    workflow: Option<&str>,
+    max: usize,
+) -> Vec<WorkflowRun> {
+    let mut seen = HashSet::new();
+    runs.into_iter()
+        .filter(|r| r.conclusion.as_deref() == Some("failure"))
+        .filter(|r| workflow.is_none_or(|w| r.name.eq_ignore_ascii_case(w)))
+        .filter(|r| seen.insert(r.name.clone()))
+        .take(max)
+        .collect()
+}
+
+/// Reruns the latest failed run of each workflow, up to `max_reruns` runs.
+///
+/// `mode` decides whether only the failed jobs or every job of each run is rerun.
+pub fn rerun_failed_jobs(
+    client: &GitHubClient,
+    repo: &str,
+    workflow: Option<&str>,
+    max_reruns: usize,
+    mode: RerunMode,
+) {
+    eprintln!("{}", format!("Rerun failed jobs for {repo}").yellow());
+
+    if matches!(client.is_empty_repo(repo), Ok(true)) {
+        eprintln!(
+            "{}",
+            format!("Repository {repo} is empty, no jobs to rerun.").blue()
+        );
+        return;
+    }
 
+    // Runs come newest first, so stop paging once enough workflows have failed
     let path = &format!("repos/{repo}/actions/runs");
-    match client.fetch_paginated::<WorkflowRun>(path) {
-        Ok(runs) => {
-            let failed_runs: Vec<WorkflowRun> = runs
-                .into_iter()
+    let mut runs = Vec::new();
+    let mut failed_workflows = HashSet::new();
+    let fetched = client.for_each_page::<WorkflowRun, _>(path, MAX_PER_PAGE, |page| {
+        failed_workflows.extend(
+            page.iter()
                 .filter(|r| r.conclusion.as_deref() == Some("failure"))
-                .collect();
+                .filter(|r| workflow.is_none_or(|w| r.name.eq_ignore_ascii_case(w)))
+                .map(|r| r.name.clone()),
+        );
+        runs.extend(page);
+        if failed_workflows.len() >= max_reruns {
+            ControlFlow::Break(())
+        } else {
+            ControlFlow::Continue(())
+        }
+    });
+
+    match fetched {
+        Ok(()) => {
+            let failed_runs = latest_failed_runs(runs, workflow, max_reruns);
 
             if failed_runs.is_empty() {
-                println!("{}", "No failed jobs found to rerun.".blue());
+                eprintln!("{}", "No failed jobs found to rerun.".blue());
                 return;
             }
 
+            if failed_runs.len() == max_reruns {
+                eprintln!(
+                    "{}",
+                    format!("Rerunning at most {max_reruns} workflows (--max-reruns)").blue()
+                );
+            }
+
             for run in failed_runs {
-                println!(
+                eprintln!(
                     "{}",
                     format!("Rerunning job \"{}\" ({})", run.name, run.id).green()
                 );
                 let rerun_path =
-                    &format!("repos/{}/actions/runs/{}/rerun-failed-jobs", repo, run.id);
+                    &format!("repos/{}/actions/runs/{}/{}", repo, run.id, mode.endpoint());
 
                 // Use post with an empty body
                 let res = client.post::<_, serde_json::Value>(rerun_path, &serde_json::json!({}));
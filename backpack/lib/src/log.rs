@@ -1,9 +1,13 @@
 // Logger utilities
-#![allow(dropping_copy_types)]
 
-use crate::ghk::config;
+use crate::{ghk::config, github};
 use once_cell::sync::OnceCell;
-use std::{cell::RefCell, sync::Arc, time::Instant};
+use std::{
+    fs::OpenOptions,
+    path::Path,
+    sync::{Arc, Mutex},
+    time::Instant,
+};
 use terminal_banner::Banner;
 use tracing::{Level, debug, error, info, span, trace, warn};
 use tracing_subscriber::prelude::*;
@@ -14,7 +18,7 @@ use tracing_subscriber::{EnvFilter, Registry};
 /// Applications call `log().ok("message")` without needing to pass
 /// logger instances around. This keeps the API clean and ergonomic.
 ///
-/// The logger must be initialized once at startup using `set_logger()`.
+/// The logger should be initialized once at startup using `set_logger()`.
 static LOGGER: OnceCell<Arc<dyn ScreenLogger + Send + Sync>> = OnceCell::new();
 
 use std::sync::LazyLock;
@@ -87,6 +91,31 @@ pub fn banner(name: &str, desc: &str) {
     }
 }
 
+/// Turn off ANSI colors for `--nocolor` or `NO_COLOR`, or when stdout is piped.
+///
+/// Covers the `colored` output of maintain/merge, the ghk helpers and (when
+/// called before `init`) the tracing output. Set `CLICOLOR_FORCE=1` to keep
+/// colors when piping.
+pub fn init_color(nocolor: bool) {
+    use std::io::IsTerminal;
+
+    let forced = std::env::var("CLICOLOR_FORCE").is_ok_and(|v| v != "0");
+    let nocolor = nocolor || no_color_env(std::env::var_os("NO_COLOR").as_deref());
+    if plain_output(nocolor, std::io::stdout().is_terminal(), forced) {
+        colored::control::set_override(false);
+        config::setnocolor(true);
+    }
+}
+
+const fn plain_output(nocolor: bool, is_terminal: bool, forced: bool) -> bool {
+    nocolor || (!is_terminal && !forced)
+}
+
+/// `NO_COLOR` turns colors off when set to anything but the empty string (no-color.org)
+fn no_color_env(value: Option<&std::ffi::OsStr>) -> bool {
+    value.is_some_and(|v| !v.is_empty())
+}
+
 /// Set the global logger.
 ///
 /// This should be called once during program initialization.
@@ -97,16 +126,27 @@ pub fn set_logger<L: ScreenLogger + Send + Sync + 'static>(logger: L) {
 
 /// Retrieve the global logger.
 ///
-/// Panics if the logger has not been initialized.
-/// Applications should call `set_logger()` early in `main()`.
+/// Applications should call `set_logger()` early in `main()`; until then
+/// (e.g. in tests) a plain-text `SimpleLogger` is used.
 pub fn log() -> &'static Arc<dyn ScreenLogger + Send + Sync> {
-    LOGGER.get().expect("Logger not initialized")
+    LOGGER.get_or_init(|| Arc::new(Printer::new(SimpleLogger, LogFormat::Text)))
 }
 
-pub fn init() {
-    // 1. Define the formatted output (The Layer)
+/// Install the global tracing subscriber.
+///
+/// `RUST_LOG` takes precedence; otherwise the filter comes from `verbosity`.
+/// `--quiet` silences tracing output entirely.
+///
+/// With `log_file`, events are also appended to that file as JSON lines, at
+/// `Verbosity::file_directive` (so `--quiet` only quiets the console). The
+/// file is never rotated; leave that to logrotate or similar.
+pub fn init(verbosity: Verbosity, log_file: Option<&Path>) -> std::io::Result<()> {
+    // 1. Define the formatted output (The Layer). On stderr, so stdout stays
+    // clean for command output such as `--json` summaries.
     let telemetry_fmt = tracing_subscriber::fmt::layer()
-        .with_ansi(true)
+        .with_writer(std::io::stderr)
+        // Same decision as the console output, see `init_color`
+        .with_ansi(!config::isnocolor())
         .without_time()
         .compact()
         .with_file(true)
@@ -114,8 +154,14 @@ pub fn init() {
         .with_thread_ids(true)
         .with_target(false);
 
-    // 2. Define the first filter (Environment variable)
-    let env_filter = EnvFilter::from_default_env();
+    // 2. Define the first filter (Environment variable, falling back to the CLI flags)
+    let env_filter = match verbosity {
+        Verbosity::Quiet => EnvFilter::new(verbosity.filter_directive()),
+        _ => with_http_trace(
+            EnvFilter::try_from_default_env()
+                .unwrap_or_else(|_| EnvFilter::new(verbosity.filter_directive())),
+        ),
+    };
 
     // --- This does not work ---
     // 3. Combine the filters: Apply both the environment filter AND the max level filter.
@@ -123,11 +169,28 @@ pub fn init() {
     // allows an event to pass is the intersection of both.
     //let combined_filter = env_filter.and(max_level_filter);
 
-    // 4. Construct the registry, applying the format layer and the combined filter layer
+    // 4. Optionally also write structured logs to a file, appending across runs
+    let file_layer = log_file
+        .map(|path| -> std::io::Result<_> {
+            let file = OpenOptions::new().create(true).append(true).open(path)?;
+            let filter = with_http_trace(
+                EnvFilter::try_from_default_env()
+                    .unwrap_or_else(|_| EnvFilter::new(verbosity.file_directive())),
+            );
+            Ok(tracing_subscriber::fmt::layer()
+                .json()
+                .with_ansi(false)
+                .with_writer(Mutex::new(file))
+                .with_filter(filter))
+        })
+        .transpose()?;
+
+    // 5. Construct the registry, applying the format layer and the combined filter layer
     let registry = Registry::default()
         // Apply formatting layer, filtered by the combined filter
         //.with(telemetry_fmt.with_filter(combined_filter))
-        .with(telemetry_fmt.with_filter(env_filter));
+        .with(telemetry_fmt.with_filter(env_filter))
+        .with(file_layer);
     // Send traces to tokio console
     //.with(console_subscriber::spawn());
 
@@ -138,6 +201,18 @@ pub fn init() {
     tracing::debug!("Logging initialized!");
     tracing::trace!("Tracing initialized!");
     tracing::debug!("Ready to begin...");
+    Ok(())
+}
+
+/// With `--trace-http`, lets the request/response logs through whatever the verbosity.
+fn with_http_trace(filter: EnvFilter) -> EnvFilter {
+    if !github::trace_http() {
+        return filter;
+    }
+    match format!("{}=trace", github::HTTP_TRACE_TARGET).parse() {
+        Ok(directive) => filter.add_directive(directive),
+        Err(_) => filter,
+    }
 }
 
 /// Cargo-style verbosity levels.
@@ -154,6 +229,30 @@ pub enum Verbosity {
     Trace,   // -vv
 }
 
+impl Verbosity {
+    /// `EnvFilter` directive used when `RUST_LOG` is not set
+    #[must_use]
+    pub const fn filter_directive(self) -> &'static str {
+        match self {
+            Self::Quiet => "off",
+            Self::Normal => "error",
+            Self::Verbose => "debug",
+            Self::Trace => "trace",
+        }
+    }
+
+    /// Like `filter_directive`, for `--log-file`: a log file is only useful
+    /// with more than errors in it, so it starts at `info`.
+    #[must_use]
+    pub const fn file_directive(self) -> &'static str {
+        match self {
+            Self::Quiet | Self::Normal => "info",
+            Self::Verbose => "debug",
+            Self::Trace => "trace",
+        }
+    }
+}
+
 /// Output format for the logger.
 ///
 /// Text → human‑friendly CLI output (println / eprintln)
@@ -502,10 +601,10 @@ pub struct Printer<L: FormatLogger> {
     inner: L,
 
     /// Stack of active task spans created by `intro()`
-    tasks: RefCell<Vec<TimedSpan>>,
+    tasks: Mutex<Vec<TimedSpan>>,
 
     /// Stack of active step spans created by `step()`
-    steps: RefCell<Vec<()>>,
+    steps: Mutex<Vec<()>>,
 
     /// Output format: Text (default) or Json
     ///
@@ -521,8 +620,8 @@ impl<L: FormatLogger> Printer<L> {
     pub const fn new(inner: L, format: LogFormat) -> Self {
         Self {
             inner,
-            tasks: RefCell::new(Vec::new()),
-            steps: RefCell::new(Vec::new()),
+            tasks: Mutex::new(Vec::new()),
+            steps: Mutex::new(Vec::new()),
             format,
         }
     }
@@ -544,7 +643,7 @@ impl<L: FormatLogger> ScreenLogger for Printer<L> {
                     let _sp = span!(Level::INFO, "task", message = %m);
 
                     // Push a timed marker
-                    self.tasks.borrow_mut().push(TimedSpan {
+                    self.tasks.lock().unwrap().push(TimedSpan {
                         start: Instant::now(),
                     });
 
@@ -570,13 +669,10 @@ impl<L: FormatLogger> ScreenLogger for Printer<L> {
                 // Verbose/Trace: close spans + timing
                 (Verbosity::Verbose | Verbosity::Trace, LogFormat::Text) => {
                     // Close all step spans first (automatic cleanup)
-                    let mut steps = self.steps.borrow_mut();
-                    while let Some(step) = steps.pop() {
-                        drop(step);
-                    }
+                    self.steps.lock().unwrap().clear();
 
                     // Close the task span
-                    if let Some(TimedSpan { start }) = self.tasks.borrow_mut().pop() {
+                    if let Some(TimedSpan { start }) = self.tasks.lock().unwrap().pop() {
                         let elapsed = start.elapsed();
 
                         // Emit outro message with timing
@@ -602,13 +698,10 @@ impl<L: FormatLogger> ScreenLogger for Printer<L> {
                 // Verbose/Trace: close spans + timing
                 (Verbosity::Verbose | Verbosity::Trace, LogFormat::Text) => {
                     // Close all step spans first (automatic cleanup)
-                    let mut steps = self.steps.borrow_mut();
-                    while let Some(step) = steps.pop() {
-                        drop(step);
-                    }
+                    self.steps.lock().unwrap().clear();
 
                     // Close the task span
-                    if let Some(TimedSpan { start }) = self.tasks.borrow_mut().pop() {
+                    if let Some(TimedSpan { start }) = self.tasks.lock().unwrap().pop() {
                         let elapsed = start.elapsed();
 
                         // Emit done message with timing
@@ -634,10 +727,10 @@ impl<L: FormatLogger> ScreenLogger for Printer<L> {
                 // Verbose/Trace: nested spans
                 (Verbosity::Verbose | Verbosity::Trace, LogFormat::Text) => {
                     // Automatically close previous step marker if one exists
-                    let _ = self.steps.borrow_mut().pop();
+                    let _ = self.steps.lock().unwrap().pop();
 
                     // Push a simple step marker
-                    self.steps.borrow_mut().push(());
+                    self.steps.lock().unwrap().push(());
 
                     // Emit step message through tracing
                     info!("{s}");
@@ -866,6 +959,39 @@ mod tests {
         assert!(l.ok("hi").is_some());
     }
 
+    #[test]
+    fn plain_output_when_piped_or_requested() {
+        assert!(plain_output(true, true, false));
+        assert!(plain_output(false, false, false));
+        assert!(!plain_output(false, true, false));
+        // CLICOLOR_FORCE keeps colors in pipes, but --nocolor still wins
+        assert!(!plain_output(false, false, true));
+        assert!(plain_output(true, false, true));
+    }
+
+    #[test]
+    fn no_color_env_needs_a_value() {
+        assert!(no_color_env(Some(std::ffi::OsStr::new("1"))));
+        assert!(!no_color_env(Some(std::ffi::OsStr::new(""))));
+        assert!(!no_color_env(None));
+    }
+
+    #[test]
+    fn verbosity_filter_directives() {
+        assert_eq!(Verbosity::Quiet.filter_directive(), "off");
+        assert_eq!(Verbosity::Normal.filter_directive(), "error");
+        assert_eq!(Verbosity::Verbose.filter_directive(), "debug");
+        assert_eq!(Verbosity::Trace.filter_directive(), "trace");
+    }
+
+    #[test]
+    fn log_file_directives_ignore_quiet() {
+        assert_eq!(Verbosity::Quiet.file_directive(), "info");
+        assert_eq!(Verbosity::Normal.file_directive(), "info");
+        assert_eq!(Verbosity::Verbose.file_directive(), "debug");
+        assert_eq!(Verbosity::Trace.file_directive(), "trace");
+    }
+
     #[test]
     fn modern_logger_raws() {
         let m = ModernLogger;
@@ -1,25 +1,70 @@
-use colored::Color;
+use colored::{Color, Colorize};
 use rhai::EvalAltResult;
-use std::fmt::Display;
+
+/// Parses a color name or hex string.
+///
+/// Accepts the eight basic names, their `bright_` variants, `gray`/`grey`, and
+/// `#rrggbb` or `#rgb` hex strings. Names are case-insensitive and may use `-`
+/// instead of `_`.
+pub fn parse_color(color_name: &str) -> Option<Color> {
+    let name = color_name.trim().to_lowercase().replace('-', "_");
+
+    if let Some(hex) = name.strip_prefix('#') {
+        return parse_hex(hex);
+    }
+
+    let color = match name.as_str() {
+        "red" => Color::Red,
+        "green" => Color::Green,
+        "blue" => Color::Blue,
+        "yellow" => Color::Yellow,
+        "cyan" => Color::Cyan,
+        "magenta" | "purple" => Color::Magenta,
+        "white" => Color::White,
+        "black" => Color::Black,
+        "gray" | "grey" | "bright_black" => Color::BrightBlack,
+        "bright_red" => Color::BrightRed,
+        "bright_green" => Color::BrightGreen,
+        "bright_blue" => Color::BrightBlue,
+        "bright_yellow" => Color::BrightYellow,
+        "bright_cyan" => Color::BrightCyan,
+        "bright_magenta" | "bright_purple" => Color::BrightMagenta,
+        "bright_white" => Color::BrightWhite,
+        _ => return None,
+    };
+    Some(color)
+}
+
+fn parse_hex(hex: &str) -> Option<Color> {
+    // The slicing below is by byte, so a multi-byte char would panic
+    if !hex.is_ascii() {
+        return None;
+    }
+    let channel = |s: &str| u8::from_str_radix(s, 16).ok();
+
+    let (r, g, b) = match hex.len() {
+        6 => (
+            channel(&hex[0..2])?,
+            channel(&hex[2..4])?,
+            channel(&hex[4..6])?,
+        ),
+        // #rgb is shorthand for #rrggbb
+        3 => (
+            channel(&hex[0..1])? * 17,
+            channel(&hex[1..2])? * 17,
+            channel(&hex[2..3])? * 17,
+        ),
+        _ => return None,
+    };
+    Some(Color::TrueColor { r, g, b })
+}
 
 /// A simple function to print a message in a specific color.
 /// This function is registered with Rhai as `cprint(message, color)`.
 pub fn cprint(message: &str, color_name: &str) -> Result<(), Box<EvalAltResult>> {
-    // Helper function to print the colored text
-    fn print_colored_text<T: Display + colored::Colorize>(text: T, color: Color) {
-        println!("{}", text.color(color));
-    }
-
-    match color_name.to_lowercase().as_str() {
-        "red" => print_colored_text(message, Color::Red),
-        "green" => print_colored_text(message, Color::Green),
-        "blue" => print_colored_text(message, Color::Blue),
-        "yellow" => print_colored_text(message, Color::Yellow),
-        "cyan" => print_colored_text(message, Color::Cyan),
-        "magenta" => print_colored_text(message, Color::Magenta),
-        "white" => print_colored_text(message, Color::White),
-        "black" => print_colored_text(message, Color::Black),
-        _ => {
+    match parse_color(color_name) {
+        Some(color) => println!("{}", message.color(color)),
+        None => {
             // If the color is unknown, just print the message normally and give a warning.
             eprintln!("Rhai Color Error: Unknown color '{color_name}'. Printing uncolored.");
             println!("{message}");
@@ -27,3 +72,26 @@ pub fn cprint(message: &str, color_name: &str) -> Result<(), Box<EvalAltResult>>
     }
     Ok(())
 }
+
+/// Prints a message with a foreground and background color.
+/// This function is registered with Rhai as `cprint_bg(message, color, background)`.
+pub fn cprint_bg(
+    message: &str,
+    color_name: &str,
+    background_name: &str,
+) -> Result<(), Box<EvalAltResult>> {
+    let mut text = message.normal();
+
+    for (name, is_background) in [(color_name, false), (background_name, true)] {
+        match parse_color(name) {
+            Some(color) if is_background => text = text.on_color(color),
+            Some(color) => text = text.color(color),
+            None => {
+                eprintln!("Rhai Color Error: Unknown color '{name}'. Ignoring it.");
+            }
+        }
+    }
+
+    println!("{text}");
+    Ok(())
+}
@@ -0,0 +1,89 @@
+use reqwest::redirect::Policy;
+use rhai::EvalAltResult;
+use std::time::Duration;
+use url::Url;
+
+/// Same limit as reqwest's default policy.
+const MAX_REDIRECTS: usize = 10;
+
+/// Whether `host` matches an `allowed_hosts` entry.
+///
+/// Entries match exactly (case-insensitive); a leading `*.` also matches any subdomain.
+#[must_use]
+pub fn is_allowed(allowed_hosts: &[String], host: &str) -> bool {
+    let host = host.to_ascii_lowercase();
+    allowed_hosts.iter().any(|entry| {
+        let entry = entry.to_ascii_lowercase();
+        match entry.strip_prefix("*.") {
+            Some(domain) => host == domain || host.ends_with(&format!(".{domain}")),
+            None => host == entry,
+        }
+    })
+}
+
+/// Performs a GET request on behalf of a plugin, restricted to its `allowed_hosts`.
+/// This function is registered with Rhai as `http_get(url)`.
+pub fn http_get(
+    plugin_name: &str,
+    allowed_hosts: &[String],
+    url: &str,
+) -> Result<String, Box<EvalAltResult>> {
+    let parsed = Url::parse(url).map_err(|e| format!("Invalid URL '{url}': {e}"))?;
+
+    if !matches!(parsed.scheme(), "http" | "https") {
+        return Err(
+            format!("HTTP request to '{url}' blocked: only http and https are supported").into(),
+        );
+    }
+
+    let host = parsed.host_str().unwrap_or_default();
+    if !is_allowed(allowed_hosts, host) {
+        return Err(format!(
+            "HTTP request to '{host}' blocked: plugin '{plugin_name}' may only contact hosts listed in \
+             `allowed_hosts` in its manifest.toml (currently: {})",
+            if allowed_hosts.is_empty() {
+                "none".to_string()
+            } else {
+                allowed_hosts.join(", ")
+            }
+        )
+        .into());
+    }
+
+    // Each redirect is checked too, or an allowed host could send the request anywhere
+    let hosts = allowed_hosts.to_vec();
+    let redirects = Policy::custom(move |attempt| {
+        let host = attempt.url().host_str().unwrap_or_default().to_string();
+        if !is_allowed(&hosts, &host) {
+            attempt.error(format!(
+                "redirect to '{host}' blocked: it is not in `allowed_hosts`"
+            ))
+        } else if attempt.previous().len() >= MAX_REDIRECTS {
+            attempt.error("too many redirects")
+        } else {
+            attempt.follow()
+        }
+    });
+
+    // The blocking client can't run on a tokio worker, so make the request on its own thread
+    std::thread::spawn(move || {
+        reqwest::blocking::Client::builder()
+            .timeout(Duration::from_secs(10))
+            .user_agent(crate::github::user_agent())
+            .redirect(redirects)
+            .build()?
+            .get(parsed)
+            .send()?
+            .error_for_status()?
+            .text()
+    })
+    .join()
+    .map_err(|_| "HTTP request thread panicked".to_string())?
+    .map_err(|e| {
+        // A blocked redirect's reason is the error's source
+        let reason = std::error::Error::source(&e)
+            .map(|source| format!(": {source}"))
+            .unwrap_or_default();
+        format!("HTTP request to '{url}' failed: {e}{reason}").into()
+    })
+}
@@ -1,18 +1,24 @@
 mod color;
+mod http;
 
 use anyhow::{Context, Result};
 use rhai::serde::to_dynamic;
-use rhai::{Dynamic, Engine, Scope};
+use rhai::{Dynamic, Engine, EvalAltResult, Map, Scope};
 use serde::{Deserialize, Serialize};
 use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
 
 // --- Configuration Constants ---
 
 pub const APP_NAME: &str = "github-bot";
 pub const PLUGINS_DIR: &str = "plugins";
+/// Environment variable that overrides the plugins directory.
+pub const PLUGINS_DIR_ENV: &str = "GITHUB_BOT_PLUGINS_DIR";
 pub const MANIFEST_FILENAME: &str = "manifest.toml";
-//pub const SCRIPT_FILENAME: &str = "run.sh";
 pub const SCRIPT_FILENAME: &str = "run.rhai";
+pub const BASH_SCRIPT_FILENAME: &str = "run.sh";
+/// How long a plugin script may run before it is terminated.
+pub const DEFAULT_SCRIPT_TIMEOUT: Duration = Duration::from_secs(5);
 
 // --- Data Structures ---
 
@@ -25,6 +31,17 @@ pub struct Manifest {
     pub repo: Option<String>,
     pub license: Option<String>,
     pub author: String,
+    /// Event names (e.g. `cli-command-execution-run`) this plugin wants.
+    /// Empty or absent means every event.
+    #[serde(default)]
+    pub events: Vec<String>,
+    /// Hosts a Rhai script may reach with `http_get` (`*.example.com` matches subdomains).
+    /// Empty or absent means no HTTP access from Rhai.
+    ///
+    /// This does not sandbox `run.sh` plugins: bash can reach any host with
+    /// curl and the like, so only install ones you trust.
+    #[serde(default)]
+    pub allowed_hosts: Vec<String>,
 }
 
 /// Represents an event that can be broadcast to plugins.
@@ -34,9 +51,74 @@ pub enum Event {
     PluginRegistrationInit,
     PluginRegistered(String),
     PluginRegistrationEnd,
+    /// Fired once after argument parsing, before any command is dispatched.
     CliCommandExecutionInit,
-    CliCommandExecutionRun { command: String, args: Vec<String> },
-    CliCommandExecutionEnd,
+    CliCommandExecutionRun {
+        command: String,
+        args: Vec<String>,
+    },
+    /// Fired once at the end of the run, whether the command succeeded or not.
+    CliCommandExecutionEnd {
+        exit_code: i32,
+    },
+    /// Fired just before a PR is merged.
+    PrMergeInit {
+        repo: String,
+        pr_number: u64,
+        title: String,
+    },
+    /// Fired after a PR was merged. `sha` is the merge commit, when GitHub reports one.
+    PrMerged {
+        repo: String,
+        pr_number: u64,
+        sha: Option<String>,
+    },
+    /// Fired when merging a PR did not succeed.
+    PrMergeFailed {
+        repo: String,
+        pr_number: u64,
+        reason: String,
+    },
+}
+
+impl Event {
+    /// Names of all events, as used in `manifest.toml`.
+    pub const NAMES: &[&str] = &[
+        "plugin-registration-init",
+        "plugin-registered",
+        "plugin-registration-end",
+        "cli-command-execution-init",
+        "cli-command-execution-run",
+        "cli-command-execution-end",
+        "pr-merge-init",
+        "pr-merged",
+        "pr-merge-failed",
+    ];
+
+    /// The kebab-case variant name, matching its serialized form.
+    #[must_use]
+    pub const fn name(&self) -> &'static str {
+        match self {
+            Self::PluginRegistrationInit => "plugin-registration-init",
+            Self::PluginRegistered(_) => "plugin-registered",
+            Self::PluginRegistrationEnd => "plugin-registration-end",
+            Self::CliCommandExecutionInit => "cli-command-execution-init",
+            Self::CliCommandExecutionRun { .. } => "cli-command-execution-run",
+            Self::CliCommandExecutionEnd { .. } => "cli-command-execution-end",
+            Self::PrMergeInit { .. } => "pr-merge-init",
+            Self::PrMerged { .. } => "pr-merged",
+            Self::PrMergeFailed { .. } => "pr-merge-failed",
+        }
+    }
+}
+
+/// How a plugin's script is executed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScriptKind {
+    /// `run.rhai`, evaluated in-process with the event as `event_data`.
+    Rhai,
+    /// `run.sh`, run with bash and the event JSON as the first argument.
+    Bash,
 }
 
 /// Represents a loaded plugin, containing its manifest data and path.
@@ -45,18 +127,30 @@ pub struct Plugin {
     pub manifest: Manifest,
     pub path: PathBuf,
     pub script_path: PathBuf,
+    pub kind: ScriptKind,
+    /// Maximum wall-clock time for a single script run.
+    pub timeout: Duration,
 }
 
 impl Plugin {
     /// Attempts to load a plugin from a given directory path.
     pub fn from_dir(path: &Path) -> Result<Self> {
         let manifest_path = path.join(MANIFEST_FILENAME);
-        let script_path = path.join(SCRIPT_FILENAME); // Check for .rhai
-
-        // 1. Check if run.rhai exists
-        if !script_path.exists() {
-            anyhow::bail!("Missing required script: {}", script_path.display());
-        }
+        let rhai_path = path.join(SCRIPT_FILENAME);
+        let bash_path = path.join(BASH_SCRIPT_FILENAME);
+
+        // 1. Check for run.rhai, then run.sh
+        let (script_path, kind) = if rhai_path.exists() {
+            (rhai_path, ScriptKind::Rhai)
+        } else if bash_path.exists() {
+            (bash_path, ScriptKind::Bash)
+        } else {
+            anyhow::bail!(
+                "Missing required script: {} or {}",
+                rhai_path.display(),
+                bash_path.display()
+            );
+        };
 
         // 2. Read and parse manifest.toml
         let manifest_content = std::fs::read_to_string(&manifest_path).with_context(|| {
@@ -66,18 +160,122 @@ impl Plugin {
             format!("Failed to parse TOML manifest: {}", manifest_path.display())
         })?;
 
+        // 3. Warn about subscriptions that can never fire
+        for name in &manifest.events {
+            if !Event::NAMES.contains(&name.as_str()) {
+                tracing::warn!(
+                    "Plugin '{}' subscribes to unknown event '{name}'. Known events: {}",
+                    manifest.name,
+                    Event::NAMES.join(", ")
+                );
+            }
+        }
+
+        // 4. An allowlist suggests a sandbox that bash scripts don't have
+        if let Some(warning) = unenforced_hosts_warning(&manifest, kind) {
+            tracing::warn!("{warning}");
+        }
+
         Ok(Self {
             manifest,
             path: path.to_path_buf(),
             script_path,
+            kind,
+            timeout: DEFAULT_SCRIPT_TIMEOUT,
         })
     }
 
-    /// Executes the plugin's Rhai script, passing the event data.
-    pub async fn run_script(&self, event: &Event) -> Result<()> {
-        fn get_rhai_engine() -> Engine {
+    /// Whether this plugin should receive `event`.
+    #[must_use]
+    pub fn subscribes_to(&self, event: &Event) -> bool {
+        self.subscribes_to_name(event.name())
+    }
+
+    /// Whether this plugin should receive the event called `name` (see [`Event::NAMES`]).
+    #[must_use]
+    pub fn subscribes_to_name(&self, name: &str) -> bool {
+        self.manifest.events.is_empty() || self.manifest.events.iter().any(|e| e == name)
+    }
+
+    /// Checks that the manifest is complete and the script parses, without running it.
+    ///
+    /// Rhai scripts are compiled; `run.sh` scripts are checked with `bash -n`.
+    pub fn validate(&self) -> Result<()> {
+        for (field, value) in [
+            ("name", &self.manifest.name),
+            ("description", &self.manifest.description),
+            ("author", &self.manifest.author),
+        ] {
+            if value.trim().is_empty() {
+                anyhow::bail!("Manifest field '{field}' is empty");
+            }
+        }
+
+        match self.kind {
+            ScriptKind::Rhai => {
+                let script_content =
+                    std::fs::read_to_string(&self.script_path).with_context(|| {
+                        format!("Failed to read Rhai script: {}", self.script_path.display())
+                    })?;
+
+                if let Err(e) = Engine::new().compile(&script_content) {
+                    let line = e.1.line().unwrap_or_default();
+                    let column = e.1.position().unwrap_or_default();
+                    anyhow::bail!(
+                        "Syntax error in {} at line {line}, column {column}: {}",
+                        self.script_path.display(),
+                        e.0
+                    );
+                }
+            }
+            ScriptKind::Bash => {
+                let output = std::process::Command::new(shell())
+                    .arg("-n")
+                    .arg(&self.script_path)
+                    .output()
+                    .with_context(|| format!("Failed to check {}", self.script_path.display()))?;
+
+                if !output.status.success() {
+                    anyhow::bail!(
+                        "Syntax error in {}:\n{}",
+                        self.script_path.display(),
+                        String::from_utf8_lossy(&output.stderr).trim()
+                    );
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Executes the plugin's script, passing the event data, and returns the script's value.
+    ///
+    /// Rhai scripts return their last expression. `run.sh` scripts return their
+    /// trimmed stdout, with `true`/`false` turned into booleans and a JSON
+    /// object (e.g. `{"veto": true}`) into a map.
+    /// The script is terminated with an error if it runs longer than `self.timeout`.
+    pub async fn run_script(&self, event: &Event) -> Result<Dynamic> {
+        match self.kind {
+            ScriptKind::Rhai => self.run_rhai(event),
+            ScriptKind::Bash => self.run_bash(event).await,
+        }
+    }
+
+    /// Evaluates `run.rhai` with the event available as `event_data`.
+    fn run_rhai(&self, event: &Event) -> Result<Dynamic> {
+        fn get_rhai_engine(deadline: Instant, manifest: &Manifest) -> Engine {
             let mut engine = Engine::new();
 
+            // Abort runaway scripts (e.g. infinite loops). Checking the clock on
+            // every operation is wasteful, so only look every 1024 operations.
+            engine.on_progress(move |ops| {
+                if ops % 1024 == 0 && Instant::now() >= deadline {
+                    Some(Dynamic::UNIT)
+                } else {
+                    None
+                }
+            });
+
             // Register the custom color printing function.
             engine.register_fn("cprint", color::cprint);
 
@@ -86,10 +284,18 @@ impl Plugin {
             engine.register_fn("print_green", |message: &str| {
                 color::cprint(message, "green")
             });
+            engine.register_fn("cprint_bg", color::cprint_bg);
+
+            // `return veto("reason");` blocks the action a gated event asks about
+            engine.register_fn("veto", || veto_value(""));
+            engine.register_fn("veto", |reason: &str| veto_value(reason));
 
-            // FIX: Add HTTP fetch via http::client().request
-            //use rhai::packages::Package;
-            //rhai_http::HttpPackage::new().register_into_engine(&mut engine);
+            // HTTP access is limited to the hosts the manifest allows.
+            let plugin_name = manifest.name.clone();
+            let allowed_hosts = manifest.allowed_hosts.clone();
+            engine.register_fn("http_get", move |url: &str| {
+                http::http_get(&plugin_name, &allowed_hosts, url)
+            });
 
             engine
         }
@@ -100,7 +306,7 @@ impl Plugin {
             tracing::debug!("-> Executing plugin '{plugin_name}' for event: {event:?}");
         }
 
-        let engine = get_rhai_engine();
+        let engine = get_rhai_engine(Instant::now() + self.timeout, &self.manifest);
 
         // Convert the Event struct to a Rhai Dynamic value (Map/Object)
         let event_data = to_dynamic(event)
@@ -126,7 +332,13 @@ impl Plugin {
                 if !cfg!(test) {
                     tracing::debug!("  [Plugin {plugin_name} RESULT]: {result:?}");
                 }
-                Ok(())
+                Ok(result)
+            }
+            Err(e) if matches!(*e, EvalAltResult::ErrorTerminated(..)) => {
+                anyhow::bail!(
+                    "Plugin '{plugin_name}' script timed out after {:?}",
+                    self.timeout
+                );
             }
             Err(e) => {
                 // Rhai execution error (script syntax error, runtime error, etc.)
@@ -136,87 +348,164 @@ impl Plugin {
             }
         }
     }
-}
 
-/*
-use std::process::ExitStatus;
-use tokio::process::Command;
-
-impl BashPlugin {
-    /// Attempts to load a plugin from a given directory path.
-    pub fn from_dir(path: &Path) -> Result<Self> {
-        let manifest_path = path.join(MANIFEST_FILENAME);
-        let script_path = path.join(SCRIPT_FILENAME);
-
-        // 1. Check if run.sh exists and is executable
-        if !script_path.exists() {
-            anyhow::bail!("Missing required script: {}", script_path.display());
-        }
-        // NOTE: Checking for actual executable permission is OS-dependent and complex.
-        // We'll trust the user has set it up correctly for demonstration.
-
-        // 2. Read and parse manifest.toml
-        let manifest_content = std::fs::read_to_string(&manifest_path).with_context(|| {
-            format!("Failed to read manifest file: {}", manifest_path.display())
-        })?;
-        let manifest: Manifest = toml::from_str(&manifest_content).with_context(|| {
-            format!("Failed to parse TOML manifest: {}", manifest_path.display())
-        })?;
-
-        Ok(Self {
-            manifest,
-            path: path.to_path_buf(),
-            script_path,
-        })
-    }
-
-    /// Executes the plugin's shell script, passing the event as a JSON argument.
-    pub async fn run_script(&self, event: &Event) -> Result<ExitStatus> {
-        let event_json = serde_json::to_string(event).unwrap();
+    /// Runs `run.sh` with bash, passing the event as a JSON argument.
+    async fn run_bash(&self, event: &Event) -> Result<Dynamic> {
+        let event_json = serde_json::to_string(event)?;
         let plugin_name = &self.manifest.name;
 
-        tracing::debug!("-> Executing plugin '{plugin_name}' for event: {event:?}");
+        if !cfg!(test) {
+            tracing::debug!("-> Executing plugin '{plugin_name}' for event: {event:?}");
+        }
 
-        // Use a standard shell (like /bin/sh or cmd.exe) to execute the script.
-        // We pass the event JSON as the first command-line argument.
-        let output = Command::new("/bin/bash")
+        // Execute the script in the plugin's directory, with the event JSON as $1.
+        // The child is killed if the timeout drops the future.
+        let output = tokio::process::Command::new(shell())
             .arg(&self.script_path)
             .arg(event_json)
-            // Execute the command in the plugin's directory context
             .current_dir(&self.path)
-            .output()
+            .kill_on_drop(true)
+            .output();
+
+        let output = tokio::time::timeout(self.timeout, output)
             .await
+            .map_err(|_| {
+                anyhow::anyhow!(
+                    "Plugin '{plugin_name}' script timed out after {:?}",
+                    self.timeout
+                )
+            })?
             .with_context(|| format!("Failed to execute plugin script for '{plugin_name}'"))?;
 
         if !output.status.success() {
             let stderr = String::from_utf8_lossy(&output.stderr);
             anyhow::bail!(
-                "Plugin '{}' script failed with status {:?}.\nStderr:\n{}",
-                plugin_name,
+                "Plugin '{plugin_name}' script failed with status {}.\nStderr:\n{}",
                 output.status,
-                stderr
+                stderr.trim()
             );
         }
 
         // Print stdout from the script for visibility
         let stdout = String::from_utf8_lossy(&output.stdout);
-        if !stdout.is_empty() {
-            tracing::info!("  [Plugin {} STDOUT]:\n{}", plugin_name, stdout.trim());
+        if !stdout.is_empty() && !cfg!(test) {
+            tracing::info!("  [Plugin {plugin_name} STDOUT]:\n{}", stdout.trim());
+        }
+
+        Ok(match stdout.trim() {
+            "" => Dynamic::UNIT,
+            "true" => Dynamic::TRUE,
+            "false" => Dynamic::FALSE,
+            other => match serde_json::from_str::<serde_json::Value>(other) {
+                Ok(object @ serde_json::Value::Object(_)) => {
+                    to_dynamic(object).map_err(|e| anyhow::anyhow!("{e}"))?
+                }
+                _ => Dynamic::from(other.to_string()),
+            },
+        })
+    }
+}
+
+/// A warning for a `run.sh` plugin that lists `allowed_hosts`, which only Rhai's
+/// `http_get` enforces.
+fn unenforced_hosts_warning(manifest: &Manifest, kind: ScriptKind) -> Option<String> {
+    (kind == ScriptKind::Bash && !manifest.allowed_hosts.is_empty()).then(|| {
+        format!(
+            "Plugin '{}' is a run.sh script: its allowed_hosts ({}) only apply to Rhai's \
+             http_get, so it can still reach any host",
+            manifest.name,
+            manifest.allowed_hosts.join(", ")
+        )
+    })
+}
+
+/// What Rhai's `veto(reason)` returns: `#{ veto: true, reason: "..." }`.
+fn veto_value(reason: &str) -> Map {
+    let mut map = Map::new();
+    map.insert("veto".into(), Dynamic::TRUE);
+    map.insert("reason".into(), reason.into());
+    map
+}
+
+/// The reason when a script's return value vetoes the action it was asked
+/// about, empty if it gave none.
+///
+/// Only an explicit veto counts: a map with `veto: true`, as built by `veto()`
+/// in Rhai or printed as JSON by `run.sh`. Any other value, `false` included,
+/// lets the action proceed, so scripts written before vetoes existed can't
+/// block by accident.
+#[must_use]
+pub fn veto_reason(value: &Dynamic) -> Option<String> {
+    let map = value.read_lock::<Map>()?;
+    if map.get("veto")?.as_bool() != Ok(true) {
+        return None;
+    }
+    Some(
+        map.get("reason")
+            .and_then(|r| r.clone().into_string().ok())
+            .unwrap_or_default(),
+    )
+}
+
+/// Whether a script's return value vetoes the action it was asked about, see `veto_reason`.
+#[must_use]
+pub fn is_veto(value: &Dynamic) -> bool {
+    veto_reason(value).is_some()
+}
+
+/// A plugin blocked the action an event asked about.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Veto {
+    pub plugin: String,
+    pub event: String,
+    /// Empty when the plugin gave none
+    pub reason: String,
+}
+
+impl std::fmt::Display for Veto {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Plugin '{}' vetoed {}", self.plugin, self.event)?;
+        if !self.reason.is_empty() {
+            write!(f, ": {}", self.reason)?;
         }
+        Ok(())
+    }
+}
 
-        Ok(output.status)
+impl std::error::Error for Veto {}
+
+/// The shell used for `run.sh` plugins.
+///
+/// `/bin/bash` on Unix; elsewhere (e.g. Windows with Git Bash or WSL) the first
+/// `bash` or `sh` found on `PATH`.
+fn shell() -> PathBuf {
+    if cfg!(unix) {
+        return PathBuf::from("/bin/bash");
     }
+    which::which("bash")
+        .or_else(|_| which::which("sh"))
+        .unwrap_or_else(|_| PathBuf::from("sh"))
 }
-*/
 
 // --- Core Functions ---
 
-/// Finds and loads all plugins from the standard configuration directory.
-pub fn discover_plugins() -> Result<Vec<Plugin>> {
-    let config_dir = dirs::config_dir()
+/// The directory plugins are loaded from.
+///
+/// `GITHUB_BOT_PLUGINS_DIR` overrides the default `<config dir>/github-bot/plugins`.
+pub fn plugins_dir() -> Result<PathBuf> {
+    if let Some(dir) = std::env::var_os(PLUGINS_DIR_ENV).filter(|d| !d.is_empty()) {
+        return Ok(PathBuf::from(dir));
+    }
+
+    Ok(dirs::config_dir()
         .context("Could not determine config directory.")?
         .join(APP_NAME)
-        .join(PLUGINS_DIR);
+        .join(PLUGINS_DIR))
+}
+
+/// Finds and loads all plugins from the standard configuration directory.
+pub fn discover_plugins() -> Result<Vec<Plugin>> {
+    let config_dir = plugins_dir()?;
 
     if !config_dir.exists() {
         tracing::debug!(
@@ -252,22 +541,39 @@ pub fn discover_plugins() -> Result<Vec<Plugin>> {
     Ok(plugins)
 }
 
+/// What happened when a plugin handled a broadcast event.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PluginOutcome {
+    pub name: String,
+    pub success: bool,
+    pub error: Option<String>,
+}
+
 /// Broadcasts a given event to all loaded plugins in parallel.
-pub async fn broadcast_event(plugins: &[Plugin], event: Event) {
+///
+/// Returns one outcome per plugin that received the event. Failures are also
+/// printed, so callers that don't care can ignore the result.
+pub async fn broadcast_event(plugins: &[Plugin], event: Event) -> Vec<PluginOutcome> {
     let tasks: Vec<_> = plugins
         .iter()
+        .filter(|plugin| plugin.subscribes_to(&event))
         .map(|plugin| {
             let event = event.clone();
             async move {
+                let name = plugin.manifest.name.clone();
                 match plugin.run_script(&event).await {
-                    Ok(()) => {
-                        // Script executed successfully
-                    }
+                    Ok(_) => PluginOutcome {
+                        name,
+                        success: true,
+                        error: None,
+                    },
                     Err(e) => {
-                        eprintln!(
-                            "Plugin execution failure for '{}': {:?}",
-                            plugin.manifest.name, e
-                        );
+                        eprintln!("Plugin execution failure for '{name}': {e:?}");
+                        PluginOutcome {
+                            name,
+                            success: false,
+                            error: Some(format!("{e:#}")),
+                        }
                     }
                 }
             }
@@ -275,7 +581,43 @@ pub async fn broadcast_event(plugins: &[Plugin], event: Event) {
         .collect();
 
     // Run all plugin scripts concurrently
-    futures::future::join_all(tasks).await;
+    futures::future::join_all(tasks).await
+}
+
+/// Broadcasts an event to plugins one at a time, stopping at the first veto.
+///
+/// A plugin vetoes by returning `veto()` (see `veto_reason`); the error is
+/// then a `Veto`. Plugins that fail are reported but do not block. Use this
+/// before an action plugins should be able to prevent.
+pub async fn broadcast_gate(plugins: &[Plugin], event: Event) -> Result<()> {
+    match find_veto(plugins, event).await {
+        Some(veto) => Err(veto.into()),
+        None => Ok(()),
+    }
+}
+
+/// Like `broadcast_gate`, returning the veto that stopped it, if any.
+pub async fn find_veto(plugins: &[Plugin], event: Event) -> Option<Veto> {
+    for plugin in plugins.iter().filter(|p| p.subscribes_to(&event)) {
+        match plugin.run_script(&event).await {
+            Ok(value) => {
+                if let Some(reason) = veto_reason(&value) {
+                    return Some(Veto {
+                        plugin: plugin.manifest.name.clone(),
+                        event: event.name().to_string(),
+                        reason,
+                    });
+                }
+            }
+            Err(e) => {
+                eprintln!(
+                    "Plugin execution failure for '{}': {:?}",
+                    plugin.manifest.name, e
+                );
+            }
+        }
+    }
+    None
 }
 
 #[cfg(test)]
@@ -46,6 +46,41 @@ mod tests {
         1 / zero; // Runtime error: Division by zero
     ";
 
+    // Reads the fields of a PR merge event out of `event_data`
+    const MOCK_SCRIPT_MERGE: &str = r##"
+        let variant = event_data.keys()[0];
+        let data = event_data[variant];
+        variant + ":" + data.repo + "#" + data.pr_number
+    "##;
+
+    // Never finishes on its own; must be stopped by the plugin timeout
+    const MOCK_SCRIPT_LOOP: &str = r"
+        let i = 0;
+        loop {
+            i += 1;
+        }
+    ";
+
+    // Tries to reach a host that no manifest allows
+    const MOCK_SCRIPT_HTTP: &str = r#"
+        http_get("https://example.invalid/exfiltrate")
+    "#;
+
+    #[cfg(unix)]
+    const MOCK_BASH_SUCCESS: &str = r#"#!/bin/bash
+        # Event JSON arrives as the first argument
+        case "$1" in
+            *cli-command-execution-init*) echo "Event received: $1" ;;
+            *) echo "Unexpected event: $1" >&2; exit 1 ;;
+        esac
+    "#;
+
+    #[cfg(unix)]
+    const MOCK_BASH_FAIL: &str = r#"#!/bin/bash
+        echo "Intentional failure" >&2
+        exit 1
+    "#;
+
     // --- Helper Functions ---
 
     /// Creates a mock configuration structure in a temporary directory.
@@ -71,6 +106,23 @@ mod tests {
         plugin_path
     }
 
+    /// Like `setup_mock_plugin_env`, but with a `run.sh` script instead of `run.rhai`.
+    #[cfg(unix)]
+    fn setup_mock_bash_plugin_env(
+        base_dir: &Path,
+        plugin_name: &str,
+        script_content: &str,
+    ) -> PathBuf {
+        let plugin_path = base_dir.join(APP_NAME).join(PLUGINS_DIR).join(plugin_name);
+        fs::create_dir_all(&plugin_path).unwrap();
+        fs::write(plugin_path.join(MANIFEST_FILENAME), MOCK_MANIFEST).unwrap();
+        fs::write(plugin_path.join(BASH_SCRIPT_FILENAME), script_content).unwrap();
+        plugin_path
+    }
+
+    /// Serializes tests that change the process environment.
+    static ENV_LOCK: std::sync::Mutex<()> = std::sync::Mutex::new(());
+
     /// Helper to mock the environment for `dirs::config_dir()`
     fn mock_config_dir(temp_path: &Path) {
         // Wrapping the calls to std::env::set_var with unsafe {} to satisfy E0133
@@ -135,10 +187,248 @@ mod tests {
         assert!(err_msg.contains("Failed to parse TOML manifest"));
     }
 
+    #[test]
+    fn test_plugin_subscribes_to_all_events_by_default() {
+        let temp_dir = tempdir().unwrap();
+        let plugin_path = setup_mock_plugin_env(
+            temp_dir.path(),
+            "all-events",
+            MOCK_SCRIPT_SUCCESS,
+            MOCK_MANIFEST,
+        );
+
+        let plugin = Plugin::from_dir(&plugin_path).unwrap();
+
+        assert!(plugin.manifest.events.is_empty());
+        assert!(plugin.subscribes_to(&Event::CliCommandExecutionInit));
+        assert!(plugin.subscribes_to(&Event::CliCommandExecutionEnd { exit_code: 0 }));
+    }
+
+    #[test]
+    fn test_plugin_subscribes_to_listed_events_only() {
+        let temp_dir = tempdir().unwrap();
+        let manifest = format!(
+            "{MOCK_MANIFEST}\nevents = [\"cli-command-execution-end\", \"not-an-event\"]\n"
+        );
+        let plugin_path = setup_mock_plugin_env(
+            temp_dir.path(),
+            "some-events",
+            MOCK_SCRIPT_SUCCESS,
+            &manifest,
+        );
+
+        // Unknown event names only warn
+        let plugin = Plugin::from_dir(&plugin_path).unwrap();
+
+        assert!(plugin.subscribes_to(&Event::CliCommandExecutionEnd { exit_code: 0 }));
+        assert!(!plugin.subscribes_to(&Event::CliCommandExecutionInit));
+    }
+
+    #[test]
+    fn test_event_names_match_serialized_form() {
+        let events = [
+            Event::PluginRegistrationInit,
+            Event::PluginRegistered("x".into()),
+            Event::PluginRegistrationEnd,
+            Event::CliCommandExecutionInit,
+            Event::CliCommandExecutionRun {
+                command: "x".into(),
+                args: vec![],
+            },
+            Event::CliCommandExecutionEnd { exit_code: 0 },
+            Event::PrMergeInit {
+                repo: "o/r".into(),
+                pr_number: 1,
+                title: "x".into(),
+            },
+            Event::PrMerged {
+                repo: "o/r".into(),
+                pr_number: 1,
+                sha: Some("abc".into()),
+            },
+            Event::PrMergeFailed {
+                repo: "o/r".into(),
+                pr_number: 1,
+                reason: "x".into(),
+            },
+        ];
+
+        for event in &events {
+            let json = serde_json::to_value(event).unwrap();
+            let serialized = json
+                .as_str()
+                .map(String::from)
+                .or_else(|| json.as_object().and_then(|o| o.keys().next().cloned()))
+                .unwrap();
+            assert_eq!(serialized, event.name());
+            assert!(Event::NAMES.contains(&event.name()));
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_plugin_from_dir_bash() {
+        let temp_dir = tempdir().unwrap();
+        let plugin_path =
+            setup_mock_bash_plugin_env(temp_dir.path(), "bash-plugin", MOCK_BASH_SUCCESS);
+
+        let plugin = Plugin::from_dir(&plugin_path).unwrap();
+
+        assert_eq!(plugin.kind, ScriptKind::Bash);
+        assert!(plugin.script_path.ends_with(BASH_SCRIPT_FILENAME));
+    }
+
+    #[test]
+    fn test_allowed_hosts_warning_only_for_bash() {
+        let manifest: Manifest = toml::from_str(&format!(
+            "{MOCK_MANIFEST}\nallowed_hosts = [\"api.example.com\"]\n"
+        ))
+        .unwrap();
+
+        let warning = unenforced_hosts_warning(&manifest, ScriptKind::Bash).unwrap();
+        assert!(warning.contains("test-plugin"));
+        assert!(warning.contains("api.example.com"));
+        assert_eq!(unenforced_hosts_warning(&manifest, ScriptKind::Rhai), None);
+
+        let open: Manifest = toml::from_str(MOCK_MANIFEST).unwrap();
+        assert_eq!(unenforced_hosts_warning(&open, ScriptKind::Bash), None);
+    }
+
+    #[test]
+    fn test_plugin_from_dir_prefers_rhai() {
+        let temp_dir = tempdir().unwrap();
+        let plugin_path = setup_mock_plugin_env(
+            temp_dir.path(),
+            "both-plugin",
+            MOCK_SCRIPT_SUCCESS,
+            MOCK_MANIFEST,
+        );
+        fs::write(plugin_path.join(BASH_SCRIPT_FILENAME), "exit 1\n").unwrap();
+
+        let plugin = Plugin::from_dir(&plugin_path).unwrap();
+
+        assert_eq!(plugin.kind, ScriptKind::Rhai);
+    }
+
+    // --- Plugin::validate Tests ---
+
+    #[test]
+    fn test_plugin_validate_success() {
+        let temp_dir = tempdir().unwrap();
+        let plugin_path =
+            setup_mock_plugin_env(temp_dir.path(), "valid", MOCK_SCRIPT_SUCCESS, MOCK_MANIFEST);
+
+        let plugin = Plugin::from_dir(&plugin_path).unwrap();
+
+        assert!(plugin.validate().is_ok());
+    }
+
+    #[test]
+    fn test_plugin_validate_reports_syntax_error_position() {
+        let temp_dir = tempdir().unwrap();
+        let plugin_path = setup_mock_plugin_env(
+            temp_dir.path(),
+            "syntax",
+            "let a = 1;\nlet b = ;\n",
+            MOCK_MANIFEST,
+        );
+
+        let plugin = Plugin::from_dir(&plugin_path).unwrap();
+        let err_msg = plugin.validate().unwrap_err().to_string();
+
+        assert!(err_msg.contains("line 2"), "{err_msg}");
+    }
+
+    #[test]
+    fn test_plugin_validate_does_not_run_script() {
+        let temp_dir = tempdir().unwrap();
+        let plugin_path =
+            setup_mock_plugin_env(temp_dir.path(), "no-run", MOCK_SCRIPT_FAIL, MOCK_MANIFEST);
+
+        let plugin = Plugin::from_dir(&plugin_path).unwrap();
+
+        // Division by zero only fails at runtime
+        assert!(plugin.validate().is_ok());
+    }
+
+    #[test]
+    fn test_plugin_validate_empty_manifest_field() {
+        let temp_dir = tempdir().unwrap();
+        let manifest = MOCK_MANIFEST.replace("A plugin for testing", " ");
+        let plugin_path =
+            setup_mock_plugin_env(temp_dir.path(), "empty", MOCK_SCRIPT_SUCCESS, &manifest);
+
+        let plugin = Plugin::from_dir(&plugin_path).unwrap();
+        let err_msg = plugin.validate().unwrap_err().to_string();
+
+        assert!(err_msg.contains("description"));
+    }
+
+    // --- cprint Tests ---
+
+    #[test]
+    fn test_parse_color_names_and_hex() {
+        use colored::Color;
+
+        assert_eq!(color::parse_color("red"), Some(Color::Red));
+        assert_eq!(color::parse_color("Bright_Red"), Some(Color::BrightRed));
+        assert_eq!(color::parse_color("bright-blue"), Some(Color::BrightBlue));
+        assert_eq!(color::parse_color("gray"), Some(Color::BrightBlack));
+        assert_eq!(
+            color::parse_color("#ff8800"),
+            Some(Color::TrueColor {
+                r: 255,
+                g: 136,
+                b: 0
+            })
+        );
+        assert_eq!(
+            color::parse_color("#f80"),
+            Some(Color::TrueColor {
+                r: 255,
+                g: 136,
+                b: 0
+            })
+        );
+        assert_eq!(color::parse_color("#ff88"), None);
+        assert_eq!(color::parse_color("#gg0000"), None);
+        assert_eq!(color::parse_color("#é1234"), None);
+        assert_eq!(color::parse_color("#é1"), None);
+        assert_eq!(color::parse_color("chartreuse"), None);
+    }
+
+    #[tokio::test]
+    async fn test_plugin_run_script_cprint_helpers() {
+        let temp_dir = tempdir().unwrap();
+        let plugin_path = setup_mock_plugin_env(
+            temp_dir.path(),
+            "colors",
+            r##"
+                cprint("hex", "#ff8800");
+                cprint("unknown", "not-a-color");
+                cprint_bg("banner", "bright_white", "blue");
+                true
+            "##,
+            MOCK_MANIFEST,
+        );
+
+        let plugin = Plugin::from_dir(&plugin_path).unwrap();
+
+        assert!(
+            plugin
+                .run_script(&Event::CliCommandExecutionInit)
+                .await
+                .is_ok()
+        );
+    }
+
     // --- discover_plugins Tests ---
 
     #[test]
     fn test_discover_plugins_none_found() {
+        let _guard = ENV_LOCK
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner);
         let temp_dir = tempdir().unwrap();
         mock_config_dir(temp_dir.path());
 
@@ -148,6 +438,9 @@ mod tests {
 
     #[test]
     fn test_discover_plugins_multiple_found() {
+        let _guard = ENV_LOCK
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner);
         let temp_dir = tempdir().unwrap();
         mock_config_dir(temp_dir.path());
 
@@ -187,6 +480,36 @@ mod tests {
         assert_eq!(plugins[1].manifest.name, "b-second-plugin");
     }
 
+    #[test]
+    fn test_discover_plugins_env_override() {
+        let _guard = ENV_LOCK
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner);
+        let config_dir = tempdir().unwrap();
+        mock_config_dir(config_dir.path());
+
+        // Plugins live directly in the override dir, not under github-bot/plugins
+        let override_dir = tempdir().unwrap();
+        let plugin_path = override_dir.path().join("custom");
+        fs::create_dir_all(&plugin_path).unwrap();
+        fs::write(plugin_path.join(MANIFEST_FILENAME), MOCK_MANIFEST).unwrap();
+        fs::write(plugin_path.join(SCRIPT_FILENAME), MOCK_SCRIPT_SUCCESS).unwrap();
+
+        unsafe {
+            std::env::set_var(PLUGINS_DIR_ENV, override_dir.path());
+        }
+        let dir = plugins_dir();
+        let plugins = discover_plugins();
+        unsafe {
+            std::env::remove_var(PLUGINS_DIR_ENV);
+        }
+
+        assert_eq!(dir.unwrap(), override_dir.path());
+        let plugins = plugins.unwrap();
+        assert_eq!(plugins.len(), 1);
+        assert_eq!(plugins[0].manifest.name, "test-plugin");
+    }
+
     // --- Plugin::run_script Tests ---
 
     #[tokio::test]
@@ -209,6 +532,184 @@ mod tests {
         assert!(result.is_ok());
     }
 
+    #[tokio::test]
+    async fn test_plugin_run_script_returns_value() {
+        let temp_dir = tempdir().unwrap();
+        let plugin_path = setup_mock_plugin_env(temp_dir.path(), "value", "40 + 2", MOCK_MANIFEST);
+
+        let plugin = Plugin::from_dir(&plugin_path).unwrap();
+        let value = plugin
+            .run_script(&Event::CliCommandExecutionInit)
+            .await
+            .unwrap();
+
+        assert_eq!(value.as_int(), Ok(42));
+        assert!(!is_veto(&value));
+    }
+
+    #[tokio::test]
+    async fn test_broadcast_event_reports_outcomes() {
+        let temp_dir = tempdir().unwrap();
+        let ok_path =
+            setup_mock_plugin_env(temp_dir.path(), "ok", MOCK_SCRIPT_SUCCESS, MOCK_MANIFEST);
+        let fail_manifest = MOCK_MANIFEST.replace("test-plugin", "failing-plugin");
+        let fail_path =
+            setup_mock_plugin_env(temp_dir.path(), "fail", MOCK_SCRIPT_FAIL, &fail_manifest);
+        let quiet_manifest = format!(
+            "{}\nevents = [\"cli-command-execution-end\"]\n",
+            MOCK_MANIFEST.replace("test-plugin", "quiet-plugin")
+        );
+        let quiet_path = setup_mock_plugin_env(
+            temp_dir.path(),
+            "quiet",
+            MOCK_SCRIPT_SUCCESS,
+            &quiet_manifest,
+        );
+
+        let plugins = [
+            Plugin::from_dir(&ok_path).unwrap(),
+            Plugin::from_dir(&fail_path).unwrap(),
+            Plugin::from_dir(&quiet_path).unwrap(),
+        ];
+
+        let outcomes = broadcast_event(&plugins, Event::CliCommandExecutionInit).await;
+
+        // The quiet plugin doesn't subscribe, so it has no outcome
+        assert_eq!(outcomes.len(), 2);
+        assert_eq!(outcomes[0].name, "test-plugin");
+        assert!(outcomes[0].success);
+        assert_eq!(outcomes[0].error, None);
+        assert_eq!(outcomes[1].name, "failing-plugin");
+        assert!(!outcomes[1].success);
+        assert!(
+            outcomes[1]
+                .error
+                .as_deref()
+                .unwrap()
+                .contains("Division by zero")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_broadcast_gate_stops_on_veto() {
+        let temp_dir = tempdir().unwrap();
+        let allow = setup_mock_plugin_env(temp_dir.path(), "allow", "true", MOCK_MANIFEST);
+        let deny_manifest = MOCK_MANIFEST.replace("test-plugin", "deny-plugin");
+        let deny = setup_mock_plugin_env(
+            temp_dir.path(),
+            "deny",
+            r#"veto("not today")"#,
+            &deny_manifest,
+        );
+
+        let allow = Plugin::from_dir(&allow).unwrap();
+        let deny = Plugin::from_dir(&deny).unwrap();
+
+        let event = Event::CliCommandExecutionRun {
+            command: "merge".into(),
+            args: vec![],
+        };
+
+        assert!(
+            broadcast_gate(std::slice::from_ref(&allow), event.clone())
+                .await
+                .is_ok()
+        );
+
+        let err_msg = broadcast_gate(&[allow, deny], event)
+            .await
+            .unwrap_err()
+            .to_string();
+        assert!(err_msg.contains("deny-plugin"));
+        assert!(err_msg.contains("vetoed"));
+        assert!(err_msg.contains("not today"));
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_only_explicit_vetoes_block() {
+        let temp_dir = tempdir().unwrap();
+        let event = Event::CliCommandExecutionRun {
+            command: "merge".into(),
+            args: vec![],
+        };
+
+        // Older scripts may happen to end in `false`
+        let falsy = setup_mock_plugin_env(temp_dir.path(), "falsy", "1 > 2", MOCK_MANIFEST);
+        let falsy = Plugin::from_dir(&falsy).unwrap();
+        assert!(find_veto(&[falsy], event.clone()).await.is_none());
+
+        let bash = temp_dir.path().join("bash-veto");
+        fs::create_dir_all(&bash).unwrap();
+        fs::write(bash.join(MANIFEST_FILENAME), MOCK_MANIFEST).unwrap();
+        fs::write(
+            bash.join(BASH_SCRIPT_FILENAME),
+            r#"echo '{"veto": true, "reason": "frozen"}'"#,
+        )
+        .unwrap();
+        let bash = Plugin::from_dir(&bash).unwrap();
+        let veto = find_veto(&[bash], event).await.unwrap();
+        assert_eq!(veto.plugin, "test-plugin");
+        assert_eq!(veto.reason, "frozen");
+    }
+
+    #[tokio::test]
+    async fn test_plugin_run_script_end_event() {
+        let temp_dir = tempdir().unwrap();
+        let plugin_path = setup_mock_plugin_env(
+            temp_dir.path(),
+            "end-test",
+            MOCK_SCRIPT_SUCCESS,
+            MOCK_MANIFEST,
+        );
+
+        let plugin = Plugin::from_dir(&plugin_path).unwrap();
+        let event = Event::CliCommandExecutionEnd { exit_code: 1 };
+
+        let result = plugin.run_script(&event).await;
+
+        assert!(result.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_plugin_run_script_merge_events() {
+        let temp_dir = tempdir().unwrap();
+        let plugin_path = setup_mock_plugin_env(
+            temp_dir.path(),
+            "merge-test",
+            MOCK_SCRIPT_MERGE,
+            MOCK_MANIFEST,
+        );
+
+        let plugin = Plugin::from_dir(&plugin_path).unwrap();
+        let events = [
+            Event::PrMergeInit {
+                repo: "owner/repo".into(),
+                pr_number: 7,
+                title: "Bump serde".into(),
+            },
+            Event::PrMerged {
+                repo: "owner/repo".into(),
+                pr_number: 7,
+                sha: Some("abc123".into()),
+            },
+            Event::PrMergeFailed {
+                repo: "owner/repo".into(),
+                pr_number: 7,
+                reason: "checks failed".into(),
+            },
+        ];
+
+        for event in &events {
+            let value = plugin.run_script(event).await.unwrap();
+            // The script echoes back "<variant>:<repo>#<pr_number>"
+            assert_eq!(
+                value.into_string().unwrap(),
+                format!("{}:owner/repo#7", event.name())
+            );
+        }
+    }
+
     #[tokio::test]
     async fn test_plugin_run_script_failure() {
         let temp_dir = tempdir().unwrap();
@@ -231,6 +732,141 @@ mod tests {
         // Check for the error message that Rhai generates for division by zero
         assert!(err_msg.contains("Division by zero"));
     }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_plugin_run_bash_script_success() {
+        let temp_dir = tempdir().unwrap();
+        let plugin_path =
+            setup_mock_bash_plugin_env(temp_dir.path(), "bash-run", MOCK_BASH_SUCCESS);
+
+        let plugin = Plugin::from_dir(&plugin_path).unwrap();
+        let result = plugin.run_script(&Event::CliCommandExecutionInit).await;
+
+        assert!(result.is_ok(), "{result:?}");
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_plugin_run_bash_script_failure() {
+        let temp_dir = tempdir().unwrap();
+        let plugin_path = setup_mock_bash_plugin_env(temp_dir.path(), "bash-fail", MOCK_BASH_FAIL);
+
+        let plugin = Plugin::from_dir(&plugin_path).unwrap();
+        let result = plugin.run_script(&Event::CliCommandExecutionInit).await;
+
+        let err_msg = result.unwrap_err().to_string();
+        assert!(err_msg.contains("Intentional failure"));
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_plugin_run_bash_script_timeout() {
+        let temp_dir = tempdir().unwrap();
+        let plugin_path = setup_mock_bash_plugin_env(temp_dir.path(), "bash-loop", "sleep 30\n");
+
+        let mut plugin = Plugin::from_dir(&plugin_path).unwrap();
+        plugin.timeout = std::time::Duration::from_millis(200);
+
+        let result = plugin.run_script(&Event::CliCommandExecutionInit).await;
+
+        assert!(result.unwrap_err().to_string().contains("timed out"));
+    }
+
+    #[tokio::test]
+    async fn test_plugin_http_blocked_without_allowlist() {
+        let temp_dir = tempdir().unwrap();
+        let plugin_path = setup_mock_plugin_env(
+            temp_dir.path(),
+            "http-test",
+            MOCK_SCRIPT_HTTP,
+            MOCK_MANIFEST,
+        );
+
+        let plugin = Plugin::from_dir(&plugin_path).unwrap();
+        let result = plugin.run_script(&Event::CliCommandExecutionInit).await;
+
+        let err_msg = result.unwrap_err().to_string();
+        assert!(err_msg.contains("blocked"));
+        assert!(err_msg.contains("allowed_hosts"));
+    }
+
+    #[test]
+    fn test_http_allowlist_matching() {
+        let allowed = vec!["api.github.com".to_string(), "*.example.com".to_string()];
+
+        assert!(http::is_allowed(&allowed, "api.github.com"));
+        assert!(http::is_allowed(&allowed, "API.GitHub.com"));
+        assert!(http::is_allowed(&allowed, "example.com"));
+        assert!(http::is_allowed(&allowed, "hooks.example.com"));
+        assert!(!http::is_allowed(&allowed, "github.com"));
+        assert!(!http::is_allowed(&allowed, "badexample.com"));
+        assert!(!http::is_allowed(&[], "api.github.com"));
+    }
+
+    #[test]
+    fn test_http_get_blocks_redirect_to_disallowed_host() {
+        let mut server = mockito::Server::new();
+        let redirect = server
+            .mock("GET", "/start")
+            .with_status(302)
+            .with_header("location", "http://evil.invalid/exfiltrate")
+            .create();
+
+        let allowed = vec!["127.0.0.1".to_string()];
+        let err = http::http_get(
+            "redirect-test",
+            &allowed,
+            &format!("{}/start", server.url()),
+        )
+        .unwrap_err()
+        .to_string();
+
+        redirect.assert();
+        assert!(err.contains("redirect to 'evil.invalid' blocked"), "{err}");
+    }
+
+    #[test]
+    fn test_http_get_follows_redirect_to_allowed_host() {
+        let mut server = mockito::Server::new();
+        server
+            .mock("GET", "/start")
+            .with_status(302)
+            .with_header("location", "/final")
+            .create();
+        server.mock("GET", "/final").with_body("ok").create();
+
+        let allowed = vec!["127.0.0.1".to_string()];
+        let body = http::http_get(
+            "redirect-test",
+            &allowed,
+            &format!("{}/start", server.url()),
+        )
+        .unwrap();
+
+        assert_eq!(body, "ok");
+    }
+
+    #[tokio::test]
+    async fn test_plugin_run_script_timeout() {
+        let temp_dir = tempdir().unwrap();
+        let plugin_path = setup_mock_plugin_env(
+            temp_dir.path(),
+            "loop-test",
+            MOCK_SCRIPT_LOOP,
+            MOCK_MANIFEST,
+        );
+
+        let mut plugin = Plugin::from_dir(&plugin_path).unwrap();
+        plugin.timeout = std::time::Duration::from_millis(200);
+
+        let start = std::time::Instant::now();
+        let result = plugin.run_script(&Event::CliCommandExecutionInit).await;
+
+        assert!(result.is_err());
+        assert!(result.unwrap_err().to_string().contains("timed out"));
+        assert!(start.elapsed() < std::time::Duration::from_secs(5));
+    }
 }
 
 /*
@@ -1,5 +1,6 @@
 use anyhow::{Context, Result, bail};
 use dialoguer::Input;
+use std::path::Path;
 use std::process::Command;
 
 /// Check if current directory is inside a git repo
@@ -21,6 +22,23 @@ pub fn remoteurl() -> Result<String> {
     Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
 }
 
+/// Ask a yes/no question, defaulting to no.
+///
+/// `yes` answers it up front (`--yes`); without a terminal to ask on, the answer is no.
+pub fn confirm(question: &str, yes: bool) -> Result<bool> {
+    use std::io::IsTerminal;
+
+    if yes {
+        Ok(true)
+    } else if std::io::stdin().is_terminal() {
+        Ok(cliclack::confirm(question)
+            .initial_value(false)
+            .interact()?)
+    } else {
+        Ok(false)
+    }
+}
+
 /// Get the target repo
 pub fn get_repo(target: Option<String>) -> Result<String> {
     let repo = if let Some(t) = target {
@@ -33,5 +51,198 @@ pub fn get_repo(target: Option<String>) -> Result<String> {
             .interact_text()?
     };
 
-    Ok(repo)
+    normalize_repo(&repo)
+}
+
+/// Turn `owner/repo`, an HTTPS URL, or an SSH remote into `owner/repo`.
+///
+/// Accepts `git@github.com:owner/repo.git`, `ssh://git@github.com/owner/repo`,
+/// `https://github.com/owner/repo/` and `github.com/owner/repo`; anything after
+/// the repo name in a URL (e.g. `/pull/1`) is ignored.
+pub fn normalize_repo(input: &str) -> Result<String> {
+    let trimmed = input.trim().trim_end_matches('/');
+
+    let (path, is_url) = if let Some((_, rest)) = trimmed.split_once("://") {
+        // Drop the host (and any user@ before it)
+        (rest.split_once('/').map_or("", |(_, path)| path), true)
+    } else if let Some(rest) = trimmed.strip_prefix("git@") {
+        (rest.split_once(':').map_or("", |(_, path)| path), true)
+    } else if let Some(rest) = trimmed.strip_prefix("github.com/") {
+        (rest, true)
+    } else {
+        (trimmed, false)
+    };
+
+    let mut parts = path.split('/');
+    let (Some(owner), Some(name)) = (parts.next(), parts.next()) else {
+        bail!("Invalid repository '{input}': expected owner/repo or a GitHub URL");
+    };
+    let name = name.strip_suffix(".git").unwrap_or(name);
+
+    if owner.is_empty() || name.is_empty() || (!is_url && parts.next().is_some()) {
+        bail!("Invalid repository '{input}': expected owner/repo or a GitHub URL");
+    }
+
+    Ok(format!("{owner}/{name}"))
+}
+
+/// Parse a repo list: one `owner/repo` (or URL) per line, blank lines and
+/// `#` comments ignored, duplicates dropped.
+///
+/// Returns the repos and, separately, one message per invalid line.
+#[must_use]
+pub fn parse_repo_list(content: &str) -> (Vec<String>, Vec<String>) {
+    let mut repos = Vec::new();
+    let mut invalid = Vec::new();
+    for (number, line) in content.lines().enumerate() {
+        let line = line
+            .split_once('#')
+            .map_or(line, |(before, _)| before)
+            .trim();
+        if line.is_empty() {
+            continue;
+        }
+        match normalize_repo(line) {
+            Ok(repo) if !repos.contains(&repo) => repos.push(repo),
+            Ok(_) => {}
+            Err(e) => invalid.push(format!("line {}: {e}", number + 1)),
+        }
+    }
+    (repos, invalid)
+}
+
+/// Read a `--repo-file` (see `parse_repo_list`).
+///
+/// Invalid lines are reported on stderr and skipped so the rest still run.
+pub fn read_repo_file(path: &Path) -> Result<Vec<String>> {
+    let content = std::fs::read_to_string(path)
+        .with_context(|| format!("Could not read repo file {}", path.display()))?;
+    let (repos, invalid) = parse_repo_list(&content);
+    for msg in invalid {
+        eprintln!("Skipping {}: {msg}", path.display());
+    }
+    Ok(repos)
+}
+
+/// Add the repos of a `--repo-file` to `repos`, skipping ones already there.
+pub fn extend_with_repo_file(repos: &mut Vec<String>, path: &Path) -> Result<()> {
+    for repo in read_repo_file(path)? {
+        if !repos.contains(&repo) {
+            repos.push(repo);
+        }
+    }
+    Ok(())
+}
+
+/// Shell-style glob match: `*` matches any run of characters, `?` exactly one.
+#[must_use]
+pub fn glob_match(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let text: Vec<char> = text.chars().collect();
+
+    let (mut p, mut t) = (0, 0);
+    // Where to resume after the last `*`: (pattern index, text index)
+    let mut star: Option<(usize, usize)> = None;
+
+    while t < text.len() {
+        if p < pattern.len() && (pattern[p] == '?' || pattern[p] == text[t]) {
+            p += 1;
+            t += 1;
+        } else if p < pattern.len() && pattern[p] == '*' {
+            star = Some((p, t));
+            p += 1;
+        } else if let Some((sp, st)) = star {
+            // Let the `*` swallow one more character and retry
+            p = sp + 1;
+            t = st + 1;
+            star = Some((sp, st + 1));
+        } else {
+            return false;
+        }
+    }
+
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn normalize_bare_repo() {
+        assert_eq!(normalize_repo("owner/repo").unwrap(), "owner/repo");
+        assert_eq!(normalize_repo(" owner/repo/ ").unwrap(), "owner/repo");
+    }
+
+    #[test]
+    fn normalize_ssh_urls() {
+        assert_eq!(
+            normalize_repo("git@github.com:owner/repo.git").unwrap(),
+            "owner/repo"
+        );
+        assert_eq!(
+            normalize_repo("ssh://git@github.com/owner/repo.git").unwrap(),
+            "owner/repo"
+        );
+    }
+
+    #[test]
+    fn normalize_https_urls() {
+        assert_eq!(
+            normalize_repo("https://github.com/owner/repo.git").unwrap(),
+            "owner/repo"
+        );
+        assert_eq!(
+            normalize_repo("https://github.com/owner/repo/").unwrap(),
+            "owner/repo"
+        );
+        assert_eq!(
+            normalize_repo("https://github.com/owner/repo/pull/42").unwrap(),
+            "owner/repo"
+        );
+        assert_eq!(
+            normalize_repo("github.com/owner/repo").unwrap(),
+            "owner/repo"
+        );
+    }
+
+    #[test]
+    fn repo_list_skips_comments_and_reports_bad_lines() {
+        let (repos, invalid) = parse_repo_list(
+            "# curated\nacme/app\n\n  https://github.com/acme/web  # the site\nnot-a-repo\nacme/app\n",
+        );
+        assert_eq!(repos, ["acme/app", "acme/web"]);
+        assert_eq!(invalid.len(), 1);
+        assert!(invalid[0].starts_with("line 5:"), "{invalid:?}");
+    }
+
+    #[test]
+    fn normalize_rejects_malformed() {
+        assert!(normalize_repo("repo").is_err());
+        assert!(normalize_repo("owner/repo/extra").is_err());
+        assert!(normalize_repo("https://github.com/owner").is_err());
+        assert!(normalize_repo("").is_err());
+    }
+
+    #[test]
+    fn repo_file_extends_without_duplicates() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("repos.txt");
+        std::fs::write(&path, "acme/app\nacme/web\n").unwrap();
+
+        let mut repos = vec!["acme/api".to_string(), "acme/web".to_string()];
+        extend_with_repo_file(&mut repos, &path).unwrap();
+        assert_eq!(repos, ["acme/api", "acme/web", "acme/app"]);
+    }
+
+    #[test]
+    fn glob_match_wildcards() {
+        assert!(glob_match("api-*", "api-gateway"));
+        assert!(glob_match("*-rs", "github-rs"));
+        assert!(glob_match("a?c", "abc"));
+        assert!(glob_match("*", ""));
+        assert!(glob_match("*bot*", "github-bot-lib"));
+        assert!(!glob_match("api-*", "web-api"));
+        assert!(!glob_match("a?c", "ac"));
+    }
 }